@@ -17,6 +17,10 @@ layout(set = 2, binding = 2) uniform sampler brdf_sampler;
 layout(set = 2, binding = 3) uniform texture2D main_map;
 layout(set = 2, binding = 4) uniform texture2D normal_map;
 layout(set = 2, binding = 5) uniform texture2D metallic_roughness_map;
+// Samplers from each texture's import settings.
+layout(set = 2, binding = 6) uniform sampler main_sampler;
+layout(set = 2, binding = 7) uniform sampler normal_sampler;
+layout(set = 2, binding = 8) uniform sampler metallic_roughness_sampler;
 
 layout(set = 3, binding = 0) uniform textureCube irradiance_cube_map;
 layout(set = 3, binding = 1) uniform textureCube spec_cube_map;
@@ -79,13 +83,13 @@ void main() {
     // }
     // return;
 
-    vec3 main_color = texture(sampler2D(main_map, tex_sampler), i_uv).rgb * color.rgb;
+    vec3 main_color = texture(sampler2D(main_map, main_sampler), i_uv).rgb * color.rgb;
     
-    vec2 metallic_roughness = texture(sampler2D(metallic_roughness_map, tex_sampler), i_uv).xy;
+    vec2 metallic_roughness = texture(sampler2D(metallic_roughness_map, metallic_roughness_sampler), i_uv).xy;
     float metallic = mix(metallic_roughness.x, pbr_info.x, pbr_info.z);
     float roughness = mix(metallic_roughness.y, pbr_info.y, pbr_info.w);
     
-    vec3 normal = texture(sampler2D(normal_map, tex_sampler), i_uv).rgb;
+    vec3 normal = texture(sampler2D(normal_map, normal_sampler), i_uv).rgb;
     normal = normal * 2.0 - 1.0;
     vec3 V = normalize(camera_pos.xyz - i_position.xyz);
     vec3 N = normalize(i_normal);
@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) in vec4 i_position;
+
+layout (location = 0) out float outColor;
+
+layout(set = 1, binding = 0) uniform Uniforms {
+    mat4 view_proj;
+    vec4 light_pos;
+};
+
+void main() {
+}
@@ -0,0 +1,2 @@
+shadow_uniform.vert.glsl
+shadow_uniform.frag.glsl
\ No newline at end of file
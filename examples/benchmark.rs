@@ -3,7 +3,7 @@ use nalgebra_glm::{Quat, Vec3, Vec4};
 
 use winit::{
     dpi::LogicalSize,
-    event::{Event, WindowEvent, VirtualKeyCode},
+    event::{Event, WindowEvent},
     event_loop::ControlFlow,
 };
 
@@ -11,7 +11,7 @@ use harmony::scene::components::{CameraData, DirectionalLightData, LightType, Me
 use harmony::scene::{resources::DeltaTime, Scene};
 use harmony::{
     graphics::resources::{ProbeFormat, ProbeQuality},
-    AssetManager, WinitState, core::input::Input,
+    AssetManager, FullscreenMode, WinitState, core::{input::{ActionMap, Input}, Stage, StagedSchedule},
 };
 
 struct WindowSize {
@@ -40,33 +40,9 @@ fn create_camera_fps_system() -> Box<dyn Schedulable> {
         .with_query(<Write<CameraData>>::query())
         .build(|_, mut world, (delta_time, input), camera_query| {
             for mut camera in camera_query.iter_mut(&mut world) {
-                
-                let forward = input.is_key_down(VirtualKeyCode::W);
-                let back = input.is_key_down(VirtualKeyCode::S);
-                let right = input.is_key_down(VirtualKeyCode::D);
-                let left = input.is_key_down(VirtualKeyCode::A);
-                
-                let frontv = Vec3::z();
-                let rightv = Vec3::x();
-        
-                let mut movement = Vec3::zeros();
-        
-                if forward {
-                    movement = movement + frontv
-                }
-        
-                if back {
-                    movement = movement - frontv
-                }
-        
-                if right {
-                    movement = movement - rightv
-                }
-        
-                if left {
-                    movement = movement + rightv
-                }
-                
+                // Bindings are loaded from assets/input.ron.
+                let movement = Vec3::new(input.axis("move_x"), 0.0, input.axis("move_z"));
+
                 camera.yaw -= input.mouse_delta.x * 0.5 * delta_time.0;
                 camera.pitch -= input.mouse_delta.y * 0.5 * delta_time.0;  
                 camera.pitch = camera
@@ -116,10 +92,10 @@ fn create_rotate_system() -> Box<dyn Schedulable> {
 
 impl harmony::AppState for AppState {
     fn load(&mut self, app: &mut harmony::Application) {
-        let scheduler_builder = Schedule::builder()
-            // .add_system(create_camera_fps_system()) // Uncomment for a fps style camera
-            .add_system(create_rotate_system());
-        app.current_scene = Scene::new(None, Some(scheduler_builder));
+        let mut schedule = StagedSchedule::game();
+        // schedule.add_system(Stage::Update, create_camera_fps_system()); // Uncomment for a fps style camera
+        schedule.add_system(Stage::Update, create_rotate_system());
+        app.current_scene = Scene::new(None, Some(schedule));
 
         let mesh_handle = {
             // asset manager lets you retrieve files from disk.
@@ -143,7 +119,7 @@ impl harmony::AppState for AppState {
         let scale = 3.0;
         for x in 0..size {
             for y in 0..size {
-                let mut transform = Transform::new(app);
+                let mut transform = Transform::default();
                 transform.position.x = x as f32 * scale;
                 transform.position.y = y as f32 * scale;
                 transform.update();
@@ -182,7 +158,7 @@ impl harmony::AppState for AppState {
         );
 
         // Add directional light to our scene.
-        let light_transform = Transform::new(app);
+        let light_transform = Transform::default();
         harmony::scene::entities::light::create(
             &mut app.current_scene.world,
             LightType::Directional(DirectionalLightData {
@@ -221,6 +197,32 @@ impl harmony::AppState for AppState {
         );
         harmony::scene::entities::camera::create(&mut app.current_scene.world, camera_data);
     }
+
+    fn update(&mut self, app: &mut harmony::Application) {
+        let (toggle_fullscreen, toggle_mouse, relative_mouse) = {
+            let input = app.resources.get::<Input>().unwrap();
+            (
+                input.action_pressed("toggle_fullscreen"),
+                input.action_pressed("toggle_mouse"),
+                input.is_relative_mouse(),
+            )
+        };
+
+        if toggle_fullscreen {
+            let mode = match app.config().fullscreen {
+                FullscreenMode::Windowed => FullscreenMode::Borderless,
+                _ => FullscreenMode::Windowed,
+            };
+            app.set_fullscreen(mode);
+        }
+
+        // Captures the mouse for the fps style camera.
+        if toggle_mouse {
+            if let Err(error) = app.set_relative_mouse(!relative_mouse) {
+                log::warn!("Couldn't grab the cursor: {}", error);
+            }
+        }
+    }
 }
 
 fn main() {
@@ -236,7 +238,19 @@ fn main() {
 
     // Tell harmony where our asset path is.
     let asset_path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/").to_string();
-    let mut application = harmony::Application::new(wb, &event_loop, asset_path, vec![]);
+    let input_path = format!("{}input.ron", asset_path);
+    let mut application = harmony::Application::new(
+        wb,
+        &event_loop,
+        asset_path,
+        vec![],
+        harmony::AppConfig::default(),
+    )
+    .expect("Couldn't create the application.");
+    match ActionMap::load(input_path) {
+        Ok(action_map) => application.resources.get_mut::<Input>().unwrap().set_action_map(action_map),
+        Err(error) => log::warn!("Couldn't load input bindings: {}", error),
+    }
     let mut app_state = AppState::new();
     // Call application load to have harmony load all the required assets.
     application.load(&mut app_state);
@@ -247,15 +261,14 @@ fn main() {
         application.run(&mut app_state, &event, control_flow);
         match event {
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                winit::event::WindowEvent::KeyboardInput {
+                WindowEvent::KeyboardInput {
                     input:
                         winit::event::KeyboardInput {
                             virtual_keycode: Some(winit::event::VirtualKeyCode::Escape),
                             ..
                         },
                     ..
-                } => *control_flow = winit::event_loop::ControlFlow::Exit,
+                } => *control_flow = ControlFlow::Exit,
                 _ => {}
             },
             _ => (),
@@ -0,0 +1,23 @@
+use harmony::BundleBuilder;
+
+// Packs an asset folder into a single bundle file.
+// Usage: cargo run --example bundle -- <asset folder> <bundle file> [--no-compression]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let paths: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    if paths.len() != 2 {
+        eprintln!("Usage: bundle <asset folder> <bundle file> [--no-compression]");
+        std::process::exit(1);
+    }
+
+    let mut builder = BundleBuilder::new();
+    builder.compress(!args.iter().any(|arg| arg == "--no-compression"));
+    builder
+        .add_directory(paths[0])
+        .expect("Couldn't read the asset folder.");
+    builder
+        .write_to_file(paths[1])
+        .expect("Couldn't write the bundle.");
+
+    println!("Packed {} files into {}.", builder.len(), paths[1]);
+}
@@ -0,0 +1,112 @@
+use log;
+use nalgebra_glm::Vec3;
+
+use harmony::graphics::resources::{ProbeFormat, ProbeQuality};
+use harmony::scene::components::{CameraData, DirectionalLightData, LightType, Mesh, Transform};
+use harmony::AssetManager;
+
+const FRAME_WIDTH: u32 = 512;
+const FRAME_HEIGHT: u32 = 512;
+// Give up waiting for the cube after this many frames.
+const MAX_LOADING_FRAMES: u32 = 600;
+
+struct AppState {
+    mesh: Option<Mesh>,
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        Self { mesh: None }
+    }
+}
+
+impl harmony::AppState for AppState {
+    fn load(&mut self, app: &mut harmony::Application) {
+        let mesh_handle = {
+            let asset_manager = app.resources.get_mut::<AssetManager>().unwrap();
+            asset_manager.get_mesh("example/meshes/cube/cube.gltf")
+        };
+        self.mesh = Some(Mesh::new(mesh_handle.clone()));
+
+        let mut transform = Transform::default();
+        transform.rotate_on_y(0.5);
+        transform.rotate_on_x(0.5);
+        app.current_scene
+            .world
+            .insert((), vec![(Mesh::new(mesh_handle), transform)]);
+
+        let clear_color =
+            harmony::graphics::material::Skybox::create_clear_color(Vec3::new(0.2, 0.2, 0.2));
+        app.current_scene.world.insert((), vec![(clear_color,)]);
+
+        // Setup probe for PBR
+        harmony::scene::entities::probe::create(
+            app,
+            Vec3::zeros(),
+            ProbeQuality::Low,
+            ProbeFormat::RGBA16,
+        );
+
+        let light_transform = Transform::default();
+        harmony::scene::entities::light::create(
+            &mut app.current_scene.world,
+            LightType::Directional(DirectionalLightData {
+                direction: Vec3::new(0.0, 1.0, 0.0),
+                color: Vec3::new(1.0, 1.0, 1.0),
+                intensity: 10.0,
+            }),
+            light_transform,
+        );
+
+        let actual_window_size = app.get_window_actual_size();
+        let mut camera_data = CameraData::new_perspective(
+            70.0,
+            actual_window_size.width,
+            actual_window_size.height,
+            1.0,
+            4000.0,
+        );
+        camera_data.position = Vec3::new(0.0, 0.0, 5.0);
+        camera_data.update_view(
+            camera_data.position,
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        harmony::scene::entities::camera::create(&mut app.current_scene.world, camera_data);
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_default_env()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("harmony", log::LevelFilter::Info)
+        .init();
+
+    // Tell harmony where our asset path is.
+    let asset_path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/").to_string();
+
+    // No window or event loop is needed when running headless.
+    let mut application = harmony::Application::new_headless(
+        winit::dpi::PhysicalSize::new(FRAME_WIDTH, FRAME_HEIGHT),
+        asset_path,
+        vec![],
+        harmony::AppConfig::default(),
+    )
+    .expect("Couldn't create the application.");
+    let mut app_state = AppState::new();
+    application.load(&mut app_state);
+
+    // Meshes load in the background so keep rendering until the cube is ready.
+    let mesh = app_state.mesh.clone().unwrap();
+    let mut frames = 0;
+    while mesh.mesh_handle.get().is_err() && frames < MAX_LOADING_FRAMES {
+        application.run_headless(&mut app_state);
+        frames += 1;
+    }
+    application.run_headless(&mut app_state);
+
+    application
+        .save_frame("headless.png")
+        .expect("Couldn't save the rendered frame.");
+    log::info!("Saved frame to headless.png");
+}
@@ -16,7 +16,10 @@ use harmony::scene::{
 // use harmony::scene::components::PointLightData;
 
 use harmony::{
-    core::input::{Input, MouseButton},
+    core::{
+        input::{Input, MouseButton},
+        Stage, StagedSchedule, SystemDesc,
+    },
     graphics::resources::{ProbeFormat, ProbeQuality},
     AssetManager, WinitState,
 };
@@ -58,17 +61,23 @@ fn create_camera_orbit_system() -> Box<dyn Schedulable> {
         .with_query(<Write<CameraData>>::query())
         .build(|_, mut world, (delta_time, input), camera_query| {
             for mut camera in camera_query.iter_mut(&mut world) {
-                if !input.is_mouse_button_down(MouseButton::Left) {
+                // Scrolling zooms in and out.
+                let zoom = input.mouse_wheel_movement().y;
+                let rotate = input.is_mouse_button_down(MouseButton::Left);
+                if !rotate && zoom == 0.0 {
                     continue;
                 }
-                camera.yaw += input.mouse_delta.x * 0.5 * delta_time.0;
-                camera.pitch += input.mouse_delta.y * 0.5 * delta_time.0;
-                camera.pitch = camera
-                    .pitch
-                    .max(-std::f32::consts::FRAC_PI_2 + 0.0001)
-                    .min(std::f32::consts::FRAC_PI_2 - 0.0001);
+                if rotate {
+                    camera.yaw += input.mouse_delta.x * 0.5 * delta_time.0;
+                    camera.pitch += input.mouse_delta.y * 0.5 * delta_time.0;
+                    camera.pitch = camera
+                        .pitch
+                        .max(-std::f32::consts::FRAC_PI_2 + 0.0001)
+                        .min(std::f32::consts::FRAC_PI_2 - 0.0001);
+                }
+                let distance = (camera.position.magnitude() - zoom * 0.5).max(1.0).min(50.0);
                 let eye = Vec3::new(0.0, 0.0, 0.0)
-                    + (5.0
+                    + (distance
                         * nalgebra::Vector3::new(
                             camera.yaw.sin() * camera.pitch.cos(),
                             camera.pitch.sin(),
@@ -82,15 +91,25 @@ fn create_camera_orbit_system() -> Box<dyn Schedulable> {
 
 impl harmony::AppState for AppState {
     fn load(&mut self, app: &mut harmony::Application) {
-        let scheduler_builder = Schedule::builder()
-            .add_system(create_rotate_system())
-            .add_system(create_camera_orbit_system());
-        app.current_scene = Scene::new(None, Some(scheduler_builder));
+        // The camera moves before harmony's culling system runs in `Stage::PostUpdate`.
+        let mut schedule = StagedSchedule::game();
+        schedule
+            .add_system(Stage::Update, create_rotate_system())
+            .add_system(
+                Stage::Update,
+                SystemDesc::new(create_camera_orbit_system()).label("camera").before("culling"),
+            );
+        app.current_scene = Scene::new(None, Some(schedule));
 
         // This is scoped to not interfer with other calls below.
         let mesh_handle = {
             // asset manager lets you retrieve files from disk.
-            let asset_manager = app.resources.get_mut::<AssetManager>().unwrap();
+            let mut asset_manager = app.resources.get_mut::<AssetManager>().unwrap();
+
+            // Reload textures, materials, meshes and shaders when they change on disk.
+            if let Err(error) = asset_manager.watch() {
+                log::warn!("{}", error);
+            }
 
             // Retrieves a mesh handle from disk.
             // Note: This could be loading still, but in our case we don't care as the system that renders the meshes
@@ -107,7 +126,7 @@ impl harmony::AppState for AppState {
         // 3. The transform which allows us to render the mesh using it's world cords. This also includes stuff like
         // rotation and scale.
 
-        let transform = Transform::new(app);
+        let transform = Transform::default();
         app.current_scene
             .world
             .insert((), vec![(Mesh::new(mesh_handle), transform)]);
@@ -134,7 +153,7 @@ impl harmony::AppState for AppState {
         );
 
         // Add directional light to our scene.
-        let light_transform = Transform::new(app);
+        let light_transform = Transform::default();
         harmony::scene::entities::light::create(
             &mut app.current_scene.world,
             LightType::Directional(DirectionalLightData {
@@ -147,7 +166,7 @@ impl harmony::AppState for AppState {
 
         // Add red point light to our scene.
         // Uncomment this code to see point light.
-        // let mut transform = Transform::new(app);
+        // let mut transform = Transform::default();
         // transform.position = Vec3::new(-5.0, 50.0, 0.0);
         // harmony::scene::entities::light::create(
         //     &mut app.current_scene.world,
@@ -193,6 +212,22 @@ impl harmony::AppState for AppState {
             );
         }
     }
+    // Drag and drop a .gltf file onto the window to add it to the scene.
+    fn file_dropped(&mut self, app: &mut harmony::Application, path: std::path::PathBuf) {
+        if path.extension().map_or(true, |extension| extension != "gltf") {
+            log::warn!("Only .gltf files can be dropped, got: {}", path.display());
+            return;
+        }
+
+        let mesh_handle = {
+            let asset_manager = app.resources.get_mut::<AssetManager>().unwrap();
+            asset_manager.get_mesh(path)
+        };
+        let transform = Transform::default();
+        app.current_scene
+            .world
+            .insert((), vec![(Mesh::new(mesh_handle), transform)]);
+    }
 }
 
 fn main() {
@@ -208,7 +243,18 @@ fn main() {
 
     // Tell harmony where our asset path is.
     let asset_path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/").to_string();
-    let mut application = harmony::Application::new(wb, &event_loop, asset_path, vec![]);
+    // Engine settings can be loaded from a RON file.
+    let config = harmony::AppConfig::load(format!("{}config.ron", asset_path)).unwrap_or_default();
+
+    // Creating the application fails if there is no compatible graphics adapter or the core assets are missing.
+    let mut application =
+        match harmony::Application::new(wb, &event_loop, asset_path, vec![], config) {
+            Ok(application) => application,
+            Err(error) => {
+                log::error!("{}", error);
+                return;
+            }
+        };
     let mut app_state = AppState::new();
     // Call application load to have harmony load all the required assets.
     application.load(&mut app_state);
@@ -219,15 +265,14 @@ fn main() {
         application.run(&mut app_state, &event, control_flow);
         match event {
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                winit::event::WindowEvent::KeyboardInput {
+                WindowEvent::KeyboardInput {
                     input:
                         winit::event::KeyboardInput {
                             virtual_keycode: Some(winit::event::VirtualKeyCode::Escape),
                             ..
                         },
                     ..
-                } => *control_flow = winit::event_loop::ControlFlow::Exit,
+                } => *control_flow = ControlFlow::Exit,
                 _ => {}
             },
             _ => (),
@@ -8,10 +8,11 @@ use winit::{
 use harmony::{
     graphics::{
         pipeline_manager::{PipelineDesc, PipelineManager},
+        renderer::FrameTexture,
         resources::{BindGroup, GPUResourceManager},
         CommandBufferQueue, CommandQueueItem,
     },
-    AssetManager, WinitState,
+    AppBuilder, AssetManager, Plugin, WinitState,
 };
 use nalgebra_glm::Vec3;
 use std::{borrow::Cow, sync::Arc};
@@ -43,7 +44,7 @@ pub fn create_triangle_render_system() -> Box<dyn Schedulable> {
         .write_resource::<CommandBufferQueue>()
         .read_resource::<PipelineManager>()
         .read_resource::<Arc<wgpu::Device>>()
-        .read_resource::<Arc<wgpu::SwapChainTexture>>()
+        .read_resource::<Arc<FrameTexture>>()
         .read_resource::<Arc<GPUResourceManager>>()
         .build(
             |_,
@@ -54,14 +55,14 @@ pub fn create_triangle_render_system() -> Box<dyn Schedulable> {
                     label: Some("Triangle Pass"),
                 });
 
-                // Name of our node we created in app state "load".
+                // Name of the pipeline we created in `create_triangle_pipeline`.
                 let node = pipeline_manager.get("triangle", None).unwrap();
 
                 let triangle_bind_group = resource_manager.get_bind_group("triangle", 0).unwrap();
                 {
                     let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                         color_attachments: Cow::Borrowed(&[wgpu::RenderPassColorAttachmentDescriptor {
-                            attachment: &output.view,
+                            attachment: output.view(),
                             resolve_target: None,
                             ops: wgpu::Operations {
                                 load: wgpu::LoadOp::Load,
@@ -86,60 +87,75 @@ pub fn create_triangle_render_system() -> Box<dyn Schedulable> {
         )
 }
 
+// Plugins package up everything a feature needs so it can be added to an application in one go.
+struct TrianglePlugin;
+
+impl Plugin for TrianglePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // It is IMPORTANT to remember NOT to add render systems to a scene's scheduler.
+        // As the scene scheduler runs potentially multiple times per frame.
+        app.add_render_system(create_triangle_render_system())
+            .add_pipeline(create_triangle_pipeline);
+    }
+}
+
+// Called by harmony during `application.load` once the core pipelines exist.
+fn create_triangle_pipeline(resources: &Resources) {
+    // First we need to access some of the internal data.
+    let device = resources.get::<Arc<wgpu::Device>>().unwrap();
+    let asset_manager = resources.get::<AssetManager>().unwrap();
+    let gpu_resource_manager = resources.get::<Arc<GPUResourceManager>>().unwrap();
+    let mut pipeline_manager = resources.get_mut::<PipelineManager>().unwrap();
+
+    // Setup our bind groups and layouts
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: Cow::Borrowed(&[]),
+        label: None,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &bind_group_layout,
+        entries: Cow::Borrowed(&[]),
+        label: Some(Cow::Borrowed("triangle")),
+    });
+    gpu_resource_manager.add_single_bind_group("triangle", BindGroup::new(0, bind_group));
+    gpu_resource_manager.add_bind_group_layout("triangle_layout", bind_group_layout);
+
+    // Setup our custom pipeline
+    let mut triangle_desc = PipelineDesc::default();
+    triangle_desc.shader = "example/shader/triangle.shader".to_string(); // Make sure we reference the right shader!
+    triangle_desc.layouts = vec!["triangle_layout".to_string()];
+    triangle_desc
+        .vertex_state
+        .set_index_format(wgpu::IndexFormat::Uint16);
+    triangle_desc.cull_mode = wgpu::CullMode::None;
+
+    // The pipeline manager helps manage pipelines. It's somewhat smart and will cache your pipeline.
+    // Remember that adding new pipelines is expensive and should be avoided at runtime.
+    pipeline_manager.add_pipeline(
+        "triangle",                   // Name of pipeline.
+        &triangle_desc,               // Pipeline description
+        vec!["skybox"], // Dependencies list as names. Uses skybox so that the triangle draws "after" the clear pass.
+        &device,        // The wgpu device.
+        &asset_manager, // asset manager from where we can load shaders.
+        gpu_resource_manager.clone(), // The gpu resource manager.
+    );
+
+    // Pipeline manager is smart enough to not add a new pipeline even if we call pipeline_manager.add again!
+    // Note: There are ways to add a variation of a pipeline by cloning the description modifying it and adding
+    // it with the same name. This is useful for example if you want to render your pipeline/shader to the
+    // frame buffer and to a render target(with a different format).
+    pipeline_manager.add_pipeline(
+        "triangle",                   // Name of pipeline.
+        &triangle_desc,               // Pipeline description
+        vec!["skybox"],               // Dependencies list as names.
+        &device,                      // The wgpu device.
+        &asset_manager,               // asset manager from where we can load shaders.
+        gpu_resource_manager.clone(), // The gpu resource manager.
+    );
+}
+
 impl harmony::AppState for AppState {
     fn load(&mut self, app: &mut harmony::Application) {
-        // First we need to access some of the internal data.
-        let device = app.resources.get::<Arc<wgpu::Device>>().unwrap();
-        let asset_manager = app.resources.get::<AssetManager>().unwrap();
-        let gpu_resource_manager = app.resources.get::<Arc<GPUResourceManager>>().unwrap();
-        let mut pipeline_manager = app.resources.get_mut::<PipelineManager>().unwrap();
-
-        // Setup our bind groups and layouts
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: Cow::Borrowed(&[]),
-            label: None,
-        });
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: Cow::Borrowed(&[]),
-            label: Some(Cow::Borrowed("triangle")),
-        });
-        gpu_resource_manager.add_single_bind_group("triangle", BindGroup::new(0, bind_group));
-        gpu_resource_manager.add_bind_group_layout("triangle_layout", bind_group_layout);
-
-        // Setup our custom pipeline
-        let mut triangle_desc = PipelineDesc::default();
-        triangle_desc.shader = "example/shader/triangle.shader".to_string(); // Make sure we reference the right shader!
-        triangle_desc.layouts = vec!["triangle_layout".to_string()];
-        triangle_desc
-            .vertex_state
-            .set_index_format(wgpu::IndexFormat::Uint16);
-        triangle_desc.cull_mode = wgpu::CullMode::None;
-
-        // The pipeline manager helps manage pipelines. It's somewhat smart and will cache your pipeline.
-        // Remember that adding new pipelines is expensive and should be avoided at runtime.
-        pipeline_manager.add_pipeline(
-            "triangle",                   // Name of pipeline.
-            &triangle_desc,               // Pipeline description
-            vec!["skybox"], // Dependencies list as names. Uses skybox so that the triangle draws "after" the clear pass.
-            &device,        // The wgpu device.
-            &asset_manager, // asset manager from where we can load shaders.
-            gpu_resource_manager.clone(), // The gpu resource manager.
-        );
-
-        // Pipeline manager is smart enough to not add a new pipeline even if we call pipeline_manager.add again!
-        // Note: There are ways to add a variation of a pipeline by cloning the description modifying it and adding
-        // it with the same name. This is useful for example if you want to render your pipeline/shader to the
-        // frame buffer and to a render target(with a different format).
-        pipeline_manager.add_pipeline(
-            "triangle",                   // Name of pipeline.
-            &triangle_desc,               // Pipeline description
-            vec!["skybox"],               // Dependencies list as names.
-            &device,                      // The wgpu device.
-            &asset_manager,               // asset manager from where we can load shaders.
-            gpu_resource_manager.clone(), // The gpu resource manager.
-        );
-
         // Create a clear color
         let clear_color =
             harmony::graphics::material::Skybox::create_clear_color(Vec3::new(0.0, 1.0, 0.0));
@@ -161,15 +177,12 @@ fn main() {
 
     // Tell harmony where our asset path is.
     let asset_path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/").to_string();
-    // When we create our application we tell it we have some custom render systems.
-    // It is IMPORTANT to remember NOT to add render systems to a scene's scheduler.
-    // As the scene scheduler runs potentially multiple times per frame.
-    let mut application = harmony::Application::new(
-        wb,
-        &event_loop,
-        asset_path,
-        vec![create_triangle_render_system()],
-    );
+    // When we create our application we add the plugin containing our custom render system and pipeline.
+    let mut builder = AppBuilder::new(asset_path, harmony::AppConfig::default());
+    builder.add_plugin(TrianglePlugin);
+    let mut application = builder
+        .build(wb, &event_loop)
+        .expect("Couldn't create the application.");
     let mut app_state = AppState::new();
     // Call application load to have harmony load all the required assets.
     application.load(&mut app_state);
@@ -180,15 +193,14 @@ fn main() {
         application.run(&mut app_state, &event, control_flow);
         match event {
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                winit::event::WindowEvent::KeyboardInput {
+                WindowEvent::KeyboardInput {
                     input:
                         winit::event::KeyboardInput {
                             virtual_keycode: Some(winit::event::VirtualKeyCode::Escape),
                             ..
                         },
                     ..
-                } => *control_flow = winit::event_loop::ControlFlow::Exit,
+                } => *control_flow = ControlFlow::Exit,
                 _ => {}
             },
             _ => (),
@@ -86,7 +86,14 @@ fn main() {
 
     // Tell harmony where our asset path is.
     let asset_path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/").to_string();
-    let mut application = harmony::Application::new(wb, &event_loop, asset_path, vec![]);
+    let mut application = harmony::Application::new(
+        wb,
+        &event_loop,
+        asset_path,
+        vec![],
+        harmony::AppConfig::default(),
+    )
+    .expect("Couldn't create the application.");
     let mut app_state = AppState::new();
     // Call application load to have harmony load all the required assets.
     application.load(&mut app_state);
@@ -97,15 +104,14 @@ fn main() {
         application.run(&mut app_state, &event, control_flow);
         match event {
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                winit::event::WindowEvent::KeyboardInput {
+                WindowEvent::KeyboardInput {
                     input:
                         winit::event::KeyboardInput {
                             virtual_keycode: Some(winit::event::VirtualKeyCode::Escape),
                             ..
                         },
                     ..
-                } => *control_flow = winit::event_loop::ControlFlow::Exit,
+                } => *control_flow = ControlFlow::Exit,
                 _ => {}
             },
             _ => (),
@@ -0,0 +1,127 @@
+use legion::prelude::*;
+use nalgebra_glm::Vec3;
+
+use winit::{dpi::LogicalSize, window::WindowBuilder};
+
+use harmony::scene::components::{CameraData, DirectionalLightData, LightType, Mesh, Transform};
+use harmony::{
+    graphics::resources::{ProbeFormat, ProbeQuality},
+    AssetManager, WinitState,
+};
+
+const MAP_SIZE: f32 = 400.0;
+
+struct AppState {
+    map_camera: Option<Entity>,
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        Self { map_camera: None }
+    }
+}
+
+impl harmony::AppState for AppState {
+    fn load(&mut self, app: &mut harmony::Application) {
+        let mesh_handle = {
+            let asset_manager = app.resources.get_mut::<AssetManager>().unwrap();
+            asset_manager.get_mesh("example/meshes/cube/cube.gltf")
+        };
+
+        // A small grid of cubes so the map has something to show.
+        for x in -2..3 {
+            for z in -2..3 {
+                let mut transform = Transform::default();
+                transform.position = Vec3::new(x as f32 * 3.0, 0.0, z as f32 * 3.0);
+                transform.update();
+                app.current_scene
+                    .world
+                    .insert((), vec![(Mesh::new(mesh_handle.clone()), transform)]);
+            }
+        }
+
+        harmony::scene::entities::probe::create(
+            app,
+            Vec3::zeros(),
+            ProbeQuality::Low,
+            ProbeFormat::RGBA16,
+        );
+
+        let light_transform = Transform::default();
+        harmony::scene::entities::light::create(
+            &mut app.current_scene.world,
+            LightType::Directional(DirectionalLightData {
+                direction: Vec3::new(0.0, 1.0, -0.5),
+                color: Vec3::new(1.0, 1.0, 1.0),
+                intensity: 5.0,
+            }),
+            light_transform,
+        );
+
+        // The camera shown in the main window.
+        let actual_window_size = app.get_window_actual_size();
+        let mut camera_data = CameraData::new_perspective(
+            70.0,
+            actual_window_size.width,
+            actual_window_size.height,
+            0.1,
+            100.0,
+        );
+        camera_data.cull = true;
+        camera_data.update_view(
+            Vec3::new(0.0, 5.0, -15.0),
+            Vec3::zeros(),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        harmony::scene::entities::camera::create(&mut app.current_scene.world, camera_data);
+
+        // The top-down camera shown in the map window.
+        let mut map_camera = CameraData::new_perspective(70.0, MAP_SIZE, MAP_SIZE, 0.1, 100.0);
+        map_camera.update_view(
+            Vec3::new(0.0, 25.0, 0.0),
+            Vec3::zeros(),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        self.map_camera =
+            Some(harmony::scene::entities::camera::create(&mut app.current_scene.world, map_camera)[0]);
+    }
+
+    // Closing the map window keeps the main window open.
+    fn window_closed(&mut self, _app: &mut harmony::Application, _window_id: winit::window::WindowId) {
+        log::info!("Map window closed.");
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_default_env()
+        .filter_level(log::LevelFilter::Error)
+        .filter_module("harmony", log::LevelFilter::Info)
+        .init();
+
+    let (wb, event_loop) = WinitState::create("Harmony - Multi Window", LogicalSize::new(1024, 768));
+
+    let asset_path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/").to_string();
+    let mut application =
+        match harmony::Application::new(wb, &event_loop, asset_path, vec![], harmony::AppConfig::default()) {
+            Ok(application) => application,
+            Err(error) => {
+                log::error!("{}", error);
+                return;
+            }
+        };
+    let mut app_state = AppState::new();
+    application.load(&mut app_state);
+
+    // Extra windows can be opened once the camera they show exists.
+    let map_window = WindowBuilder::new()
+        .with_title("Harmony - Map")
+        .with_inner_size(LogicalSize::new(MAP_SIZE, MAP_SIZE));
+    application
+        .open_window(map_window, &event_loop, app_state.map_camera)
+        .expect("Couldn't open the map window.");
+
+    // Harmony closes the map window itself and exits when the main window closes.
+    event_loop.run(move |event, _, control_flow| {
+        application.run(&mut app_state, &event, control_flow);
+    });
+}
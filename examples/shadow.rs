@@ -14,7 +14,10 @@ use harmony::scene::{
 };
 
 use harmony::{
-    core::input::{Input, MouseButton},
+    core::{
+        input::{Input, MouseButton},
+        Stage, StagedSchedule, SystemDesc,
+    },
     graphics::resources::{ProbeFormat, ProbeQuality},
     AssetManager, WinitState,
 };
@@ -82,10 +85,15 @@ fn create_camera_orbit_system() -> Box<dyn Schedulable> {
 
 impl harmony::AppState for AppState {
     fn load(&mut self, app: &mut harmony::Application) {
-        let scheduler_builder = Schedule::builder()
-            .add_system(create_rotate_system())
-            .add_system(create_camera_orbit_system());
-        app.current_scene = Scene::new(None, Some(scheduler_builder));
+        // The camera moves before harmony's culling system runs in `Stage::PostUpdate`.
+        let mut schedule = StagedSchedule::game();
+        schedule
+            .add_system(Stage::Update, create_rotate_system())
+            .add_system(
+                Stage::Update,
+                SystemDesc::new(create_camera_orbit_system()).label("camera").before("culling"),
+            );
+        app.current_scene = Scene::new(None, Some(schedule));
 
         // This is scoped to not interfer with other calls below.
         let mesh_handle = {
@@ -100,7 +108,7 @@ impl harmony::AppState for AppState {
         };
 
         // Ground mesh..
-        let mut transform = Transform::new(app);
+        let mut transform = Transform::default();
         transform.scale = Vec3::new(10.0, 0.5, 100.0);
         transform.position = Vec3::new(0.0, -5.0, 0.0);
         app.current_scene
@@ -130,14 +138,14 @@ impl harmony::AppState for AppState {
 
 
         for i in 0..20 {
-            let mut transform = Transform::new(app);
+            let mut transform = Transform::default();
             transform.position.z = -i as f32 * 4.0;
             app.current_scene
                 .world
                 .insert((), vec![(Mesh::new(mesh_handle.clone()), transform)]);
 
             // Add point light
-            let mut transform = Transform::new(app);
+            let mut transform = Transform::default();
             transform.position = Vec3::new(0.0, 6.0, -i as f32 * 4.0);
             harmony::scene::entities::light::create(
                 &mut app.current_scene.world,
@@ -199,7 +207,14 @@ fn main() {
 
     // Tell harmony where our asset path is.
     let asset_path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/").to_string();
-    let mut application = harmony::Application::new(wb, &event_loop, asset_path, vec![]);
+    let mut application = harmony::Application::new(
+        wb,
+        &event_loop,
+        asset_path,
+        vec![],
+        harmony::AppConfig::default(),
+    )
+    .expect("Couldn't create the application.");
     let mut app_state = AppState::new();
     // Call application load to have harmony load all the required assets.
     application.load(&mut app_state);
@@ -210,15 +225,14 @@ fn main() {
         application.run(&mut app_state, &event, control_flow);
         match event {
             Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                winit::event::WindowEvent::KeyboardInput {
+                WindowEvent::KeyboardInput {
                     input:
                         winit::event::KeyboardInput {
                             virtual_keycode: Some(winit::event::VirtualKeyCode::Escape),
                             ..
                         },
                     ..
-                } => *control_flow = winit::event_loop::ControlFlow::Exit,
+                } => *control_flow = ControlFlow::Exit,
                 _ => {}
             },
             _ => (),
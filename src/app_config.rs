@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::graphics::shadows::ShadowQuality;
+
+/// How frames are presented to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PresentMode {
+    /// Frames are presented right away, this can cause tearing.
+    Immediate,
+    /// Waits for vertical blank but replaces queued frames with newer ones.
+    Mailbox,
+    /// Waits for vertical blank(vsync).
+    Fifo,
+}
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(present_mode: PresentMode) -> Self {
+        match present_mode {
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+        }
+    }
+}
+
+/// How the window covers the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FullscreenMode {
+    Windowed,
+    /// A borderless window the size of the current monitor.
+    Borderless,
+    /// Takes exclusive control of the current monitor using its native resolution and highest refresh rate.
+    Exclusive,
+}
+
+/// Which graphics APIs harmony is allowed to use.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Backend {
+    /// Vulkan, Metal or DX12 depending on the platform.
+    Primary,
+    Vulkan,
+    Metal,
+    Dx12,
+    Dx11,
+    Gl,
+    /// Any backend including the secondary ones(DX11, GL).
+    All,
+}
+
+impl From<Backend> for wgpu::BackendBit {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Primary => wgpu::BackendBit::PRIMARY,
+            Backend::Vulkan => wgpu::BackendBit::VULKAN,
+            Backend::Metal => wgpu::BackendBit::METAL,
+            Backend::Dx12 => wgpu::BackendBit::DX12,
+            Backend::Dx11 => wgpu::BackendBit::DX11,
+            Backend::Gl => wgpu::BackendBit::GL,
+            Backend::All => wgpu::BackendBit::all(),
+        }
+    }
+}
+
+/// Which kind of adapter to prefer when more than one is available.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PowerPreference {
+    Default,
+    LowPower,
+    HighPerformance,
+}
+
+impl From<PowerPreference> for wgpu::PowerPreference {
+    fn from(power_preference: PowerPreference) -> Self {
+        match power_preference {
+            PowerPreference::Default => wgpu::PowerPreference::Default,
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// Engine settings used when creating an `Application`.
+/// Any field missing from a RON file falls back to its default value.
+///
+/// Example `config.ron`:
+/// ```ron
+/// (
+///     shadow_quality: High,
+///     present_mode: Mailbox,
+///     fixed_timestep: 0.008333,
+/// )
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Graphics APIs to pick an adapter from.
+    pub backend: Backend,
+    /// Used to pick an adapter when `adapter_name` isn't set.
+    pub power_preference: PowerPreference,
+    /// Picks the first adapter whose name contains this string, ex: "Intel".
+    /// Falls back to `power_preference` if no adapter matches.
+    pub adapter_name: Option<String>,
+    /// Only use a software(CPU) adapter. Creating the application fails if there isn't one.
+    pub force_fallback_adapter: bool,
+    /// Size of the point light shadow atlas. Can't be changed at runtime.
+    pub shadow_quality: ShadowQuality,
+    /// Can be changed at runtime with `Application::set_present_mode` or `Application::set_vsync`.
+    pub present_mode: PresentMode,
+    /// Can be changed at runtime with `Application::set_fullscreen`. Ignored when running headless.
+    pub fullscreen: FullscreenMode,
+    /// Time in seconds between scene updates. Can be changed at runtime with `Application::set_fixed_timestep`.
+    /// Must be positive, invalid timesteps are replaced with 60 updates per second.
+    pub fixed_timestep: f32,
+    /// The most fixed updates run in a single frame. When a frame takes longer the simulation slows down
+    /// instead of falling further behind.
+    pub max_substeps: u32,
+    /// The maximum number of command buffers that can be queued up in a single frame. Can't be changed at runtime.
+    pub command_buffer_capacity: usize,
+    /// Size of the imgui font in pixels before hidpi scaling. Can't be changed at runtime.
+    pub font_size: f32,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            backend: Backend::Primary,
+            power_preference: PowerPreference::HighPerformance,
+            adapter_name: None,
+            force_fallback_adapter: false,
+            shadow_quality: ShadowQuality::Medium,
+            present_mode: PresentMode::Fifo,
+            fullscreen: FullscreenMode::Windowed,
+            fixed_timestep: 1.0 / 60.0,
+            max_substeps: 5,
+            command_buffer_capacity: 50,
+            font_size: 13.0,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Parses a config from a RON string.
+    pub fn from_ron(config: &str) -> Result<Self, ron::de::Error> {
+        ron::de::from_str(config)
+    }
+
+    /// Loads a config from a RON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ron::de::Error> {
+        let file = std::fs::File::open(path)?;
+        ron::de::from_reader(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppConfig, Backend, PresentMode};
+    use crate::graphics::shadows::ShadowQuality;
+
+    #[test]
+    fn should_use_defaults_for_missing_fields() {
+        let config = AppConfig::from_ron("(shadow_quality: High, present_mode: Mailbox)").unwrap();
+        assert_eq!(config.shadow_quality, ShadowQuality::High);
+        assert_eq!(config.present_mode, PresentMode::Mailbox);
+        assert_eq!(config.fixed_timestep, AppConfig::default().fixed_timestep);
+        assert_eq!(config.command_buffer_capacity, 50);
+        assert_eq!(config.backend, Backend::Primary);
+        assert_eq!(config.adapter_name, None);
+    }
+
+    #[test]
+    fn should_parse_adapter_selection() {
+        let config =
+            AppConfig::from_ron("(backend: Vulkan, adapter_name: Some(\"Intel\"), force_fallback_adapter: true)")
+                .unwrap();
+        assert_eq!(config.backend, Backend::Vulkan);
+        assert_eq!(config.adapter_name, Some("Intel".to_string()));
+        assert!(config.force_fallback_adapter);
+    }
+
+    #[test]
+    fn should_load_config_file() {
+        let config = AppConfig::load("./assets/config.ron").unwrap();
+        assert_eq!(config, AppConfig::default());
+    }
+}
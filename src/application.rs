@@ -1,28 +1,39 @@
-use std::{path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 use winit::{
-    event::Event,
-    event_loop::{ControlFlow, EventLoop},
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+    window::WindowId,
 };
 
 use imgui::*;
 use legion::prelude::*;
 
 use crate::{
-    core::input::Input,
+    core::{
+        input::{GamepadBackend, Input, InputRecording, RecordedFrame},
+        FixedTimestep, StagedSchedule,
+    },
     graphics::{
         self,
         material::Skybox,
         pipeline_manager::PipelineManager,
-        resources::{CurrentRenderTarget, GPUResourceManager, ProbeManager},
-        systems::create_render_schedule_builder,
-        RenderGraph, Renderer,
+        render_thread::{harmony_render_resources, RenderJob, RenderPass, RenderResource, RenderThread},
+        resources::{CurrentRenderTarget, GPUResourceManager, ProbeManager, RenderTarget},
+        systems::{create_render_schedule, create_window_render_schedule},
+        RenderGraph, RenderWorld, Renderer,
     },
-    scene::Scene,
-    AssetManager, TransformCount,
+    scene::{resources::InterpolationAlpha, Scene},
+    plugin::PipelineFn,
+    AppBuilder, AppConfig, AssetManager, FullscreenMode, HarmonyError, PresentMode,
 };
 use graphics::{
     material::skybox::SkyboxType,
-    renderer::{DepthTexture, DEPTH_FORMAT},
+    renderer::{DepthTexture, FrameTexture},
     // pipelines::{LinePipelineDesc, UnlitPipelineDesc},
     CommandBufferQueue,
     CommandQueueItem, lighting::cluster::Clustering, shadows::{ShadowCamera, OmniShadowManager},
@@ -32,32 +43,70 @@ use nalgebra_glm::Vec2;
 pub trait AppState {
     /// Is called after the engine has loaded an assets.
     fn load(&mut self, _app: &mut Application) {}
-    /// Called to update app state.
+    /// Called at a fixed rate(`AppConfig::fixed_timestep`) after the scene's systems have run.
+    /// `app.delta_time` is the fixed timestep. Use this for gameplay and physics.
+    fn fixed_update(&mut self, _app: &mut Application) {}
+    /// Called once per frame after any fixed updates. `app.delta_time` is the time the frame took.
     fn update(&mut self, _app: &mut Application) {}
     /// Called when the window resizes
     fn resize(&mut self, _app: &mut Application) {}
-    /// Used to update your app state for the UI.
+    /// Called when the user tries to close the window. Return false to keep the application running,
+    /// ex: to show a confirmation dialog first.
+    fn close_requested(&mut self, _app: &mut Application) -> bool {
+        true
+    }
+    /// Called when the main window gains or loses focus.
+    fn focus_changed(&mut self, _app: &mut Application, _focused: bool) {}
+    /// Called when the window moves to a monitor with a different DPI or the DPI setting changes.
+    /// The swap chain has already been resized at this point.
+    fn scale_factor_changed(&mut self, _app: &mut Application, _scale_factor: f64) {}
+    /// Called for every file dropped onto the main window.
+    fn file_dropped(&mut self, _app: &mut Application, _path: PathBuf) {}
+    /// Called when the window is minimized. Nothing is rendered until it's restored.
+    fn minimized(&mut self, _app: &mut Application) {}
+    /// Called when the window is restored after being minimized.
+    fn restored(&mut self, _app: &mut Application) {}
+    /// Called after a window opened with `Application::open_window` was closed by the user.
+    fn window_closed(&mut self, _app: &mut Application, _window_id: WindowId) {}
+    /// Called when a window opened with `Application::open_window` gains or loses focus.
+    fn window_focus_changed(&mut self, _app: &mut Application, _window_id: WindowId, _focused: bool) {}
+    /// Called for every file dropped onto a window opened with `Application::open_window`.
+    fn window_file_dropped(&mut self, _app: &mut Application, _window_id: WindowId, _path: PathBuf) {}
+    /// Used to update your app state for the UI. Called once per frame after `update`.
     // TODO: Maybe update should just be used instead.
     fn update_ui(&mut self, _app: &mut Application) {}
     /// A function to help draw your UI. PLease see hello-world for an example.
     fn draw_ui(&mut self, _ui: &mut imgui::Ui<'_>, _screen_size: Vec2) {}
+    /// Called once when the graphics device is lost or runs out of memory.
+    /// Nothing is rendered afterwards, save any state you need and recreate the application.
+    fn device_lost(&mut self, _app: &mut Application) {}
 }
 
 pub struct Application {
     // TODO: Don't expose renderer outside of harmony?
     pub renderer: Renderer,
     clock: Instant,
-    fixed_timestep: f32,
+    config: AppConfig,
     elapsed_time: f32,
+    // Splits frames into fixed updates.
+    fixed_timestep: FixedTimestep,
     /// Time last frame took.
     pub frame_time: f32,
     /// Current delta time.
     pub delta_time: f32,
     /// Current scene.
     pub current_scene: Scene,
-    /// A legion schedule that contains the systems used to render.
-    pub render_schedule: Schedule,
+    // Runs the render schedule, and the schedule of windows opened with `open_window`, against `render_world`.
+    render_thread: RenderThread,
+    // Snapshot of the current scene's render data, extracted every frame before the render schedule runs.
+    // None while the render thread is rendering it.
+    render_world: Option<RenderWorld>,
+    // The resources that are moved or copied to the render thread while a frame renders.
+    render_resources: Vec<RenderResource>,
     /// Legion resources.
+    ///
+    /// *Note*: While the game updates the previous frame is still rendering, resources render systems use(ex:
+    /// the `PipelineManager`) are on the render thread until `wait_for_render` is called.
     pub resources: Resources,
     /// The probe manager.
     pub probe_manager: ProbeManager,
@@ -66,6 +115,13 @@ pub struct Application {
     pub(crate) imgui_renderer: imgui_wgpu::Renderer,
     last_cursor: Option<imgui::MouseCursor>,
     last_frame: Instant,
+    device_lost: bool,
+    focused: bool,
+    // Pipelines added by plugins, created in `load`.
+    pipelines: Vec<PipelineFn>,
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    recording: Option<InputRecording>,
+    playback: Option<VecDeque<RecordedFrame>>,
 }
 
 impl Application {
@@ -75,88 +131,156 @@ impl Application {
     /// * `window_builder` - The winit WindowBuilder that harmony can use to setup the window for rendering.
     /// * `event_loop` - A reference to winit's event loop.
     /// * `asset_path` - Path to the asset folder.
+    /// * `render_systems` - Extra systems added to the render schedule.
+    /// * `config` - Engine settings, use `AppConfig::default()` or load them with `AppConfig::load`.
     ///
-    /// *Note*: This returns a new instance of Application.
+    /// *Note*: Returns an error if no compatible graphics adapter was found or harmony's core assets couldn't be loaded.
+    /// Use `AppBuilder` to add plugins.
     pub fn new<T>(
         window_builder: winit::window::WindowBuilder,
         event_loop: &EventLoop<()>,
         asset_path: T,
-        mut render_systems: Vec<Box<dyn Schedulable>>,
-    ) -> Self
+        render_systems: Vec<Box<dyn Schedulable>>,
+        config: AppConfig,
+    ) -> Result<Self, HarmonyError>
+    where
+        T: Into<PathBuf>,
+    {
+        let mut builder = AppBuilder::new(asset_path, config);
+        for system in render_systems {
+            builder.add_render_system(system);
+        }
+        builder.build(window_builder, event_loop)
+    }
+
+    /// Creates a new application without a window.
+    /// Frames are rendered into an offscreen `RenderTarget` which can be retrieved with `renderer.headless_target()`.
+    /// # Arguments
+    ///
+    /// * `size` - The size in pixels of the offscreen frame.
+    /// * `asset_path` - Path to the asset folder.
+    /// * `render_systems` - Extra systems added to the render schedule.
+    /// * `config` - Engine settings. `present_mode` is ignored since there is no window to present to.
+    ///
+    /// *Note*: Use `run_headless` instead of `run` to update and render a frame.
+    pub fn new_headless<T>(
+        size: winit::dpi::PhysicalSize<u32>,
+        asset_path: T,
+        render_systems: Vec<Box<dyn Schedulable>>,
+        config: AppConfig,
+    ) -> Result<Self, HarmonyError>
     where
         T: Into<PathBuf>,
     {
-        let scene = Scene::new(None, None);
-        let window = window_builder.build(event_loop).unwrap();
-        let size = window.inner_size();
+        let mut builder = AppBuilder::new(asset_path, config);
+        for system in render_systems {
+            builder.add_render_system(system);
+        }
+        builder.build_headless(size)
+    }
+
+    pub(crate) fn from_renderer(
+        renderer: Renderer,
+        builder: AppBuilder,
+    ) -> Result<Self, HarmonyError> {
+        let AppBuilder {
+            asset_path,
+            config,
+            mut resources,
+            systems,
+            render_systems,
+            render_resources: plugin_render_resources,
+            pipelines,
+            asset_loaders,
+            bundles,
+            gamepad_backend,
+            ..
+        } = builder;
+        let gamepad_backend = gamepad_backend.or_else(default_gamepad_backend);
+
+        let mut game_schedule = StagedSchedule::game();
+        for (stage, system) in systems {
+            game_schedule.add_system(stage, system);
+        }
+        let scene = Scene::new(None, Some(game_schedule));
 
         // Add resources
-        let mut resources = Resources::default();
         resources.insert(crate::scene::resources::DeltaTime(0.05));
-
-        let renderer = futures::executor::block_on(Renderer::new(window, size, &mut resources));
+        resources.insert(InterpolationAlpha::default());
 
         let (asset_manager, clustering) = {
             let device = resources.get::<Arc<wgpu::Device>>().unwrap();
             let queue = resources.get::<Arc<wgpu::Queue>>().unwrap();
             let gpu_resource_manager = resources.get::<Arc<GPUResourceManager>>().unwrap();
             let mut pipeline_manager = resources.get_mut::<PipelineManager>().unwrap();
-            let asset_manager = AssetManager::new(
-                asset_path.into(),
+            let mut asset_manager = AssetManager::new(
+                asset_path,
                 device.clone(),
                 queue.clone(),
                 gpu_resource_manager.clone(),
             );
+            for bundle in bundles {
+                asset_manager.mount_bundle(bundle)?;
+            }
+            for register in asset_loaders {
+                register(&mut asset_manager);
+            }
+            // Fail early if core assets are missing or shaders don't compile, pipelines expect them to exist.
+            asset_manager.load_core_assets()?;
+
             let clustering = Clustering::new(device.clone(), gpu_resource_manager.clone(), &mut pipeline_manager, &asset_manager);
 
-            OmniShadowManager::create_pipeline(device.clone(), &asset_manager, gpu_resource_manager.clone(), &mut pipeline_manager);
+            let max_casters_per_frame = resources.get::<OmniShadowManager>().unwrap().max_casters_per_frame;
+            OmniShadowManager::create_pipeline(device.clone(), &asset_manager, gpu_resource_manager.clone(), &mut pipeline_manager, max_casters_per_frame);
 
             (asset_manager, clustering)
         };
         resources.insert(asset_manager);
         resources.insert(clustering);
 
-        // Setup default cube shadow camera.
+        // Setup default cube shadow camera. Cube faces are square, the size of each face comes from `shadow_quality`.
         resources.insert(ShadowCamera::new_perspective(
             90.0,
-            1024.0,
-            1024.0,
+            1.0,
+            1.0,
             0.1,
             1000.0,
         ));
 
-        let mut render_schedule_builder = create_render_schedule_builder();
-        render_schedule_builder =
-            render_schedule_builder
-                .add_system(crate::graphics::systems::shadow::create())
-                .add_system(crate::graphics::systems::mesh::create());
-
-        for index in 0..render_systems.len() {
-            let system = render_systems.remove(index);
-            render_schedule_builder = render_schedule_builder.add_system(system);
+        let mut render_schedule = create_render_schedule();
+        for (stage, system) in render_systems {
+            render_schedule.add_system(stage, system);
         }
+        // Schedules are built on the render thread.
+        let render_thread = RenderThread::new(render_schedule, create_window_render_schedule());
+        let mut render_resources = harmony_render_resources();
+        render_resources.extend(plugin_render_resources);
 
-        let render_schedule = render_schedule_builder
-            .flush()
-            .add_thread_local_fn(graphics::systems::render::create())
-            .build();
-
-        resources.insert(TransformCount(0));
         resources.insert(CurrentRenderTarget(None));
 
         resources.insert(Input::new());
 
-        let hidpi_factor = renderer.window.scale_factor();
         let mut imgui = imgui::Context::create();
         let mut platform = imgui_winit_support::WinitPlatform::init(&mut imgui);
-        platform.attach_window(
-            imgui.io_mut(),
-            &renderer.window,
-            imgui_winit_support::HiDpiMode::Default,
-        );
+        let hidpi_factor = match renderer.window.as_ref() {
+            Some(window) => {
+                platform.attach_window(
+                    imgui.io_mut(),
+                    window,
+                    imgui_winit_support::HiDpiMode::Default,
+                );
+                window.scale_factor()
+            }
+            None => {
+                // Without a window imgui needs to be told how big the frame is.
+                imgui.io_mut().display_size =
+                    [renderer.size.width as f32, renderer.size.height as f32];
+                1.0
+            }
+        };
         imgui.set_ini_filename(None);
 
-        let font_size = (13.0 * hidpi_factor) as f32;
+        let font_size = (config.font_size as f64 * hidpi_factor) as f32;
         imgui.io_mut().font_global_scale = (1.0 / hidpi_factor) as f32;
 
         imgui.fonts().add_font(&[FontSource::DefaultFontData {
@@ -196,23 +320,42 @@ impl Application {
 
         resources.insert(crate::core::PerformanceMetrics::new());
 
-        Application {
+        let fullscreen = config.fullscreen;
+        let fixed_timestep = FixedTimestep::new(config.fixed_timestep, config.max_substeps);
+        // Headless frames advance by the config's timestep, keep it in sync when an invalid one was replaced.
+        let mut config = config;
+        config.fixed_timestep = fixed_timestep.timestep();
+        let mut app = Application {
             renderer,
             clock: Instant::now(),
-            fixed_timestep: 1.0 / 60.0,
+            config,
             elapsed_time: 0.0,
+            fixed_timestep,
             frame_time: 0.0,
             delta_time: 0.0,
             current_scene: scene,
             resources,
-            render_schedule,
+            render_thread,
+            render_world: Some(RenderWorld::new()),
+            render_resources,
             probe_manager: ProbeManager::new(),
             imgui,
             platform,
             imgui_renderer,
             last_frame,
             last_cursor: None,
+            device_lost: false,
+            focused: true,
+            pipelines,
+            gamepad_backend,
+            recording: None,
+            playback: None,
+        };
+        if fullscreen != FullscreenMode::Windowed {
+            app.set_fullscreen(fullscreen);
         }
+
+        Ok(app)
     }
 
     /// Set's the current scene that harmony will use for rendering.
@@ -225,15 +368,342 @@ impl Application {
         self.current_scene = current_scene;
     }
 
+    /// Returns true once the graphics device has been lost. See `AppState::device_lost`.
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost
+    }
+
+    /// Returns true if the window has focus. Always true when running headless.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// The settings this application was created with.
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    /// Changes how frames are presented to the window, this recreates the swap chain.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.wait_for_render();
+        self.config.present_mode = present_mode;
+        {
+            let mut sc_desc = self
+                .resources
+                .get_mut::<wgpu::SwapChainDescriptor>()
+                .unwrap();
+            sc_desc.present_mode = present_mode.into();
+        }
+        self.renderer.resize(&mut self.resources);
+        let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+        self.renderer
+            .set_windows_present_mode(present_mode.into(), &device);
+    }
+
+    /// Opens an extra window showing `camera`, a `CameraData` entity from the current scene.
+    /// Extra windows render the same scene as the main window but don't draw the UI.
+    /// The main window stops rendering cameras that are shown in extra windows.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_builder` - The winit WindowBuilder used to create the window.
+    /// * `event_loop` - Winit's event loop, inside of `event_loop.run` use the window target it passes in.
+    /// * `camera` - The camera to render, nothing is rendered until one is set with `set_window_camera`.
+    pub fn open_window(
+        &mut self,
+        window_builder: winit::window::WindowBuilder,
+        event_loop: &EventLoopWindowTarget<()>,
+        camera: Option<Entity>,
+    ) -> Result<WindowId, HarmonyError> {
+        let window = window_builder.build(event_loop)?;
+        let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+        Ok(self
+            .renderer
+            .add_window(window, camera, &self.config, &device))
+    }
+
+    /// Closes a window opened with `open_window`.
+    pub fn close_window(&mut self, window_id: WindowId) {
+        self.renderer.windows.remove(&window_id);
+    }
+
+    /// Changes the camera shown in a window opened with `open_window`.
+    pub fn set_window_camera(&mut self, window_id: WindowId, camera: Option<Entity>) {
+        if let Some(window) = self.renderer.get_window_mut(window_id) {
+            window.camera = camera;
+        }
+    }
+
+    /// Turns vsync on(`PresentMode::Fifo`) or off(`PresentMode::Immediate`), this recreates the swap chain.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.set_present_mode(if vsync {
+            PresentMode::Fifo
+        } else {
+            PresentMode::Immediate
+        });
+    }
+
+    /// Switches the window between windowed, borderless and exclusive fullscreen.
+    /// The swap chain is recreated once the window reports its new size. Does nothing when running headless.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) {
+        self.config.fullscreen = mode;
+        let window = match self.renderer.window.as_ref() {
+            Some(window) => window,
+            None => return,
+        };
+
+        let fullscreen = match mode {
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => {
+                Some(winit::window::Fullscreen::Borderless(window.current_monitor()))
+            }
+            FullscreenMode::Exclusive => {
+                let monitor = window.current_monitor();
+                let size = monitor.size();
+                let video_mode = monitor
+                    .video_modes()
+                    .filter(|video_mode| video_mode.size() == size)
+                    .max_by_key(|video_mode| (video_mode.refresh_rate(), video_mode.bit_depth()));
+                match video_mode {
+                    Some(video_mode) => Some(winit::window::Fullscreen::Exclusive(video_mode)),
+                    None => {
+                        log::warn!("Monitor has no video mode matching its size, using borderless fullscreen.");
+                        Some(winit::window::Fullscreen::Borderless(monitor))
+                    }
+                }
+            }
+        };
+        window.set_fullscreen(fullscreen);
+    }
+
+    /// Confines the cursor to the window. Does nothing when running headless.
+    pub fn set_cursor_grab(&self, grab: bool) -> Result<(), winit::error::ExternalError> {
+        match self.renderer.window.as_ref() {
+            Some(window) => window.set_cursor_grab(grab),
+            None => Ok(()),
+        }
+    }
+
+    /// Shows or hides the cursor while it's over the window. Does nothing when running headless.
+    pub fn set_cursor_visible(&self, visible: bool) {
+        if let Some(window) = self.renderer.window.as_ref() {
+            window.set_cursor_visible(visible);
+        }
+    }
+
+    /// Hides and grabs the cursor so the mouse can be used to look around, read the movement from `Input::mouse_delta`.
+    pub fn set_relative_mouse(&mut self, enabled: bool) -> Result<(), winit::error::ExternalError> {
+        self.set_cursor_grab(enabled)?;
+        self.set_cursor_visible(!enabled);
+        let mut input = self.resources.get_mut::<Input>().unwrap();
+        input.relative_mouse = enabled;
+        Ok(())
+    }
+
+    /// Replaces the backend gamepads are read from, ex: with a `MockGamepadBackend` in tests.
+    pub fn set_gamepad_backend<B: GamepadBackend + 'static>(&mut self, backend: B) {
+        self.gamepad_backend = Some(Box::new(backend));
+    }
+
+    /// Starts recording the input of every frame, including the time each frame took.
+    /// Input captured by the UI isn't recorded.
+    pub fn start_recording(&mut self) {
+        if self.playback.is_some() {
+            log::warn!("Can't record input while playing back a recording.");
+            return;
+        }
+        // Playback starts from an empty accumulator and without pending input, so recording does too.
+        self.fixed_timestep.reset();
+        let mut input = self.resources.get_mut::<Input>().unwrap();
+        input.clear();
+        input.start_recording();
+        self.recording = Some(InputRecording::default());
+    }
+
+    /// Stops recording and returns the recording, or `None` if nothing was being recorded.
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        let mut input = self.resources.get_mut::<Input>().unwrap();
+        input.stop_recording();
+        self.recording.take()
+    }
+
+    /// Returns true while input is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Feeds a recording back one frame at a time instead of winit and gamepad events.
+    /// Each frame runs with the recorded frame time, so the scene sees the same fixed updates with the same input.
+    /// Live input resumes once the recording ends.
+    pub fn play_recording(&mut self, recording: InputRecording) {
+        if self.recording.is_some() {
+            log::warn!("Stopped recording input to play back a recording.");
+            self.stop_recording();
+        }
+        self.fixed_timestep.reset();
+        let mut input = self.resources.get_mut::<Input>().unwrap();
+        input.reset();
+        // The recording only contains input that reached the game.
+        input.set_ui_capture(false, false);
+        self.playback = Some(recording.frames.into());
+    }
+
+    /// Stops playback and switches back to live input.
+    pub fn stop_playback(&mut self) {
+        if self.playback.take().is_some() {
+            let mut input = self.resources.get_mut::<Input>().unwrap();
+            input.reset();
+        }
+    }
+
+    /// Returns true while a recording is being played back.
+    pub fn is_playing_back(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Changes the time in seconds between scene updates.
+    /// Timesteps that are zero, negative or not finite log an error and use 60 updates per second instead.
+    pub fn set_fixed_timestep(&mut self, fixed_timestep: f32) {
+        self.fixed_timestep.set_timestep(fixed_timestep);
+        self.config.fixed_timestep = self.fixed_timestep.timestep();
+    }
+
     /// A function to help get the actual screen size as a LogicalSize<f32>
+    /// When running headless this is the size of the offscreen frame.
     pub fn get_window_actual_size(&self) -> winit::dpi::LogicalSize<f32> {
-        let size = self.renderer.window.inner_size();
+        let size = match self.renderer.window.as_ref() {
+            Some(window) => window.inner_size(),
+            None => self.renderer.size,
+        };
         winit::dpi::LogicalSize {
             width: size.width as f32,
             height: size.height as f32,
         }
     }
 
+    /// Captures the current frame as an RGBA image.
+    /// When running headless this reads back the frame rendered by the last `run_headless` call.
+    /// With a window the scene is rendered again into an offscreen target since swap chain textures can't be read back.
+    ///
+    /// *Note*: Headless frames include the UI since it's drawn into the headless target, frames captured with a window
+    /// only contain the scene. Like the main window, captures skip cameras that are shown in extra windows. To read back any other render target use `RenderTarget::read_rgba`.
+    pub fn capture_frame(&mut self) -> Option<image::RgbaImage> {
+        let target = self.capture_target();
+
+        let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+        let queue = self.resources.get::<Arc<wgpu::Queue>>().unwrap();
+        target.read_rgba(&device, &queue)
+    }
+
+    /// Captures the current frame and saves it to disk, see `capture_frame` for what the frame contains.
+    /// Paths ending in `.hdr` are saved as a radiance file, otherwise the format is picked from the extension(ex: `.png`).
+    pub fn save_frame<P: AsRef<Path>>(&mut self, path: P) -> image::ImageResult<()> {
+        let path = path.as_ref();
+        let is_hdr = path.extension().map_or(false, |ext| ext == "hdr");
+
+        let target = self.capture_target();
+
+        let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+        let queue = self.resources.get::<Arc<wgpu::Queue>>().unwrap();
+        let result = if is_hdr {
+            target
+                .read_hdr(&device, &queue)
+                .map(|image| graphics::resources::save_hdr(&image, path))
+        } else {
+            target.read_rgba(&device, &queue).map(|image| image.save(path))
+        };
+
+        result.unwrap_or_else(|| {
+            Err(image::ImageError::IoError(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Couldn't read back the current frame.",
+            )))
+        })
+    }
+
+    // The target frames are captured from, the headless target or the scene rendered offscreen when there is a window.
+    fn capture_target(&mut self) -> Arc<RenderTarget> {
+        // The headless target is written by the frame that's rendering.
+        self.wait_for_render();
+        match self.renderer.headless_target() {
+            Some(target) => target,
+            None => self.render_offscreen(),
+        }
+    }
+
+    // Renders the current scene into a new offscreen target the size of the frame.
+    fn render_offscreen(&mut self) -> Arc<RenderTarget> {
+        let target = {
+            let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+            let sc_desc = self.resources.get::<wgpu::SwapChainDescriptor>().unwrap();
+            Arc::new(RenderTarget::new(
+                &device,
+                sc_desc.width as f32,
+                sc_desc.height as f32,
+                1,
+                1,
+                sc_desc.format,
+                wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+            ))
+        };
+
+        self.resources
+            .insert(Arc::new(FrameTexture::Offscreen(target.clone())));
+        self.extract_render_world();
+        // Same as the main window, cameras shown in extra windows aren't part of the frame.
+        self.render_world
+            .as_mut()
+            .unwrap()
+            .deactivate_cameras(&self.renderer.window_cameras());
+        self.render_pass(RenderPass::Main);
+
+        target
+    }
+
+    /// The snapshot of the scene the last frame rendered, waits for the frame if it's still rendering.
+    pub fn render_world(&mut self) -> &RenderWorld {
+        self.wait_for_render();
+        self.render_world.as_ref().unwrap()
+    }
+
+    /// Waits for the frame that's rendering on the render thread and gives its resources back to `resources`.
+    /// Frames render while the next frame updates, call this before using a resource render systems use(ex: the
+    /// `PipelineManager`) from `AppState::update` or game systems.
+    pub fn wait_for_render(&mut self) {
+        if let Some(mut job) = self.render_thread.wait() {
+            for render_resource in self.render_resources.iter() {
+                render_resource.receive(&mut job.resources, &mut self.resources);
+            }
+            self.render_world = Some(job.world);
+        }
+    }
+
+    // Copies the scene into the render world, must be called after waiting for the render thread.
+    fn extract_render_world(&mut self) {
+        self.render_world
+            .as_mut()
+            .unwrap()
+            .extract(&mut self.current_scene.world);
+    }
+
+    // Hands the render world and render resources to the render thread without waiting for the pass.
+    fn start_render_pass(&mut self, pass: RenderPass) {
+        let mut resources = Resources::default();
+        for render_resource in self.render_resources.iter() {
+            render_resource.send(&mut self.resources, &mut resources);
+        }
+        self.render_thread.start(RenderJob {
+            pass,
+            world: self.render_world.take().unwrap(),
+            resources,
+        });
+    }
+
+    fn render_pass(&mut self, pass: RenderPass) {
+        self.start_render_pass(pass);
+        self.wait_for_render();
+    }
+
     /// Load's the entire application up. This also calls asset_manager.load and creates some default rendering pipelines.
     /// # Arguments
     ///
@@ -243,8 +713,10 @@ impl Application {
     where
         T: AppState,
     {
+        self.wait_for_render();
         {
-            let render_graph = RenderGraph::new(&mut self.resources, true);
+            let render_graph =
+                RenderGraph::new(&mut self.resources, Some(self.config.command_buffer_capacity));
             self.resources.insert(render_graph);
         }
 
@@ -289,7 +761,9 @@ impl Application {
         // Global Node
         {
             let mut pipeline_manager = self.resources.get_mut::<PipelineManager>().unwrap();
-            pipeline_manager.add_node("globals", vec!["shadow"]);
+            // Lights are uploaded after the shadow maps are assigned.
+            pipeline_manager.add_node("lighting", vec!["shadow"]);
+            pipeline_manager.add_node("globals", vec!["lighting"]);
         }
 
         // Create new pipelines
@@ -299,6 +773,11 @@ impl Application {
         // PBR pipeline
         super::graphics::pipelines::pbr::create(&self.resources);
 
+        // Plugin pipelines
+        for create in self.pipelines.drain(..) {
+            create(&self.resources);
+        }
+
         {
             let mut asset_manager = self.resources.get_mut::<AssetManager>().unwrap();
             asset_manager.load();
@@ -337,162 +816,525 @@ impl Application {
     ///
     /// * `app_state` - The app state you created which should implement the AppState trait.
     /// * `event` - The event data as a reference from winit.
-    /// * `control_flow` - a mutable reference to winit's control flow.
+    /// * `control_flow` - a mutable reference to winit's control flow. Set to `ControlFlow::Exit` when the window is
+    /// closed and `AppState::close_requested` returns true.
     ///
     pub fn run<T>(
         &mut self,
         app_state: &mut T,
         event: &Event<'_, ()>,
-        _control_flow: &mut ControlFlow, // TODO: Figure out if we actually will use this...
+        control_flow: &mut ControlFlow,
     ) where
         T: AppState,
     {
         {
             let mut input = self.resources.get_mut::<Input>().unwrap();
-            input.update_events(event);
+            // Recorded input replaces winit's events during playback.
+            if self.playback.is_none() {
+                // Imgui decides what it wants while building the previous frame's UI.
+                let io = self.imgui.io();
+                input.set_ui_capture(io.want_capture_mouse, io.want_capture_keyboard);
+                input.update_events(event);
+            }
         }
 
         match event {
             Event::MainEventsCleared => {
-                let mut frame_time = self.clock.elapsed().as_secs_f32() - self.elapsed_time;
-                self.frame_time = frame_time * 1000.0;
-                {
-                    self.last_frame = self.imgui.io_mut().update_delta_time(self.last_frame);
+                let frame_time = self.clock.elapsed().as_secs_f32() - self.elapsed_time;
+                self.frame(app_state, frame_time);
+
+                if let Some(window) = self.renderer.window.as_ref() {
+                    window.request_redraw();
                 }
+            }
+            Event::WindowEvent { window_id, event }
+                if self.renderer.get_window(*window_id).is_some() =>
+            {
+                self.handle_extra_window_event(app_state, *window_id, event);
+            }
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::Resized(size) => {
+                    self.resize_window(app_state, *size);
+                }
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    new_inner_size,
+                } => {
+                    self.resize_window(app_state, **new_inner_size);
+                    app_state.scale_factor_changed(self, *scale_factor);
+                }
+                WindowEvent::CloseRequested => {
+                    if app_state.close_requested(self) {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                }
+                WindowEvent::Focused(focused) => {
+                    self.focused = *focused;
+                    app_state.focus_changed(self, *focused);
+                }
+                WindowEvent::DroppedFile(path) => {
+                    app_state.file_dropped(self, path.clone());
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+        if let Some(window) = self.renderer.window.as_ref() {
+            self.platform
+                .handle_event(self.imgui.io_mut(), window, &event);
+        }
+    }
+
+    fn handle_extra_window_event<T>(&mut self, app_state: &mut T, window_id: WindowId, event: &WindowEvent<'_>)
+    where
+        T: AppState,
+    {
+        match event {
+            WindowEvent::Resized(size) => {
+                let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+                self.renderer.resize_window(window_id, *size, &device);
+            }
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+                self.renderer
+                    .resize_window(window_id, **new_inner_size, &device);
+            }
+            WindowEvent::CloseRequested => {
+                self.close_window(window_id);
+                app_state.window_closed(self, window_id);
+            }
+            WindowEvent::Focused(focused) => {
+                app_state.window_focus_changed(self, window_id, *focused);
+            }
+            WindowEvent::DroppedFile(path) => {
+                app_state.window_file_dropped(self, window_id, path.clone());
+            }
+            _ => (),
+        }
+    }
+
+    fn resize_window<T>(&mut self, app_state: &mut T, size: winit::dpi::PhysicalSize<u32>)
+    where
+        T: AppState,
+    {
+        self.wait_for_render();
+        let was_minimized = self.renderer.is_minimized();
+        {
+            let mut sc_desc = self
+                .resources
+                .get_mut::<wgpu::SwapChainDescriptor>()
+                .unwrap();
 
-                while frame_time > 0.0 {
-                    self.delta_time = f32::min(frame_time, self.fixed_timestep);
+            sc_desc.width = size.width;
+            sc_desc.height = size.height;
+        }
 
-                    self.current_scene
-                        .update(self.delta_time, &mut self.resources);
+        // Recreates the swap chain and depth buffer.
+        self.renderer.resize(&mut self.resources);
 
-                    {
-                        let mut input = self.resources.get_mut::<Input>().unwrap();
-                        input.clear();
-                    }
+        // Windows report a size of zero while minimized.
+        match (was_minimized, self.renderer.is_minimized()) {
+            (false, true) => app_state.minimized(self),
+            (true, false) => app_state.restored(self),
+            _ => (),
+        }
 
-                    app_state.update_ui(self);
+        app_state.resize(self);
+    }
 
-                    frame_time -= self.delta_time;
-                    self.elapsed_time += self.delta_time;
-                }
+    /// Updates and renders a single frame when the application was created with `new_headless`.
+    /// Each call advances the scene by exactly one fixed timestep so the output is reproducible.
+    /// Headless frames always show the state after the latest fixed update without interpolation.
+    /// The rendered frame can be found in `renderer.headless_target()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_state` - The app state you created which should implement the AppState trait.
+    ///
+    pub fn run_headless<T>(&mut self, app_state: &mut T)
+    where
+        T: AppState,
+    {
+        self.update_frame(app_state, self.config.fixed_timestep);
+        self.resources.insert(InterpolationAlpha(1.0));
+        self.render_frame(app_state);
 
-                self.platform
-                    .prepare_frame(self.imgui.io_mut(), &self.renderer.window)
-                    .expect("Failed to prepare frame");
-                let mut ui = self.imgui.frame();
+        // Wait for the GPU so the offscreen target is ready to be read.
+        self.wait_for_render();
+        let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+        device.poll(wgpu::Maintain::Wait);
+    }
 
-                // Store current frame buffer.
-                {
-                    let output = Arc::new(self.renderer.render().output);
-                    self.resources.insert(output);
+    fn frame<T>(&mut self, app_state: &mut T, frame_time: f32)
+    where
+        T: AppState,
+    {
+        self.update_frame(app_state, frame_time);
+        self.render_frame(app_state);
+    }
+
+    // Runs as many fixed updates as fit in the accumulated time followed by the per frame updates.
+    fn update_frame<T>(&mut self, app_state: &mut T, frame_time: f32)
+    where
+        T: AppState,
+    {
+        // Elapsed time follows the clock even during playback, it's used to measure the next frame.
+        self.elapsed_time += frame_time;
+        {
+            self.last_frame = self.imgui.io_mut().update_delta_time(self.last_frame);
+        }
+
+        let frame_time = match self.playback.as_mut().map(|playback| playback.pop_front()) {
+            // Playback replaces the frame's input and time so the same fixed updates run.
+            Some(Some(frame)) => {
+                let mut input = self.resources.get_mut::<Input>().unwrap();
+                for change in frame.changes {
+                    input.apply(change);
+                }
+                frame.frame_time
+            }
+            Some(None) => {
+                log::info!("Finished playing back recorded input.");
+                self.playback = None;
+                // Release whatever the recording left held down.
+                self.resources.get_mut::<Input>().unwrap().reset();
+                frame_time
+            }
+            None => {
+                if let Some(gamepad_backend) = self.gamepad_backend.as_mut() {
+                    let mut events = Vec::new();
+                    gamepad_backend.poll(&mut events);
+                    let mut input = self.resources.get_mut::<Input>().unwrap();
+                    input.update_gamepad_events(&events);
                 }
+                frame_time
+            }
+        };
+        self.frame_time = frame_time * 1000.0;
 
-                // First update our probes if we need to.
-                {
-                    self.probe_manager
-                        .render(&mut self.resources, &mut self.current_scene);
+        if let Some(recording) = self.recording.as_mut() {
+            let mut input = self.resources.get_mut::<Input>().unwrap();
+            recording.frames.push(RecordedFrame {
+                frame_time,
+                changes: input.take_recorded_changes(),
+            });
+        }
+
+        // Fixed updates see the input since the last fixed update, frames without one keep it for the next.
+        self.resources.get_mut::<Input>().unwrap().begin_fixed_update();
+        let fixed_timestep = self.fixed_timestep.timestep();
+        for _ in 0..self.fixed_timestep.advance(frame_time) {
+            self.delta_time = fixed_timestep;
+            self.current_scene
+                .update(fixed_timestep, &mut self.resources);
+
+            app_state.fixed_update(self);
+            self.resources.get_mut::<Input>().unwrap().end_fixed_update();
+        }
+
+        self.resources
+            .insert(InterpolationAlpha(self.fixed_timestep.alpha()));
+
+        self.resources.get_mut::<Input>().unwrap().begin_frame_update();
+        self.delta_time = frame_time;
+        app_state.update(self);
+        app_state.update_ui(self);
+
+        {
+            let mut input = self.resources.get_mut::<Input>().unwrap();
+            if let Some(gamepad_backend) = self.gamepad_backend.as_mut() {
+                for (gamepad, rumble) in input.take_rumbles() {
+                    gamepad_backend.rumble(gamepad, rumble);
                 }
+            }
+            input.end_frame_update();
+        }
+    }
+
+    // Reloads assets the asset manager's watcher saw change and rebuilds the pipelines of reloaded shaders.
+    fn reload_assets(&mut self) {
+        let mut asset_manager = self.resources.get_mut::<AssetManager>().unwrap();
+        asset_manager.reload_changed();
+        let shaders = asset_manager.take_reloaded_shaders();
+        if shaders.is_empty() {
+            return;
+        }
 
-                // Allow user to render UI stuff.
-                let scale = self.renderer.window.scale_factor() as f32;
+        let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+        let gpu_resource_manager = self.resources.get::<Arc<GPUResourceManager>>().unwrap();
+        let mut pipeline_manager = self.resources.get_mut::<PipelineManager>().unwrap();
+        pipeline_manager.rebuild_pipelines(&shaders, &device, &asset_manager, &gpu_resource_manager);
+    }
 
-                {
-                    let mut performance_metrics = self.resources.get_mut::<crate::core::PerformanceMetrics>().unwrap();
-                    let input = self.resources.get::<crate::core::input::Input>().unwrap();
-                    performance_metrics.display(&mut ui, &input);
+    fn render_frame<T>(&mut self, app_state: &mut T)
+    where
+        T: AppState,
+    {
+        // The previous frame might still be rendering, the game update above ran alongside it.
+        self.wait_for_render();
+        if self.device_lost {
+            return;
+        }
+        self.reload_assets();
+
+        // Store current frame buffer.
+        let output = match self.renderer.render(&self.resources) {
+            Ok(Some(output)) => output,
+            Ok(None) => {
+                // Extra windows keep rendering while the main window is minimized.
+                if !self.renderer.windows.is_empty() {
+                    self.extract_render_world();
+                    self.render_extra_windows(app_state);
                 }
+                return;
+            }
+            Err(error) => {
+                log::error!("Lost the graphics device: {}", error);
+                self.device_lost = true;
+                app_state.device_lost(self);
+                return;
+            }
+        };
+        self.resources.insert(Arc::new(output));
 
-                app_state.draw_ui(
-                    &mut ui,
-                    Vec2::new(
-                        self.renderer.size.width as f32 / scale,
-                        self.renderer.size.height as f32 / scale,
-                    ),
-                );
+        if let Some(window) = self.renderer.window.as_ref() {
+            self.platform
+                .prepare_frame(self.imgui.io_mut(), window)
+                .expect("Failed to prepare frame");
+        }
+        let mut ui = self.imgui.frame();
 
-                // Draw UI.
-                {
-                    let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
-                    let frame = self.resources.get::<Arc<wgpu::SwapChainTexture>>().unwrap();
-                    let command_buffer_queue = self.resources.get::<CommandBufferQueue>().unwrap();
-                    let mut encoder: wgpu::CommandEncoder =
-                        device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                            label: Some("UI"),
-                        });
-
-                    if self.last_cursor != ui.mouse_cursor() {
-                        self.last_cursor = ui.mouse_cursor();
-                        self.platform.prepare_render(&ui, &self.renderer.window);
-                    }
+        // First update our probes if we need to.
+        {
+            self.probe_manager
+                .render(&mut self.resources, &mut self.current_scene);
+        }
 
-                    self.imgui_renderer
-                        .render(ui.render(), &device, &mut encoder, &frame.view)
-                        .expect("Rendering failed");
+        // Allow user to render UI stuff.
+        let scale = match self.renderer.window.as_ref() {
+            Some(window) => window.scale_factor() as f32,
+            None => 1.0,
+        };
 
-                    command_buffer_queue
-                        .push(CommandQueueItem {
-                            buffer: encoder.finish(),
-                            name: "UI".to_string(),
-                        })
-                        .unwrap();
+        {
+            let mut performance_metrics = self.resources.get_mut::<crate::core::PerformanceMetrics>().unwrap();
+            let input = self.resources.get::<crate::core::input::Input>().unwrap();
+            performance_metrics.display(&mut ui, &input);
+        }
+
+        app_state.draw_ui(
+            &mut ui,
+            Vec2::new(
+                self.renderer.size.width as f32 / scale,
+                self.renderer.size.height as f32 / scale,
+            ),
+        );
+
+        // Draw UI.
+        {
+            let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+            let frame = self.resources.get::<Arc<FrameTexture>>().unwrap();
+            let command_buffer_queue = self.resources.get::<CommandBufferQueue>().unwrap();
+            let mut encoder: wgpu::CommandEncoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("UI"),
+                });
+
+            let relative_mouse = self.resources.get::<Input>().unwrap().relative_mouse;
+            // Imgui would show the cursor again.
+            if !relative_mouse && self.last_cursor != ui.mouse_cursor() {
+                self.last_cursor = ui.mouse_cursor();
+                if let Some(window) = self.renderer.window.as_ref() {
+                    self.platform.prepare_render(&ui, window);
                 }
+            }
 
-                // Next render's our scene.
-                self.render_schedule
-                    .execute(&mut self.current_scene.world, &mut self.resources);
+            self.imgui_renderer
+                .render(ui.render(), &device, &mut encoder, frame.view())
+                .expect("Rendering failed");
+
+            command_buffer_queue
+                .push(CommandQueueItem {
+                    buffer: encoder.finish(),
+                    name: "UI".to_string(),
+                })
+                .unwrap();
+        }
 
-                // We need to let the swap drop so the frame renderers.
-                let _swap_chain_output = self
-                    .resources
-                    .remove::<Arc<wgpu::SwapChainTexture>>()
-                    .unwrap();
+        // Next render's our scene.
+        // Render systems only see a snapshot of the scene, taken after the updates for this frame.
+        self.extract_render_world();
+        self.render_world
+            .as_mut()
+            .unwrap()
+            .deactivate_cameras(&self.renderer.window_cameras());
+        // The render thread presents the frame, the next frame's update runs while it renders.
+        self.start_render_pass(RenderPass::Main);
+
+        self.render_extra_windows(app_state);
+    }
+
+    fn render_extra_windows<T>(&mut self, app_state: &mut T)
+    where
+        T: AppState,
+    {
+        let window_ids = self.renderer.windows.keys().copied().collect::<Vec<_>>();
+        // Windows swap their depth buffer and frame size into the resources, the main window's pass has to finish first.
+        if !window_ids.is_empty() {
+            self.wait_for_render();
+        }
+        for window_id in window_ids {
+            self.render_extra_window(app_state, window_id);
+        }
+    }
+
+    // Renders the frame's snapshot again using the window's camera, swap chain and depth buffer.
+    // Only the camera's passes run, shadow maps and transform buffers from the main window's pass are reused.
+    fn render_extra_window<T>(&mut self, app_state: &mut T, window_id: WindowId)
+    where
+        T: AppState,
+    {
+        if self.device_lost {
+            return;
+        }
 
-                self.renderer.window.request_redraw();
+        let output = {
+            let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
+            self.renderer.render_window(window_id, &device)
+        };
+        let output = match output {
+            Ok(Some(output)) => output,
+            Ok(None) => return,
+            Err(error) => {
+                log::error!("Lost the graphics device: {}", error);
+                self.device_lost = true;
+                app_state.device_lost(self);
+                return;
             }
-            Event::WindowEvent {
-                event: winit::event::WindowEvent::Resized(size),
-                ..
-            } => {
-                {
-                    let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
-                    let mut sc_desc = self
-                        .resources
-                        .get_mut::<wgpu::SwapChainDescriptor>()
-                        .unwrap();
+        };
 
-                    sc_desc.width = size.width;
-                    sc_desc.height = size.height;
-                    self.renderer.size = *size;
-                    self.renderer.swap_chain =
-                        device.create_swap_chain(&self.renderer.surface, &sc_desc);
-                }
+        let window = self.renderer.windows.get_mut(&window_id).unwrap();
+        self.render_world
+            .as_mut()
+            .unwrap()
+            .select_camera(window.camera.unwrap());
 
-                // Resize depth buffer too
-                let depth_texture = {
-                    let device = self.resources.get::<Arc<wgpu::Device>>().unwrap();
-                    device.create_texture(&wgpu::TextureDescriptor {
-                        size: wgpu::Extent3d {
-                            width: size.width,
-                            height: size.height,
-                            depth: 1,
-                        },
-                        mip_level_count: 1,
-                        sample_count: 1,
-                        dimension: wgpu::TextureDimension::D2,
-                        format: DEPTH_FORMAT,
-                        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-                        label: None,
-                    })
-                };
-                self.resources
-                    .insert(DepthTexture(depth_texture.create_default_view()));
-
-                app_state.resize(self);
+        // Systems read the depth buffer and frame size from resources, swap in the window's own for this pass.
+        {
+            let mut depth_texture = self.resources.get_mut::<DepthTexture>().unwrap();
+            let mut sc_desc = self.resources.get_mut::<wgpu::SwapChainDescriptor>().unwrap();
+            std::mem::swap(&mut *depth_texture, &mut window.depth_texture);
+            std::mem::swap(&mut *sc_desc, &mut window.sc_desc);
+        }
+        self.resources.insert(Arc::new(output));
+
+        self.render_pass(RenderPass::Window);
+
+        let window = self.renderer.windows.get_mut(&window_id).unwrap();
+        let mut depth_texture = self.resources.get_mut::<DepthTexture>().unwrap();
+        let mut sc_desc = self.resources.get_mut::<wgpu::SwapChainDescriptor>().unwrap();
+        std::mem::swap(&mut *depth_texture, &mut window.depth_texture);
+        std::mem::swap(&mut *sc_desc, &mut window.sc_desc);
+    }
+}
+
+#[cfg(feature = "gilrs")]
+fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    match crate::core::input::GilrsBackend::new() {
+        Ok(backend) => Some(Box::new(backend)),
+        Err(error) => {
+            log::warn!("Gamepads aren't available: {}", error);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "gilrs"))]
+fn default_gamepad_backend() -> Option<Box<dyn GamepadBackend>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppState, Application};
+    use crate::{
+        core::input::{Input, InputChange},
+        testing::APPLICATION_LOCK,
+        AppConfig, HarmonyError,
+    };
+    use winit::{dpi::PhysicalSize, event::VirtualKeyCode};
+
+    const ASSET_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/");
+
+    // Logs the delta time and the state of W seen by every fixed update.
+    #[derive(Default)]
+    struct FixedUpdateLog {
+        updates: Vec<(f32, bool, bool, bool)>,
+    }
+
+    impl AppState for FixedUpdateLog {
+        fn fixed_update(&mut self, app: &mut Application) {
+            let input = app.resources.get::<Input>().unwrap();
+            self.updates.push((
+                app.delta_time,
+                input.is_key_pressed(VirtualKeyCode::W),
+                input.is_key_down(VirtualKeyCode::W),
+                input.is_key_released(VirtualKeyCode::W),
+            ));
+        }
+    }
+
+    #[test]
+    fn should_replay_recording_with_the_same_fixed_updates() {
+        let _lock = APPLICATION_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        let mut app = match Application::new_headless(
+            PhysicalSize::new(64, 64),
+            ASSET_PATH,
+            vec![],
+            AppConfig::default(),
+        ) {
+            Ok(app) => app,
+            Err(HarmonyError::NoAdapter) => {
+                eprintln!("Skipping should_replay_recording_with_the_same_fixed_updates, no GPU adapter is available.");
+                return;
             }
-            _ => (),
+            Err(error) => panic!("Failed to create the application: {:?}", error),
+        };
+        let mut log = FixedUpdateLog::default();
+
+        // Leaves time in the accumulator which must not leak into the recording.
+        app.update_frame(&mut log, 0.01);
+        app.start_recording();
+        let frames = [
+            (0.007, Some(true)),
+            (0.012, None),
+            (0.03, Some(false)),
+            (0.005, None),
+            (0.02, None),
+        ];
+        log.updates.clear();
+        for (frame_time, key) in frames.iter() {
+            if let Some(pressed) = key {
+                let mut input = app.resources.get_mut::<Input>().unwrap();
+                input.apply(InputChange::Key(VirtualKeyCode::W, *pressed));
+            }
+            app.update_frame(&mut log, *frame_time);
+        }
+        let recording = app.stop_recording().unwrap();
+        let recorded = std::mem::take(&mut log.updates);
+
+        app.update_frame(&mut log, 0.01);
+        app.play_recording(recording);
+        log.updates.clear();
+        // Playback uses the recorded frame times, the last frame ends the playback.
+        for _ in 0..=frames.len() {
+            app.update_frame(&mut log, 0.0);
         }
-        self.platform
-            .handle_event(self.imgui.io_mut(), &self.renderer.window, &event);
+        assert!(!app.is_playing_back());
+
+        assert_eq!(log.updates, recorded);
+        // Each edge reaches exactly one fixed update.
+        assert_eq!(recorded.iter().filter(|update| update.1).count(), 1);
+        assert_eq!(recorded.iter().filter(|update| update.3).count(), 1);
     }
 }
@@ -1,28 +1,64 @@
 use super::{
+    asset_watcher::AssetWatcher,
     file_manager::{AssetHandle, FileManager},
-    material::Material,
-    material_manager::MaterialManager,
-    mesh::Gltf,
-    mesh_manager::MeshManager,
+    import_settings::read_settings,
+    loader::{AssetLoader, LoaderError, Loaders, TryFromLoader},
+    material::{Material, MaterialLoader, PBRMaterialRon},
+    mesh::{Gltf, GltfLoader},
     shader::Shader,
     shader_manager::ShaderManager,
     texture::Texture,
     texture_manager::TextureManager,
+    AssetSource, BundleSource, DirectorySource, OverlaySource,
 };
-use crate::graphics::resources::GPUResourceManager;
-use legion::{prelude::Resources, systems::resource::Resource};
-use std::{any::TypeId, convert::TryFrom, fmt::Debug, path::PathBuf, sync::Arc};
-use walkdir::WalkDir;
+use crate::{graphics::resources::GPUResourceManager, HarmonyError};
+use serde::de::DeserializeOwned;
+use std::{
+    convert::TryFrom,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+// Textures harmony needs to render, relative to the asset path.
+const CORE_TEXTURES: [&str; 6] = [
+    "core/white.png",
+    "core/black.png",
+    "core/empty_normal.png",
+    "core/pbr_flat.png",
+    "core/mie.hdr",
+    "core/rayleigh.hdr",
+];
+
+// Shaders used by harmony's built in pipelines, relative to the asset path.
+const CORE_SHADERS: [&str; 14] = [
+    "core/shaders/pbr.shader",
+    "core/shaders/shadow.shader",
+    "core/shaders/shadow_uniform.shader",
+    "core/shaders/skybox.shader",
+    "core/shaders/unlit.shader",
+    "core/shaders/line.shader",
+    "core/shaders/sky/sky.shader",
+    "core/shaders/clustered/froxels.shader",
+    "core/shaders/clustered/light_culling.shader",
+    "core/shaders/calculations/hdr_to_cubemap.shader",
+    "core/shaders/calculations/irradiance2.shader",
+    "core/shaders/calculations/mipmap.shader",
+    "core/shaders/calculations/specular2.shader",
+    "core/shaders/calculations/specular_brdf.shader",
+];
 
 pub struct AssetManager {
-    loaders: Resources,
+    loaders: Arc<Loaders>,
     texture_manager: Arc<TextureManager>,
     shader_manager: Arc<ShaderManager>,
-    mesh_manager: Arc<MeshManager>,
     device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
     path: PathBuf,
+    source: Arc<OverlaySource>,
     gpu_resource_manager: Arc<GPUResourceManager>,
+    watcher: Option<AssetWatcher>,
+    reloaded_shaders: Vec<PathBuf>,
 }
 
 impl AssetManager {
@@ -32,37 +68,42 @@ impl AssetManager {
         queue: Arc<wgpu::Queue>,
         gpu_resource_manager: Arc<GPUResourceManager>,
     ) -> Self {
-        let texture_manager = Arc::new(TextureManager::new(device.clone(), queue.clone()));
-        let shader_manager = Arc::new(ShaderManager::new(device.clone()));
-        let mut loaders = Resources::default();
-
-        let material_manager = Arc::new(MaterialManager::new(
+        let source = Arc::new(OverlaySource::new(Arc::new(DirectorySource::new(path.clone()))));
+        let texture_manager = Arc::new(TextureManager::new(
             device.clone(),
             queue.clone(),
-            texture_manager.clone(),
-            gpu_resource_manager.clone(),
-            path.clone(),
+            source.clone(),
         ));
-        let mesh_manager = Arc::new(MeshManager::new(device.clone(), material_manager.clone()));
+        let shader_manager = Arc::new(ShaderManager::new(device.clone(), source.clone()));
+        let loaders = Arc::new(Loaders::new(texture_manager.clone()));
 
-        loaders.insert(material_manager);
-        Self {
+        let mut asset_manager = Self {
             loaders,
             texture_manager,
             shader_manager,
-            mesh_manager,
-            device,
+            device: device.clone(),
             queue,
             path,
+            source,
             gpu_resource_manager,
-        }
+            watcher: None,
+            reloaded_shaders: Vec::new(),
+        };
+        asset_manager.register_material::<PBRMaterialRon>();
+        asset_manager.register_loader(GltfLoader::new(device));
+        asset_manager
     }
 
     pub fn load(&mut self) {
-        for entry in WalkDir::new(&self.path) {
-            let entry = entry.expect("Error: Could not access asset directory.");
-            let file_name = entry.file_name().to_str().unwrap().to_string();
-            let path = entry.into_path();
+        let files = self
+            .source
+            .list(Path::new(""))
+            .expect("Error: Could not access asset directory.");
+        for path in files {
+            let file_name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(file_name) => file_name.to_string(),
+                None => continue,
+            };
             if file_name.ends_with(".png")
                 || file_name.ends_with(".jpg")
                 || file_name.ends_with(".hdr")
@@ -76,48 +117,164 @@ impl AssetManager {
         }
     }
 
-    pub fn register<T: Resource + TryFrom<(PathBuf, Vec<u8>)>>(&mut self) {
-        if self.loaders.contains::<FileManager<T>>() {
-            log::warn!("Duplicate registration of key: {:?}", TypeId::of::<T>());
+    /// Makes sure harmony's core assets exist and compiles the core shaders up front.
+    pub(crate) fn load_core_assets(&self) -> Result<(), HarmonyError> {
+        for texture in CORE_TEXTURES.iter() {
+            if !self.source.exists(Path::new(texture)) {
+                return Err(HarmonyError::MissingCoreAsset(self.path.join(texture)));
+            }
+        }
+
+        for shader in CORE_SHADERS.iter() {
+            if !self.source.exists(Path::new(shader)) {
+                return Err(HarmonyError::MissingCoreAsset(self.path.join(shader)));
+            }
+            self.shader_manager.load(*shader)?;
+        }
+
+        Ok(())
+    }
+
+    /// Registers a loader for assets of type `L::Asset`, load them with `get`.
+    pub fn register_loader<L: AssetLoader>(&mut self, loader: L) {
+        if self.loaders.contains::<L::Asset>() {
+            log::warn!(
+                "Duplicate registration of key: {}",
+                std::any::type_name::<L::Asset>()
+            );
             return;
         }
 
-        let loader = FileManager::<T>::new();
-        self.loaders.insert(loader);
+        let file_manager = FileManager::with_loader(loader, self.source.clone())
+            .with_loaders(Arc::downgrade(&self.loaders));
+        self.loaders.insert(Arc::new(file_manager));
+    }
+
+    /// Registers an asset type that's created from a file's data with `TryFrom`.
+    pub fn register<T: TryFrom<(PathBuf, Vec<u8>)> + Send + Sync + 'static>(&mut self) {
+        self.register_loader(TryFromLoader::<T>::new());
     }
 
+    /// Registers a material type, its files are loaded with `get_material`.
     pub fn register_material<
         T: TryFrom<(PathBuf, Vec<u8>)> + Debug + Material + Send + Sync + 'static,
     >(
         &mut self,
     ) {
-        if self.loaders.contains::<Arc<MaterialManager<T>>>() {
-            log::warn!(
-                "Duplicate registration of material key: {:?}",
-                TypeId::of::<T>()
-            );
-            return;
-        }
-
-        let loader = MaterialManager::<T>::new(
+        self.register_loader(MaterialLoader::<T>::new(
             self.device.clone(),
-            self.queue.clone(),
-            self.texture_manager.clone(),
             self.gpu_resource_manager.clone(),
-            self.path.clone(),
-        );
-        self.loaders.insert(Arc::new(loader));
+        ));
+    }
+
+    /// Mounts a source over the asset folder and the sources mounted before it, ex: a mod folder.
+    /// Files in the mounted source are used instead of files with the same path in earlier sources.
+    /// Assets that were already loaded keep their data until they're reloaded.
+    pub fn mount(&self, source: Arc<dyn AssetSource>) {
+        self.source.mount(source);
+    }
+
+    /// Mounts a bundle written by `BundleBuilder` over the asset folder and the sources mounted before it.
+    /// Assets in the bundle are loaded with the paths they had in the packed folder.
+    pub fn mount_bundle<P: Into<PathBuf>>(&self, path: P) -> Result<(), HarmonyError> {
+        let path = path.into();
+        let bundle = BundleSource::open(path.clone())
+            .map_err(|error| HarmonyError::AssetBundle { path: path.clone(), error })?;
+        log::info!("Mounted bundle {:?} with {} files.", path, bundle.entries().count());
+        self.mount(Arc::new(bundle));
+        Ok(())
+    }
+
+    /// Returns the source every asset is read from.
+    pub fn source(&self) -> Arc<dyn AssetSource> {
+        self.source.clone()
+    }
+
+    /// Reads the import settings of an asset from its `.meta` sidecar file, ex: `TextureSettings` for a texture.
+    /// Returns the default settings when the asset doesn't have a sidecar file.
+    pub fn import_settings<S: DeserializeOwned + Default, K: Into<PathBuf>>(&self, path: K) -> Result<S, LoaderError> {
+        read_settings(self.source.as_ref(), &path.into())
+    }
+
+    /// Starts watching the asset folder. Changed files are reloaded once per frame and existing handles
+    /// resolve to the new data, pipelines are rebuilt when their shader changes.
+    pub fn watch(&mut self) -> Result<(), HarmonyError> {
+        if self.watcher.is_none() {
+            self.watcher = Some(AssetWatcher::new(&self.path)?);
+            log::info!("Watching {:?} for changes.", self.path);
+        }
+        Ok(())
+    }
+
+    /// Stops watching the asset folder.
+    pub fn unwatch(&mut self) {
+        self.watcher = None;
+    }
+
+    /// Returns true while the asset folder is watched for changes.
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Reloads every asset loaded from `path`(relative to the asset path) and the assets that depend on it,
+    /// ex: materials using a texture. Returns false if nothing was loaded from the file.
+    pub fn reload<K: Into<PathBuf>>(&mut self, path: K) -> bool {
+        let path = path.into();
+
+        // Textures are reloaded first so materials and meshes wait for the new texture.
+        let texture_path = self.texture_manager.reload(&path);
+        let mut reloaded = texture_path.is_some();
+
+        let is_shader_source = path.extension().map_or(false, |ext| ext == "glsl");
+        if is_shader_source {
+            let reloaded_shaders = self.shader_manager.reload_all();
+            reloaded |= !reloaded_shaders.is_empty();
+            self.add_reloaded_shaders(reloaded_shaders);
+        } else if let Some(shader) = self.shader_manager.reload(&path) {
+            self.add_reloaded_shaders(vec![shader]);
+            reloaded = true;
+        }
+
+        // A texture's `.meta` file reloads whatever uses the texture.
+        let path = texture_path.unwrap_or(path);
+        reloaded |= self.loaders.reload(&path);
+
+        if reloaded {
+            log::info!("Reloaded {:?}.", path);
+        }
+        reloaded
+    }
+
+    // Reloads the files the watcher reported since the last call, called by the application every frame.
+    pub(crate) fn reload_changed(&mut self) {
+        let changed = match self.watcher.as_ref() {
+            Some(watcher) => watcher.changed_files(),
+            None => return,
+        };
+        for path in changed {
+            self.reload(path);
+        }
+    }
+
+    // Returns the shaders reloaded since the last call, like `PipelineDesc::shader` they're relative to the asset path.
+    pub(crate) fn take_reloaded_shaders(&mut self) -> Vec<PathBuf> {
+        std::mem::replace(&mut self.reloaded_shaders, Vec::new())
+    }
+
+    fn add_reloaded_shaders(&mut self, shaders: Vec<PathBuf>) {
+        for shader in shaders {
+            if !self.reloaded_shaders.contains(&shader) {
+                self.reloaded_shaders.push(shader);
+            }
+        }
     }
 
     // Instantly returns Arc<AssetHandle<T>> from a path.
     // Note: You should only call `get` once per path.
     // TODO: Add better checking to make sure we don't load an asset more than once.
-    pub fn get<T: Resource + TryFrom<(PathBuf, Vec<u8>)>, K: Into<PathBuf>>(
-        &self,
-        path: K,
-    ) -> Arc<AssetHandle<T>> {
-        let path = self.path.join(path.into());
-        let loader = self.loaders.get::<FileManager<T>>();
+    pub fn get<T: Send + Sync + 'static, K: Into<PathBuf>>(&self, path: K) -> Arc<AssetHandle<T>> {
+        let path = path.into();
+        let loader = self.loaders.get::<T>();
 
         if loader.is_none() {
             panic!("Couldn't find asset loader for the requested file.");
@@ -130,19 +287,18 @@ impl AssetManager {
 
     // Instantly returns Arc<AssetHandle<Texture>> from a path.
     pub fn get_texture<K: Into<PathBuf>>(&self, path: K) -> Arc<AssetHandle<Texture>> {
-        let path = self.path.join(path.into());
+        let path = path.into();
         self.texture_manager.get(path)
     }
 
     // Instantly returns Arc<AssetHandle<Shader>> from a path.
     pub fn get_shader<K: Into<PathBuf>>(&self, path: K) -> Arc<AssetHandle<Shader>> {
-        let path = self.path.join(path.into());
+        let path = path.into();
         self.shader_manager.get(path)
     }
 
     pub fn get_mesh<K: Into<PathBuf>>(&self, path: K) -> Arc<AssetHandle<Gltf>> {
-        let path = self.path.join(path.into());
-        self.mesh_manager.get(path)
+        self.get::<Gltf, _>(path)
     }
 
     // Instantly returns a Arc<AssetHandle<T::BindMaterialType>> from a path.
@@ -154,8 +310,8 @@ impl AssetManager {
         &self,
         path: K,
     ) -> Arc<AssetHandle<T::BindMaterialType>> {
-        let path = self.path.join(path.into());
-        let loader = self.loaders.get::<Arc<MaterialManager<T>>>();
+        let path = path.into();
+        let loader = self.loaders.get::<T::BindMaterialType>();
         if loader.is_none() {
             panic!("Couldn't find material asset loader for the requested file.");
         }
@@ -169,7 +325,7 @@ impl AssetManager {
     >(
         &self,
     ) -> Vec<Arc<AssetHandle<T::BindMaterialType>>> {
-        let loader = self.loaders.get::<Arc<MaterialManager<T>>>();
+        let loader = self.loaders.get::<T::BindMaterialType>();
         if loader.is_none() {
             panic!("Couldn't find material asset loader for the requested file.");
         }
@@ -229,16 +385,16 @@ mod tests {
         let pbr_bind_group_layout = create_pbr_bindgroup_layout(device.clone());
         gpu_resource_manager.add_bind_group_layout("pbr_material_layout", pbr_bind_group_layout);
 
-        let mut asset_manager = AssetManager::new(
-            PathBuf::from(""),
+        // `AssetManager::new` registers `PBRMaterialRon`.
+        let asset_manager = AssetManager::new(
+            PathBuf::from("./assets"),
             device.clone(),
             queue.clone(),
             gpu_resource_manager,
         );
 
-        asset_manager.register_material::<PBRMaterialRon>();
         let material_handle =
-            asset_manager.get_material::<PBRMaterialRon, _>("./assets/material.ron");
+            asset_manager.get_material::<PBRMaterialRon, _>("material.ron");
         let material = material_handle.get();
         assert!(match *material.err().unwrap() {
             AssetError::Loading => true,
@@ -0,0 +1,118 @@
+use crate::HarmonyError;
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+// How long file events are collected before they're reported, editors often write a file more than once.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(100);
+
+/// Watches the asset folder and reports which files changed.
+/// The watcher runs on its own thread since not every platform's watcher can be shared between threads.
+pub(crate) struct AssetWatcher {
+    root: PathBuf,
+    changes: Mutex<Receiver<PathBuf>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl AssetWatcher {
+    pub(crate) fn new(path: &Path) -> Result<Self, HarmonyError> {
+        let root = path
+            .canonicalize()
+            .map_err(|error| HarmonyError::AssetWatch(notify::Error::Io(error)))?;
+        let (change_sender, changes) = channel();
+        let (ready_sender, ready) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let watch_root = root.clone();
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            let (event_sender, events) = channel();
+            let mut watcher = match notify::watcher(event_sender, DEBOUNCE_DELAY)
+                .and_then(|mut watcher| watcher.watch(&watch_root, RecursiveMode::Recursive).map(|_| watcher))
+            {
+                Ok(watcher) => watcher,
+                Err(error) => {
+                    let _ = ready_sender.send(Err(error));
+                    return;
+                }
+            };
+            let _ = ready_sender.send(Ok(()));
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                let path = match events.recv_timeout(DEBOUNCE_DELAY) {
+                    Ok(DebouncedEvent::Create(path)) | Ok(DebouncedEvent::Write(path)) => path,
+                    Ok(DebouncedEvent::Rename(_, path)) => path,
+                    Ok(_) | Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                if change_sender.send(path).is_err() {
+                    break;
+                }
+            }
+            let _ = watcher.unwatch(&watch_root);
+        });
+
+        match ready.recv() {
+            Ok(Ok(())) => Ok(Self {
+                root,
+                changes: Mutex::new(changes),
+                stop,
+            }),
+            Ok(Err(error)) => Err(HarmonyError::AssetWatch(error)),
+            Err(_) => Err(HarmonyError::AssetWatch(notify::Error::Generic(
+                "The watcher thread stopped.".to_string(),
+            ))),
+        }
+    }
+
+    /// Returns the files that changed since the last call, relative to the asset folder.
+    pub(crate) fn changed_files(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for path in self.changes.lock().unwrap().try_iter() {
+            let path = path.canonicalize().unwrap_or(path);
+            let relative = match path.strip_prefix(&self.root) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => continue,
+            };
+            if path.is_file() && !changed.contains(&relative) {
+                changed.push(relative);
+            }
+        }
+        changed
+    }
+}
+
+impl Drop for AssetWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AssetWatcher;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_report_changed_files() {
+        let root = std::env::temp_dir().join("harmony_asset_watcher");
+        std::fs::create_dir_all(root.join("textures")).unwrap();
+        let watcher = AssetWatcher::new(&root).unwrap();
+
+        std::fs::write(root.join("textures/test.png.meta"), "(format: SRGB)").unwrap();
+        std::fs::write(root.join("textures/test.png.meta"), "(format: RGB)").unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        assert_eq!(watcher.changed_files(), vec![PathBuf::from("textures/test.png.meta")]);
+        assert!(watcher.changed_files().is_empty());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,428 @@
+use super::{source::normalize, AssetSource, DirectorySource};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+// A bundle starts with `MAGIC` and the format version followed by the file data. The index comes after
+// the data and the bundle ends with the offset and length of the index, so files can be packed one at a time.
+const MAGIC: &[u8; 8] = b"HARMONYB";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 12;
+const FOOTER_SIZE: u64 = 16;
+
+// Deflate level used when compressing files, same as zlib's default.
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// How a file is stored inside of a bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BundleCompression {
+    /// Stored as is, used for files that don't get smaller when compressed(ex: png files).
+    None,
+    /// Compressed with deflate.
+    Deflate,
+}
+
+/// Where a file is stored in a bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEntry {
+    /// Path of the file relative to the bundle's root, always separated by `/`.
+    pub path: String,
+    /// Offset of the stored data from the start of the bundle.
+    pub offset: u64,
+    /// Size of the stored data.
+    pub size: u64,
+    /// How the data is stored.
+    pub compression: BundleCompression,
+    /// Crc32 hash of the uncompressed file, checked every time the file is read.
+    pub hash: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundleIndex {
+    entries: Vec<BundleEntry>,
+}
+
+enum BundleFile {
+    Memory(Vec<u8>),
+    Disk(PathBuf),
+}
+
+/// Packs files into a single bundle file that can be mounted with `AssetManager::mount_bundle`.
+///
+/// ```ignore
+/// let mut builder = BundleBuilder::new();
+/// builder.add_directory("./assets")?;
+/// builder.write_to_file("./assets.bundle")?;
+/// ```
+pub struct BundleBuilder {
+    files: BTreeMap<String, BundleFile>,
+    compress: bool,
+}
+
+impl BundleBuilder {
+    pub fn new() -> Self {
+        Self {
+            files: BTreeMap::new(),
+            compress: true,
+        }
+    }
+
+    /// Sets whether files are compressed, defaults to true. Files that don't get smaller are always stored as is.
+    pub fn compress(&mut self, compress: bool) -> &mut Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Adds or replaces a file, `path` is the path used to load the file once the bundle is mounted.
+    pub fn add_file<P: AsRef<Path>, D: Into<Vec<u8>>>(&mut self, path: P, data: D) -> &mut Self {
+        self.files
+            .insert(bundle_path(path.as_ref()), BundleFile::Memory(data.into()));
+        self
+    }
+
+    /// Adds every file inside of a folder and its sub folders, paths in the bundle are relative to the folder.
+    /// Files are read when the bundle is written.
+    pub fn add_directory<P: AsRef<Path>>(&mut self, root: P) -> io::Result<&mut Self> {
+        let root = root.as_ref();
+        for path in DirectorySource::new(root).list(Path::new(""))? {
+            self.files
+                .insert(bundle_path(&path), BundleFile::Disk(root.join(&path)));
+        }
+        Ok(self)
+    }
+
+    /// Returns the number of files in the bundle.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns true if no files were added.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Writes the bundle.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        let mut offset = (MAGIC.len() + 4) as u64;
+
+        let mut entries = Vec::with_capacity(self.files.len());
+        for (path, file) in self.files.iter() {
+            let data = match file {
+                BundleFile::Memory(data) => data.clone(),
+                BundleFile::Disk(file_path) => std::fs::read(file_path)?,
+            };
+            let hash = crc32fast::hash(&data);
+
+            let compressed = if self.compress {
+                Some(miniz_oxide::deflate::compress_to_vec(&data, COMPRESSION_LEVEL))
+                    .filter(|compressed| compressed.len() < data.len())
+            } else {
+                None
+            };
+            let (data, compression) = match compressed {
+                Some(compressed) => (compressed, BundleCompression::Deflate),
+                None => (data, BundleCompression::None),
+            };
+
+            writer.write_all(&data)?;
+            entries.push(BundleEntry {
+                path: path.clone(),
+                offset,
+                size: data.len() as u64,
+                compression,
+                hash,
+            });
+            offset += data.len() as u64;
+        }
+
+        let index = ron::ser::to_string(&BundleIndex { entries })
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        writer.write_all(index.as_bytes())?;
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.write_all(&(index.len() as u64).to_le_bytes())?;
+        writer.flush()
+    }
+
+    /// Writes the bundle to a file.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl Default for BundleBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads assets from a bundle file written by `BundleBuilder`.
+pub struct BundleSource {
+    path: PathBuf,
+    file: Mutex<File>,
+    entries: HashMap<PathBuf, BundleEntry>,
+}
+
+impl BundleSource {
+    /// Opens a bundle and reads its index, the files are read when they're loaded.
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        let mut file = File::open(&path)?;
+
+        let mut header = [0u8; HEADER_SIZE as usize];
+        file.read_exact(&mut header)
+            .map_err(|_| invalid_bundle(&path, "the file is too small"))?;
+        if &header[..8] != MAGIC {
+            return Err(invalid_bundle(&path, "the file isn't a bundle"));
+        }
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if version != VERSION {
+            return Err(invalid_bundle(
+                &path,
+                &format!("unsupported version {}, expected {}", version, VERSION),
+            ));
+        }
+
+        let file_length = file.metadata()?.len();
+        if file_length < HEADER_SIZE + FOOTER_SIZE {
+            return Err(invalid_bundle(&path, "the file is too small"));
+        }
+        let mut footer = [0u8; FOOTER_SIZE as usize];
+        file.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        file.read_exact(&mut footer)?;
+        let mut index_offset = [0u8; 8];
+        let mut index_length = [0u8; 8];
+        index_offset.copy_from_slice(&footer[..8]);
+        index_length.copy_from_slice(&footer[8..]);
+        let index_offset = u64::from_le_bytes(index_offset);
+        let index_length = u64::from_le_bytes(index_length);
+
+        // Checked before allocating so a corrupted footer can't ask for more memory than the file holds.
+        let data_end = file_length - FOOTER_SIZE;
+        let index_fits = index_offset >= HEADER_SIZE
+            && index_offset
+                .checked_add(index_length)
+                .map_or(false, |index_end| index_end <= data_end);
+        if !index_fits {
+            return Err(invalid_bundle(&path, "the index is outside of the file"));
+        }
+
+        let mut index = vec![0u8; index_length as usize];
+        file.seek(SeekFrom::Start(index_offset))?;
+        file.read_exact(&mut index)?;
+        let index: BundleIndex = ron::de::from_bytes(&index)
+            .map_err(|error| invalid_bundle(&path, &format!("the index is invalid: {}", error)))?;
+
+        let entry_fits = |entry: &BundleEntry| {
+            entry.offset >= HEADER_SIZE
+                && entry
+                    .offset
+                    .checked_add(entry.size)
+                    .map_or(false, |entry_end| entry_end <= index_offset)
+        };
+        if let Some(entry) = index.entries.iter().find(|entry| !entry_fits(entry)) {
+            return Err(invalid_bundle(
+                &path,
+                &format!("{} is outside of the file's data", entry.path),
+            ));
+        }
+
+        let entries = index
+            .entries
+            .into_iter()
+            .map(|entry| (PathBuf::from(&entry.path), entry))
+            .collect();
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+            entries,
+        })
+    }
+
+    /// Returns the index entry of every file in the bundle.
+    pub fn entries(&self) -> impl Iterator<Item = &BundleEntry> {
+        self.entries.values()
+    }
+}
+
+impl AssetSource for BundleSource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let entry = match self.entries.get(&normalize(path)) {
+            Some(entry) => entry,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} isn't in {}.", path.display(), self.path.display()),
+                ))
+            }
+        };
+
+        let mut data = vec![0u8; entry.size as usize];
+        {
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(entry.offset))?;
+            file.read_exact(&mut data)?;
+        }
+
+        let data = match entry.compression {
+            BundleCompression::None => data,
+            BundleCompression::Deflate => miniz_oxide::inflate::decompress_to_vec(&data)
+                .map_err(|status| {
+                    invalid_bundle(
+                        &self.path,
+                        &format!("couldn't decompress {}: {:?}", entry.path, status),
+                    )
+                })?,
+        };
+
+        if crc32fast::hash(&data) != entry.hash {
+            return Err(invalid_bundle(
+                &self.path,
+                &format!("{} doesn't match its hash", entry.path),
+            ));
+        }
+        Ok(data)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.entries.contains_key(&normalize(path))
+    }
+
+    fn list(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let folder = normalize(path);
+        Ok(self
+            .entries
+            .keys()
+            .filter(|file| file.starts_with(&folder))
+            .cloned()
+            .collect())
+    }
+}
+
+// Paths are stored with `/` so bundles built on one platform can be read on others.
+fn bundle_path(path: &Path) -> String {
+    normalize(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn invalid_bundle(path: &Path, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid bundle {}: {}.", path.display(), message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BundleBuilder, BundleCompression, BundleSource};
+    use crate::assets::AssetSource;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn should_read_files_from_bundle() {
+        let path = std::env::temp_dir().join("harmony_read.bundle");
+        let mut builder = BundleBuilder::new();
+        builder
+            .add_file("./materials/red.ron", "(color: [1.0, 0.0, 0.0, 1.0])".repeat(8))
+            .add_file("core/empty.txt", "")
+            .add_directory("./assets/core/shaders")
+            .unwrap();
+        builder.write_to_file(&path).unwrap();
+
+        let bundle = BundleSource::open(&path).unwrap();
+        assert_eq!(bundle.entries().count(), builder.len());
+        assert_eq!(
+            bundle.read(Path::new("materials/red.ron")).unwrap(),
+            "(color: [1.0, 0.0, 0.0, 1.0])".repeat(8).into_bytes()
+        );
+        assert!(bundle.read(Path::new("core/empty.txt")).unwrap().is_empty());
+        assert_eq!(
+            bundle.read(Path::new("pbr.shader")).unwrap(),
+            std::fs::read("./assets/core/shaders/pbr.shader").unwrap()
+        );
+        assert!(bundle
+            .entries()
+            .any(|entry| entry.compression == BundleCompression::Deflate));
+        assert_eq!(
+            bundle.list(Path::new("materials")).unwrap(),
+            vec![PathBuf::from("materials/red.ron")]
+        );
+
+        let error = bundle.read(Path::new("missing.ron")).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_reject_corrupted_bundle() {
+        let path = std::env::temp_dir().join("harmony_corrupted.bundle");
+        let mut bundle = Vec::new();
+        BundleBuilder::new()
+            .compress(false)
+            .add_file("material.ron", "PBRMaterialRon()")
+            .write(&mut bundle)
+            .unwrap();
+        // The first file's data starts right after the header.
+        bundle[12] = b'X';
+        std::fs::write(&path, &bundle).unwrap();
+
+        let error = BundleSource::open(&path)
+            .unwrap()
+            .read(Path::new("material.ron"))
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        std::fs::write(&path, b"not a bundle").unwrap();
+        assert!(BundleSource::open(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_reject_corrupted_footer() {
+        let path = std::env::temp_dir().join("harmony_corrupted_footer.bundle");
+        let mut bundle = Vec::new();
+        BundleBuilder::new()
+            .add_file("material.ron", "PBRMaterialRon()")
+            .write(&mut bundle)
+            .unwrap();
+        let footer = bundle.len() - 16;
+
+        // An index length far past the end of the file.
+        let mut corrupted = bundle.clone();
+        corrupted[footer + 8..].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &corrupted).unwrap();
+        let error = BundleSource::open(&path).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        // An index offset that overflows when the length is added.
+        let mut corrupted = bundle.clone();
+        corrupted[footer..footer + 8].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        std::fs::write(&path, &corrupted).unwrap();
+        let error = BundleSource::open(&path).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        // An index overlapping the footer.
+        let mut corrupted = bundle;
+        let mut index_length = [0u8; 8];
+        index_length.copy_from_slice(&corrupted[footer + 8..]);
+        let index_length = u64::from_le_bytes(index_length) + 1;
+        corrupted[footer + 8..].copy_from_slice(&index_length.to_le_bytes());
+        std::fs::write(&path, &corrupted).unwrap();
+        let error = BundleSource::open(&path).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+}
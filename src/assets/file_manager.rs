@@ -1,5 +1,16 @@
+use super::{
+    loader::{AssetLoader, LoadContext, LoaderError, Loaders, TryFromLoader},
+    source::normalize,
+    AssetSource, DirectorySource,
+};
+use dashmap::DashMap;
 use futures::executor::{ThreadPool, ThreadPoolBuilder};
-use std::{convert::TryFrom, hash::Hash, path::PathBuf, sync::Arc};
+use std::{
+    convert::TryFrom,
+    hash::Hash,
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+};
 
 pub type AssetCache<T> = Arc<dashmap::DashMap<PathBuf, Result<Arc<T>, Arc<AssetError>>>>;
 
@@ -55,22 +66,13 @@ where
         }
     }
 
+    // Waits until the asset has loaded or failed to load.
+    // Yields between checks so the executor can run the task loading the asset.
     pub async fn get_async(&self) -> Result<Arc<T>, Arc<AssetError>> {
-        let mut asset_result = self.cache.get(&self.handle_id);
-        while asset_result.is_none() {
-            asset_result = self.cache.get(&self.handle_id);
-        }
-
-        let asset_result = asset_result.unwrap();
-        let asset = asset_result.as_ref();
-        match asset {
-            Ok(asset) => {
-                return Ok(asset.clone());
-            }
-            Err(error) => {
-                return Err(error.clone());
-            }
+        while !self.cache.contains_key(&self.handle_id) {
+            async_std::task::yield_now().await;
         }
+        self.get()
     }
 }
 
@@ -84,22 +86,61 @@ pub enum AssetError {
     Loading,
     // Thrown on some other IO error.
     OtherError(std::io::Error),
+    // Thrown when the asset's loader returned an error.
+    Loader(LoaderError),
 }
 
 pub struct FileManager<T> {
     pool: Arc<ThreadPool>,
     cache: AssetCache<T>,
+    source: Arc<dyn AssetSource>,
+    loader: Arc<dyn AssetLoader<Asset = T>>,
+    loaders: Weak<Loaders>,
+    // Files each asset read or loaded while it was loading.
+    dependencies: Arc<DashMap<PathBuf, Vec<PathBuf>>>,
+    // Data of assets that don't have their own file, kept so they can be reloaded.
+    embedded: DashMap<PathBuf, Vec<u8>>,
 }
 
 impl<T> FileManager<T>
 where
     T: TryFrom<(PathBuf, Vec<u8>)> + Send + Sync + 'static,
 {
+    /// Creates a file manager that reads paths from disk as they are given.
     pub fn new() -> Self {
+        Self::with_source(Arc::new(DirectorySource::new("")))
+    }
+
+    /// Creates a file manager that reads files from `source`.
+    pub fn with_source(source: Arc<dyn AssetSource>) -> Self {
+        Self::with_loader(TryFromLoader::<T>::new(), source)
+    }
+}
+
+impl<T> FileManager<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Creates a file manager that reads files from `source` and creates assets with `loader`.
+    pub fn with_loader<L: AssetLoader<Asset = T>>(loader: L, source: Arc<dyn AssetSource>) -> Self {
         // TODO: One pool that we pass in is probably enough.
         let pool = Arc::new(ThreadPoolBuilder::new().pool_size(4).create().unwrap());
         let cache = Arc::new(dashmap::DashMap::new());
-        Self { pool, cache }
+        Self {
+            pool,
+            cache,
+            source,
+            loader: Arc::new(loader),
+            loaders: Weak::new(),
+            dependencies: Arc::new(DashMap::new()),
+            embedded: DashMap::new(),
+        }
+    }
+
+    // Lets the loader load assets of other types through its `LoadContext`.
+    pub(crate) fn with_loaders(mut self, loaders: Weak<Loaders>) -> Self {
+        self.loaders = loaders;
+        self
     }
 
     pub fn get<P: Into<PathBuf>>(&self, path: P) -> Arc<AssetHandle<T>> {
@@ -108,50 +149,103 @@ where
         let asset_handle = Arc::new(AssetHandle::new(path.clone(), self.cache.clone()));
 
         if !self.cache.contains_key(&path) {
-            let cache = self.cache.clone();
+            self.spawn_load(path, None);
+        }
 
-            let asset_thread_handle = asset_handle.clone();
+        asset_handle
+    }
+
+    // Loads an asset from data that isn't stored in its own file, replaces the asset if it was loaded before.
+    pub(crate) fn load_embedded(&self, path: PathBuf, data: Vec<u8>) -> Arc<AssetHandle<T>> {
+        self.embedded.insert(path.clone(), data.clone());
+        self.spawn_load(path.clone(), Some(data));
+        Arc::new(AssetHandle::new(path, self.cache.clone()))
+    }
 
-            self.pool.spawn_ok(async move {
-                let file = async_std::fs::read(path.clone()).await;
-                let result = if file.is_ok() {
-                    // Do something
-                    let file = file.unwrap();
-                    match T::try_from((path.clone(), file)) {
-                        Ok(f) => {
+    fn spawn_load(&self, path: PathBuf, data: Option<Vec<u8>>) {
+        let cache = self.cache.clone();
+        let source = self.source.clone();
+        let loader = self.loader.clone();
+        let loaders = self.loaders.clone();
+        let dependencies = self.dependencies.clone();
+
+        self.pool.spawn_ok(async move {
+            let file = match data {
+                Some(data) => Ok(data),
+                None => source.read(&path),
+            };
+            let result = match file {
+                Ok(file) => {
+                    let mut context = LoadContext::new(path.clone(), source, loaders);
+                    let result = loader.load(&mut context, file).await;
+                    dependencies.insert(path.clone(), context.into_dependencies());
+                    match result {
+                        Ok(asset) => {
                             log::info!("{:?} loaded.", path.file_name().unwrap());
-                            Ok(Arc::new(f))
-                        },
-                        Err(_e) => Err(Arc::new(AssetError::InvalidData)),
+                            Ok(Arc::new(asset))
+                        }
+                        Err(error) => {
+                            log::error!("Couldn't load {:?}: {}", path, error);
+                            Err(Arc::new(AssetError::Loader(error)))
+                        }
                     }
-                } else {
-                    let error = file.err().unwrap();
-                    match error.kind() {
-                        std::io::ErrorKind::NotFound => Err(Arc::new(AssetError::FileNotFound)),
-                        _ => Err(Arc::new(AssetError::OtherError(error))),
-                    }
-                };
+                }
+                Err(error) => match error.kind() {
+                    std::io::ErrorKind::NotFound => Err(Arc::new(AssetError::FileNotFound)),
+                    _ => Err(Arc::new(AssetError::OtherError(error))),
+                },
+            };
+
+            cache.insert(path, result);
+        });
+    }
 
-                cache.insert(asset_thread_handle.handle_id.clone(), result);
-            });
+    // Loads the assets of a file again if they were loaded before along with the assets that read or loaded the file.
+    // Existing handles resolve to the new data once it's loaded.
+    pub(crate) fn reload(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        let assets = self
+            .cache
+            .iter()
+            .map(|item| item.key().clone())
+            .filter(|asset| {
+                normalize(asset) == path
+                    || self
+                        .dependencies
+                        .get(asset)
+                        .map_or(false, |dependencies| dependencies.contains(&path))
+            })
+            .collect::<Vec<_>>();
+
+        for asset in assets.iter() {
+            self.cache.remove(asset);
+            let data = self.embedded.get(asset).map(|data| data.value().clone());
+            self.spawn_load(asset.clone(), data);
         }
+        !assets.is_empty()
+    }
 
-        asset_handle
+    /// Returns a handle to every asset that finished loading or failed to load.
+    pub fn get_all(&self) -> Vec<Arc<AssetHandle<T>>> {
+        self.cache
+            .iter()
+            .map(|item| Arc::new(AssetHandle::new(item.key().clone(), self.cache.clone())))
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{AssetError, FileManager};
-    use crate::assets::image::ImageFormat;
-    use crate::assets::image::ImageRon;
+    use crate::assets::{image::ImageFormat, MemorySource, TextureSettings};
     use crate::assets::material::PBRMaterialRon;
     use nalgebra_glm::Vec4;
+    use std::sync::Arc;
 
     #[test]
-    fn should_load_image_ron_file() {
-        let file_manager = FileManager::<ImageRon>::new();
-        let asset_handle = file_manager.get("./assets/core/white.png.ron");
+    fn should_load_texture_settings_file() {
+        let file_manager = FileManager::<TextureSettings>::new();
+        let asset_handle = file_manager.get("./assets/core/white.png.meta");
 
         let asset = asset_handle.get();
         assert!(match *asset.err().unwrap() {
@@ -188,6 +282,71 @@ mod tests {
         assert!(asset.color == Vec4::new(1.0, 1.0, 1.0, 1.0));
     }
 
+    #[test]
+    fn should_wait_for_asset_async() {
+        let file_manager = FileManager::<PBRMaterialRon>::new();
+        let asset_handle = file_manager.get("./assets/material.ron");
+        let missing_handle = file_manager.get("./assets/missing.ron");
+
+        let asset = async_std::task::block_on(asset_handle.get_async()).unwrap();
+        assert!(asset.color == Vec4::new(1.0, 1.0, 1.0, 1.0));
+        assert!(match *async_std::task::block_on(missing_handle.get_async()).err().unwrap() {
+            AssetError::FileNotFound => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn should_reload_changed_file() {
+        let path = std::env::temp_dir().join("harmony_reload_material.ron");
+        let material = std::fs::read_to_string("./assets/material.ron").unwrap();
+        std::fs::write(&path, &material).unwrap();
+
+        let file_manager = FileManager::<PBRMaterialRon>::new();
+        let asset_handle = file_manager.get(path.clone());
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        assert!(asset_handle.get().unwrap().color == Vec4::new(1.0, 1.0, 1.0, 1.0));
+
+        std::fs::write(&path, material.replace("color: [1.0, 1.0, 1.0, 1.0]", "color: [1.0, 0.0, 0.0, 1.0]"))
+            .unwrap();
+        assert!(file_manager.reload(&path));
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        // The handle from before the reload sees the new data.
+        assert!(asset_handle.get().unwrap().color == Vec4::new(1.0, 0.0, 0.0, 1.0));
+        assert!(!file_manager.reload(&std::env::temp_dir().join("harmony_not_loaded.ron")));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_load_from_source() {
+        let source = MemorySource::new();
+        source.insert(
+            "materials/red.ron",
+            r#"PBRMaterialRon(
+                main_texture: "core/white.png",
+                roughness_texture: "core/white.png",
+                normal_texture: "core/white.png",
+                roughness: 0.0,
+                metallic: 0.0,
+                roughness_override: 0.0,
+                metallic_override: 0.0,
+                color: [1.0, 0.0, 0.0, 1.0],
+            )"#,
+        );
+        let file_manager = FileManager::<PBRMaterialRon>::with_source(Arc::new(source));
+
+        let asset_handle = file_manager.get("materials/red.ron");
+        let missing_handle = file_manager.get("materials/missing.ron");
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        assert!(asset_handle.get().unwrap().color == Vec4::new(1.0, 0.0, 0.0, 1.0));
+        assert!(match *missing_handle.get().err().unwrap() {
+            AssetError::FileNotFound => true,
+            _ => false,
+        });
+    }
+
     #[test]
     fn should_only_load_once() {
         let file_manager = FileManager::<PBRMaterialRon>::new();
@@ -1,4 +1,8 @@
-use std::{convert::TryFrom, path::PathBuf};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
 
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
 pub enum ImageFormat {
@@ -28,23 +32,18 @@ pub struct Image {
     pub(crate) path: PathBuf,
 }
 
-impl TryFrom<(Option<ImageRon>, PathBuf, Vec<u8>)> for Image {
-    type Error = std::io::Error;
+impl TryFrom<(ImageFormat, PathBuf, Vec<u8>)> for Image {
+    type Error = Error;
+    // Returns an `InvalidData` error when the image can't be decoded, ex: a file that's still being written.
     fn try_from(
-        (image_ron, path, data): (Option<ImageRon>, PathBuf, Vec<u8>),
+        (format, path, data): (ImageFormat, PathBuf, Vec<u8>),
     ) -> Result<Self, Self::Error> {
-        let format = if image_ron.is_some() {
-            image_ron.unwrap().format
-        } else {
-            ImageFormat::SRGB
-        };
-
         let (image, width, height) = match format {
             ImageFormat::HDR32 | ImageFormat::HDR16 => {
                 // Load the hdr image
-                let decoder = image::hdr::HdrDecoder::new(data.as_slice()).unwrap();
+                let decoder = image::hdr::HdrDecoder::new(data.as_slice()).map_err(invalid_data)?;
                 let metadata = decoder.metadata();
-                let decoded = decoder.read_image_hdr().unwrap();
+                let decoded = decoder.read_image_hdr().map_err(invalid_data)?;
 
                 let (w, h) = (metadata.width, metadata.height);
 
@@ -63,7 +62,7 @@ impl TryFrom<(Option<ImageRon>, PathBuf, Vec<u8>)> for Image {
                 (image_bytes, w, h)
             }
             _ => {
-                let image = image::load_from_memory(&data).unwrap().to_rgba();
+                let image = image::load_from_memory(&data).map_err(invalid_data)?.to_rgba();
                 let (width, height) = image.dimensions();
 
                 (image.into_raw(), width, height)
@@ -79,14 +78,202 @@ impl TryFrom<(Option<ImageRon>, PathBuf, Vec<u8>)> for Image {
     }
 }
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
-pub struct ImageRon {
-    pub format: ImageFormat,
+fn invalid_data(error: image::ImageError) -> Error {
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+impl Image {
+    /// Returns the image at half its size, used to generate mips. Colors are averaged in linear space.
+    pub fn downsample(&self, format: ImageFormat) -> Image {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let pixel_size = format.pixel_size();
+
+        let mut data = Vec::with_capacity(width as usize * height as usize * pixel_size);
+        for y in 0..height {
+            for x in 0..width {
+                let texels = [(x * 2, y * 2), (x * 2 + 1, y * 2), (x * 2, y * 2 + 1), (x * 2 + 1, y * 2 + 1)];
+                for channel in 0..4 {
+                    let mut sum = 0.0;
+                    for (texel_x, texel_y) in texels.iter() {
+                        // Odd sized images repeat their last row or column.
+                        let texel_x = (*texel_x).min(self.width - 1) as usize;
+                        let texel_y = (*texel_y).min(self.height - 1) as usize;
+                        let offset = (texel_y * self.width as usize + texel_x) * pixel_size;
+                        sum += read_channel(&self.data, format, channel, offset);
+                    }
+                    write_channel(&mut data, format, channel, sum / 4.0);
+                }
+            }
+        }
+
+        Image {
+            data,
+            width,
+            height,
+            path: self.path.clone(),
+        }
+    }
+
+    /// Compresses an 8 bit image to BC1, the width and height have to be multiples of 4.
+    pub fn compress_bc1(&self) -> Vec<u8> {
+        let blocks_x = self.width as usize / 4;
+        let blocks_y = self.height as usize / 4;
+        let mut data = Vec::with_capacity(blocks_x * blocks_y * 8);
+        for block_y in 0..blocks_y {
+            for block_x in 0..blocks_x {
+                let mut block = [[0u8; 3]; 16];
+                for (i, texel) in block.iter_mut().enumerate() {
+                    let x = block_x * 4 + i % 4;
+                    let y = block_y * 4 + i / 4;
+                    let offset = (y * self.width as usize + x) * 4;
+                    texel.copy_from_slice(&self.data[offset..offset + 3]);
+                }
+                data.extend_from_slice(&encode_bc1_block(&block));
+            }
+        }
+        data
+    }
+}
+
+impl ImageFormat {
+    // Size in bytes of a decoded pixel, hdr images are decoded to 4 f32 channels.
+    fn pixel_size(self) -> usize {
+        match self {
+            ImageFormat::HDR16 | ImageFormat::HDR32 => 16,
+            ImageFormat::RGB | ImageFormat::SRGB => 4,
+        }
+    }
+}
+
+fn read_channel(data: &[u8], format: ImageFormat, channel: usize, offset: usize) -> f32 {
+    match format {
+        ImageFormat::HDR16 | ImageFormat::HDR32 => {
+            let offset = offset + channel * 4;
+            f32::from_ne_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+        }
+        ImageFormat::SRGB if channel < 3 => srgb_to_linear(data[offset + channel] as f32 / 255.0),
+        _ => data[offset + channel] as f32 / 255.0,
+    }
+}
+
+fn write_channel(data: &mut Vec<u8>, format: ImageFormat, channel: usize, value: f32) {
+    match format {
+        ImageFormat::HDR16 | ImageFormat::HDR32 => data.extend_from_slice(&value.to_ne_bytes()),
+        ImageFormat::SRGB if channel < 3 => data.push((linear_to_srgb(value) * 255.0).round() as u8),
+        _ => data.push((value * 255.0).round() as u8),
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
 }
 
-impl TryFrom<(PathBuf, Vec<u8>)> for ImageRon {
-    type Error = ron::de::Error;
-    fn try_from((_p, v): (PathBuf, Vec<u8>)) -> Result<Self, Self::Error> {
-        ron::de::from_bytes(&v)
+// Encodes 4x4 texels using the two corners of their bounding box as the end points.
+fn encode_bc1_block(texels: &[[u8; 3]; 16]) -> [u8; 8] {
+    let mut min = [255u8; 3];
+    let mut max = [0u8; 3];
+    for texel in texels.iter() {
+        for channel in 0..3 {
+            min[channel] = min[channel].min(texel[channel]);
+            max[channel] = max[channel].max(texel[channel]);
+        }
+    }
+
+    // The max end point always packs to the larger value which selects the 4 color mode.
+    let color0 = pack_565(max);
+    let color1 = pack_565(min);
+    let end0 = unpack_565(color0);
+    let end1 = unpack_565(color1);
+    let mut palette = [end0, end1, [0.0; 3], [0.0; 3]];
+    for channel in 0..3 {
+        palette[2][channel] = (2.0 * end0[channel] + end1[channel]) / 3.0;
+        palette[3][channel] = (end0[channel] + 2.0 * end1[channel]) / 3.0;
+    }
+
+    let mut indices = 0u32;
+    for (i, texel) in texels.iter().enumerate() {
+        let distance = |color: &[f32; 3]| {
+            (0..3)
+                .map(|channel| (texel[channel] as f32 - color[channel]).powi(2))
+                .sum::<f32>()
+        };
+        let closest = palette
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| distance(a).partial_cmp(&distance(b)).unwrap())
+            .map(|(index, _)| index as u32)
+            .unwrap();
+        indices |= closest << (i * 2);
+    }
+
+    let mut block = [0u8; 8];
+    block[0..2].copy_from_slice(&color0.to_le_bytes());
+    block[2..4].copy_from_slice(&color1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+fn pack_565(color: [u8; 3]) -> u16 {
+    let r = (color[0] as u16 * 31 + 127) / 255;
+    let g = (color[1] as u16 * 63 + 127) / 255;
+    let b = (color[2] as u16 * 31 + 127) / 255;
+    (r << 11) | (g << 5) | b
+}
+
+fn unpack_565(color: u16) -> [f32; 3] {
+    [
+        ((color >> 11) & 31) as f32 * 255.0 / 31.0,
+        ((color >> 5) & 63) as f32 * 255.0 / 63.0,
+        (color & 31) as f32 * 255.0 / 31.0,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Image, ImageFormat};
+    use std::{convert::TryFrom, io::ErrorKind, path::PathBuf};
+
+    #[test]
+    fn should_downsample_and_compress() {
+        let image = Image {
+            data: [[255u8, 255, 255, 255], [0, 0, 0, 255]].repeat(8).concat(),
+            width: 4,
+            height: 4,
+            path: PathBuf::from("test.png"),
+        };
+
+        let mip = image.downsample(ImageFormat::RGB);
+        assert_eq!((mip.width, mip.height), (2, 2));
+        assert_eq!(&mip.data[..4], &[128, 128, 128, 255]);
+        let mip = mip.downsample(ImageFormat::RGB).downsample(ImageFormat::RGB);
+        assert_eq!((mip.width, mip.height), (1, 1));
+
+        let block = image.compress_bc1();
+        assert_eq!(block.len(), 8);
+        // White and black are the end points of the block and the texels alternate between them.
+        assert_eq!(u16::from_le_bytes([block[0], block[1]]), 0xffff);
+        assert_eq!(u16::from_le_bytes([block[2], block[3]]), 0);
+        assert_eq!(u32::from_le_bytes([block[4], block[5], block[6], block[7]]), 0x4444_4444);
+    }
+
+    #[test]
+    fn should_reject_corrupt_images() {
+        for format in [ImageFormat::SRGB, ImageFormat::HDR32].iter() {
+            let error = Image::try_from((*format, PathBuf::from("broken.png"), vec![1, 2, 3])).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+        }
     }
 }
@@ -0,0 +1,263 @@
+use super::{image::ImageFormat, loader::LoaderError, AssetSource};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+// Extension of the sidecar files holding an asset's import settings, ex: `white.png.meta`.
+pub(crate) const META_EXTENSION: &str = "meta";
+// Extension sidecar files had in older versions, ex: `white.png.ron`. Still read when there is no `.meta` file.
+const LEGACY_META_EXTENSION: &str = "ron";
+
+// Returns the path of the sidecar file holding the import settings of `path`.
+pub(crate) fn meta_path(path: &Path) -> PathBuf {
+    sidecar_path(path, META_EXTENSION)
+}
+
+// Returns the path of the sidecar file older versions read the import settings of `path` from.
+pub(crate) fn legacy_meta_path(path: &Path) -> PathBuf {
+    sidecar_path(path, LEGACY_META_EXTENSION)
+}
+
+fn sidecar_path(path: &Path, extension: &str) -> PathBuf {
+    let mut sidecar_path = path.as_os_str().to_os_string();
+    sidecar_path.push(".");
+    sidecar_path.push(extension);
+    PathBuf::from(sidecar_path)
+}
+
+// Returns the asset a sidecar file belongs to, or None if `path` isn't a sidecar file.
+pub(crate) fn asset_path(path: &Path) -> Option<PathBuf> {
+    if path.extension() == Some(OsStr::new(META_EXTENSION)) {
+        Some(path.with_extension(""))
+    } else {
+        None
+    }
+}
+
+// Reads the import settings of `path`, assets without a sidecar file use the default settings.
+// A legacy `<path>.ron` sidecar is read when there is no `<path>.meta`.
+pub(crate) fn read_settings<S: DeserializeOwned + Default>(
+    source: &dyn AssetSource,
+    path: &Path,
+) -> Result<S, LoaderError> {
+    let mut meta_path = meta_path(path);
+    if !source.exists(&meta_path) {
+        let legacy_meta_path = legacy_meta_path(path);
+        if !source.exists(&legacy_meta_path) {
+            return Ok(S::default());
+        }
+        log::warn!(
+            "Reading the import settings of {} from {}, rename it to {}.",
+            path.display(),
+            legacy_meta_path.display(),
+            meta_path.display()
+        );
+        meta_path = legacy_meta_path;
+    }
+    let data = source.read(&meta_path)?;
+    ron::de::from_bytes(&data)
+        .map_err(|error| LoaderError::InvalidData(format!("{}: {}", meta_path.display(), error)))
+}
+
+/// How a texture is compressed on the GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextureCompression {
+    None,
+    /// Compressed to BC1 when the texture is loaded. Drops the alpha channel and needs the texture's size to be
+    /// a multiple of 4. Textures stay uncompressed on devices that don't support BC compression.
+    Bc1,
+}
+
+/// How texture coordinates outside of the texture are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddressMode {
+    Repeat,
+    MirrorRepeat,
+    ClampToEdge,
+}
+
+impl Into<wgpu::AddressMode> for AddressMode {
+    fn into(self) -> wgpu::AddressMode {
+        match self {
+            AddressMode::Repeat => wgpu::AddressMode::Repeat,
+            AddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+            AddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        }
+    }
+}
+
+/// How texels are filtered when a texture is sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterMode {
+    Nearest,
+    Linear,
+}
+
+impl Into<wgpu::FilterMode> for FilterMode {
+    fn into(self) -> wgpu::FilterMode {
+        match self {
+            FilterMode::Nearest => wgpu::FilterMode::Nearest,
+            FilterMode::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+/// Sampler used when a material samples the texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplerSettings {
+    pub address_mode: AddressMode,
+    pub filter: FilterMode,
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        Self {
+            address_mode: AddressMode::Repeat,
+            filter: FilterMode::Linear,
+        }
+    }
+}
+
+/// Import settings of a texture, read from `<texture>.meta`.
+///
+/// ```ignore
+/// (
+///     format: RGB,
+///     mips: true,
+///     sampler: (address_mode: ClampToEdge, filter: Nearest),
+///     compression: Bc1,
+/// )
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextureSettings {
+    pub format: ImageFormat,
+    /// Generates the whole mip chain when the texture is loaded.
+    pub mips: bool,
+    pub sampler: SamplerSettings,
+    pub compression: TextureCompression,
+}
+
+impl Default for TextureSettings {
+    fn default() -> Self {
+        Self {
+            format: ImageFormat::SRGB,
+            mips: false,
+            sampler: SamplerSettings::default(),
+            compression: TextureCompression::None,
+        }
+    }
+}
+
+impl TryFrom<(PathBuf, Vec<u8>)> for TextureSettings {
+    type Error = ron::de::Error;
+    fn try_from((_p, v): (PathBuf, Vec<u8>)) -> Result<Self, Self::Error> {
+        ron::de::from_bytes(&v)
+    }
+}
+
+/// Import settings of a mesh, read from `<mesh>.gltf.meta`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeshSettings {
+    /// Scales every vertex of the mesh.
+    pub scale: f32,
+    /// Converts the mesh from glTF's right handed coordinates to harmony's left handed ones by flipping the Z axis.
+    pub convert_axes: bool,
+    /// Generates smooth normals for primitives without normals.
+    pub generate_normals: bool,
+    /// Generates tangents for primitives without tangents, needed by normal maps.
+    pub generate_tangents: bool,
+    /// Merges duplicate vertices.
+    pub optimize: bool,
+}
+
+impl Default for MeshSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            convert_axes: false,
+            generate_normals: true,
+            generate_tangents: true,
+            optimize: false,
+        }
+    }
+}
+
+/// Import settings of a shader, read from `<shader>.shader.meta`.
+///
+/// ```ignore
+/// (
+///     defines: {"MAX_LIGHTS": "128", "DEBUG": ""},
+/// )
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShaderSettings {
+    /// Macros defined when every stage of the shader is compiled, an empty value defines the macro without a value.
+    pub defines: BTreeMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        asset_path, legacy_meta_path, meta_path, read_settings, AddressMode, MeshSettings, ShaderSettings, TextureCompression,
+        TextureSettings,
+    };
+    use crate::assets::{image::ImageFormat, loader::LoaderError, MemorySource};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn should_read_import_settings() {
+        let source = MemorySource::new();
+        source.insert(
+            "textures/grass.png.meta",
+            "(format: RGB, mips: true, sampler: (address_mode: ClampToEdge), compression: Bc1)",
+        );
+        source.insert("meshes/tree.gltf.meta", "(scale: 0.5, convert_axes: true)");
+        source.insert("shaders/grass.shader.meta", "(defines: {\"WIND\": \"1\"})");
+        source.insert("shaders/broken.shader.meta", "(defines: 1)");
+
+        let texture: TextureSettings = read_settings(&source, Path::new("textures/grass.png")).unwrap();
+        assert_eq!(texture.format, ImageFormat::RGB);
+        assert!(texture.mips);
+        assert_eq!(texture.sampler.address_mode, AddressMode::ClampToEdge);
+        assert_eq!(texture.compression, TextureCompression::Bc1);
+
+        let mesh: MeshSettings = read_settings(&source, Path::new("meshes/tree.gltf")).unwrap();
+        assert_eq!(mesh.scale, 0.5);
+        assert!(mesh.convert_axes);
+        assert!(mesh.generate_tangents);
+
+        let shader: ShaderSettings = read_settings(&source, Path::new("shaders/grass.shader")).unwrap();
+        assert_eq!(shader.defines.get("WIND").map(String::as_str), Some("1"));
+
+        // Assets without a sidecar file use the defaults.
+        let texture: TextureSettings = read_settings(&source, Path::new("textures/dirt.png")).unwrap();
+        assert_eq!(texture, TextureSettings::default());
+
+        // Sidecar files from older versions are still read, `.meta` files take precedence.
+        source.insert("textures/old.png.ron", "(format: HDR16)");
+        let texture: TextureSettings = read_settings(&source, Path::new("textures/old.png")).unwrap();
+        assert_eq!(texture.format, ImageFormat::HDR16);
+        source.insert("textures/old.png.meta", "(format: RGB)");
+        let texture: TextureSettings = read_settings(&source, Path::new("textures/old.png")).unwrap();
+        assert_eq!(texture.format, ImageFormat::RGB);
+
+        let error = read_settings::<ShaderSettings>(&source, Path::new("shaders/broken.shader")).unwrap_err();
+        assert!(match error {
+            LoaderError::InvalidData(_) => true,
+            _ => false,
+        });
+
+        assert_eq!(meta_path(Path::new("core/white.png")), PathBuf::from("core/white.png.meta"));
+        assert_eq!(legacy_meta_path(Path::new("core/white.png")), PathBuf::from("core/white.png.ron"));
+        assert_eq!(asset_path(Path::new("core/white.png.meta")), Some(PathBuf::from("core/white.png")));
+        assert_eq!(asset_path(Path::new("core/white.png")), None);
+    }
+}
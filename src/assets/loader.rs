@@ -0,0 +1,375 @@
+use super::{
+    file_manager::{AssetError, AssetHandle, FileManager},
+    import_settings::{legacy_meta_path, meta_path, read_settings},
+    source::normalize,
+    texture::Texture,
+    texture_manager::TextureManager,
+    AssetSource,
+};
+use futures::future::{BoxFuture, FutureExt};
+use serde::de::DeserializeOwned;
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    convert::TryFrom,
+    io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock, Weak},
+};
+
+/// Errors returned by an `AssetLoader`.
+#[derive(Debug)]
+pub enum LoaderError {
+    // Thrown when a file couldn't be read.
+    Io(io::Error),
+    // Thrown when the file's data isn't valid for the asset type.
+    InvalidData(String),
+    // Thrown when a dependency was requested for an asset type that doesn't have a loader.
+    MissingLoader(&'static str),
+    // Thrown when a dependency failed to load.
+    Dependency { path: PathBuf, error: Arc<AssetError> },
+}
+
+impl std::fmt::Display for LoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoaderError::Io(error) => write!(f, "{}", error),
+            LoaderError::InvalidData(message) => write!(f, "{}", message),
+            LoaderError::MissingLoader(asset) => write!(f, "No loader is registered for {}.", asset),
+            LoaderError::Dependency { path, error } => {
+                write!(f, "Dependency {} failed to load: {:?}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoaderError {}
+
+impl From<io::Error> for LoaderError {
+    fn from(error: io::Error) -> Self {
+        LoaderError::Io(error)
+    }
+}
+
+/// Creates an asset from a file's data, register loaders with `AssetManager::register_loader`.
+///
+/// Example:
+/// ```ignore
+/// struct LevelLoader;
+///
+/// impl AssetLoader for LevelLoader {
+///     type Asset = Level;
+///
+///     fn load<'a>(&'a self, context: &'a mut LoadContext, data: Vec<u8>) -> BoxFuture<'a, Result<Level, LoaderError>> {
+///         Box::pin(async move {
+///             let mut level: Level = ron::de::from_bytes(&data)
+///                 .map_err(|error| LoaderError::InvalidData(error.to_string()))?;
+///             level.skybox = context.load_texture_async(&level.skybox_path).await?;
+///             Ok(level)
+///         })
+///     }
+/// }
+/// ```
+pub trait AssetLoader: Send + Sync + 'static {
+    /// The type of asset the loader creates.
+    type Asset: Send + Sync + 'static;
+
+    /// Creates the asset from the data of the file at `context.path()`. Runs on the asset manager's thread pool.
+    fn load<'a>(
+        &'a self,
+        context: &'a mut LoadContext,
+        data: Vec<u8>,
+    ) -> BoxFuture<'a, Result<Self::Asset, LoaderError>>;
+}
+
+// Loads assets registered with `AssetManager::register` that only implement `TryFrom<(PathBuf, Vec<u8>)>`.
+pub(crate) struct TryFromLoader<T> {
+    asset: PhantomData<fn() -> T>,
+}
+
+impl<T> TryFromLoader<T> {
+    pub(crate) fn new() -> Self {
+        Self { asset: PhantomData }
+    }
+}
+
+impl<T> AssetLoader for TryFromLoader<T>
+where
+    T: TryFrom<(PathBuf, Vec<u8>)> + Send + Sync + 'static,
+{
+    type Asset = T;
+
+    fn load<'a>(
+        &'a self,
+        context: &'a mut LoadContext,
+        data: Vec<u8>,
+    ) -> BoxFuture<'a, Result<T, LoaderError>> {
+        let path = context.path().to_path_buf();
+        let result = T::try_from((path.clone(), data)).map_err(|_| {
+            LoaderError::InvalidData(format!(
+                "{} isn't a valid {}.",
+                path.display(),
+                std::any::type_name::<T>()
+            ))
+        });
+        futures::future::ready(result).boxed()
+    }
+}
+
+/// Passed to `AssetLoader::load`, reads other files and loads the assets the loaded asset depends on.
+/// Every file read or loaded through the context is a dependency, reloading it reloads the asset as well.
+pub struct LoadContext {
+    path: PathBuf,
+    source: Arc<dyn AssetSource>,
+    loaders: Weak<Loaders>,
+    dependencies: Vec<PathBuf>,
+}
+
+impl LoadContext {
+    pub(crate) fn new(path: PathBuf, source: Arc<dyn AssetSource>, loaders: Weak<Loaders>) -> Self {
+        Self {
+            path,
+            source,
+            loaders,
+            dependencies: Vec::new(),
+        }
+    }
+
+    /// Path of the asset being loaded, relative to the asset folder.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads another file, ex: the buffers of a gltf file. `path` is relative to the asset folder.
+    pub fn read<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<u8>, LoaderError> {
+        let path = path.as_ref();
+        self.add_dependency(path);
+        Ok(self.source.read(path)?)
+    }
+
+    /// Reads the asset's import settings from its sidecar file(`<path>.meta`), ex: `MeshSettings`.
+    /// Returns the default settings when the asset doesn't have a sidecar file. Sidecar files from older versions
+    /// (`<path>.ron`) are read when there is no `.meta` file.
+    pub fn settings<S: DeserializeOwned + Default>(&mut self) -> Result<S, LoaderError> {
+        self.add_dependency(&meta_path(&self.path));
+        self.add_dependency(&legacy_meta_path(&self.path));
+        read_settings(self.source.as_ref(), &self.path)
+    }
+
+    /// Starts loading an asset the loaded asset depends on, `path` is relative to the asset folder.
+    pub fn load<T: Send + Sync + 'static, P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> Result<Arc<AssetHandle<T>>, LoaderError> {
+        let path = path.into();
+        self.add_dependency(&path);
+        Ok(self.file_manager::<T>()?.get(path))
+    }
+
+    /// Loads an asset the loaded asset depends on and waits for it.
+    pub async fn load_async<T: Send + Sync + 'static, P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> Result<Arc<T>, LoaderError> {
+        let path = path.into();
+        let handle = self.load::<T, _>(path.clone())?;
+        handle
+            .get_async()
+            .await
+            .map_err(|error| LoaderError::Dependency { path, error })
+    }
+
+    /// Starts loading a texture the loaded asset depends on, `path` is relative to the asset folder.
+    pub fn load_texture<P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> Result<Arc<AssetHandle<Texture>>, LoaderError> {
+        let path = path.into();
+        self.add_dependency(&path);
+        Ok(self.loaders()?.texture_manager.get(path))
+    }
+
+    /// Loads a texture the loaded asset depends on and waits for it.
+    /// Returns `LoaderError::Dependency` when the texture couldn't be loaded.
+    pub async fn load_texture_async<P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> Result<Arc<AssetHandle<Texture>>, LoaderError> {
+        let path = path.into();
+        self.add_dependency(&path);
+        let texture_manager = self.loaders()?.texture_manager.clone();
+        let handle = texture_manager.get_async(path.clone()).await;
+        handle
+            .get()
+            .map_err(|error| LoaderError::Dependency { path, error })?;
+        Ok(handle)
+    }
+
+    /// Loads an asset from data that isn't stored in its own file, ex: the materials of a gltf file.
+    /// The asset's path is the loaded asset's path followed by `#label`, loading it again replaces the old asset.
+    pub fn load_embedded<T: Send + Sync + 'static>(
+        &mut self,
+        label: &str,
+        data: Vec<u8>,
+    ) -> Result<Arc<AssetHandle<T>>, LoaderError> {
+        let mut path = self.path.clone().into_os_string();
+        path.push("#");
+        path.push(label);
+        Ok(self.file_manager::<T>()?.load_embedded(PathBuf::from(path), data))
+    }
+
+    pub(crate) fn into_dependencies(self) -> Vec<PathBuf> {
+        self.dependencies
+    }
+
+    fn add_dependency(&mut self, path: &Path) {
+        let path = normalize(path);
+        if !self.dependencies.contains(&path) {
+            self.dependencies.push(path);
+        }
+    }
+
+    fn loaders(&self) -> Result<Arc<Loaders>, LoaderError> {
+        self.loaders
+            .upgrade()
+            .ok_or(LoaderError::MissingLoader("assets outside of an AssetManager"))
+    }
+
+    fn file_manager<T: Send + Sync + 'static>(&self) -> Result<Arc<FileManager<T>>, LoaderError> {
+        self.loaders()?
+            .get::<T>()
+            .ok_or_else(|| LoaderError::MissingLoader(std::any::type_name::<T>()))
+    }
+}
+
+// Lets file managers of different asset types live in one map.
+pub(crate) trait AnyFileManager: Send + Sync {
+    fn reload(&self, path: &Path) -> bool;
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync>;
+}
+
+impl<T: Send + Sync + 'static> AnyFileManager for FileManager<T> {
+    fn reload(&self, path: &Path) -> bool {
+        FileManager::reload(self, path)
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
+        self
+    }
+}
+
+/// The file manager of every registered asset type, shared with loaders so they can load dependencies.
+pub(crate) struct Loaders {
+    managers: RwLock<HashMap<TypeId, Arc<dyn AnyFileManager>>>,
+    texture_manager: Arc<TextureManager>,
+}
+
+impl Loaders {
+    pub(crate) fn new(texture_manager: Arc<TextureManager>) -> Self {
+        Self {
+            managers: RwLock::new(HashMap::new()),
+            texture_manager,
+        }
+    }
+
+    pub(crate) fn contains<T: 'static>(&self) -> bool {
+        self.managers.read().unwrap().contains_key(&TypeId::of::<T>())
+    }
+
+    pub(crate) fn insert<T: Send + Sync + 'static>(&self, manager: Arc<FileManager<T>>) {
+        self.managers
+            .write()
+            .unwrap()
+            .insert(TypeId::of::<T>(), manager);
+    }
+
+    pub(crate) fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<FileManager<T>>> {
+        let manager = self.managers.read().unwrap().get(&TypeId::of::<T>()).cloned()?;
+        manager.into_any().downcast::<FileManager<T>>().ok()
+    }
+
+    // Reloads the assets loaded from `path` or depending on it, returns true if any asset was reloaded.
+    pub(crate) fn reload(&self, path: &Path) -> bool {
+        let managers = self.managers.read().unwrap().values().cloned().collect::<Vec<_>>();
+        managers
+            .iter()
+            .fold(false, |reloaded, manager| manager.reload(path) || reloaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetLoader, LoadContext, LoaderError};
+    use crate::assets::{AssetError, FileManager, MemorySource};
+    use futures::future::BoxFuture;
+    use std::{path::Path, sync::Arc};
+
+    #[derive(serde::Deserialize)]
+    struct LevelSettings {
+        scale: u32,
+    }
+
+    impl Default for LevelSettings {
+        fn default() -> Self {
+            Self { scale: 1 }
+        }
+    }
+
+    struct Level {
+        tiles: Vec<u8>,
+        scale: u32,
+    }
+
+    // A level file contains the path of its tiles file.
+    struct LevelLoader;
+
+    impl AssetLoader for LevelLoader {
+        type Asset = Level;
+
+        fn load<'a>(
+            &'a self,
+            context: &'a mut LoadContext,
+            data: Vec<u8>,
+        ) -> BoxFuture<'a, Result<Level, LoaderError>> {
+            Box::pin(async move {
+                let tiles_path = String::from_utf8(data)
+                    .map_err(|error| LoaderError::InvalidData(error.to_string()))?;
+                let tiles = context.read(tiles_path.trim())?;
+                let settings: LevelSettings = context.settings()?;
+                Ok(Level {
+                    tiles,
+                    scale: settings.scale,
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn should_load_with_custom_loader() {
+        let source = Arc::new(MemorySource::new());
+        source.insert("levels/one.level", "levels/one.tiles");
+        source.insert("levels/one.tiles", vec![1u8, 2, 3]);
+        source.insert("levels/one.level.meta", "(scale: 2)");
+        source.insert("levels/two.level", "levels/missing.tiles");
+        let file_manager = FileManager::with_loader(LevelLoader, source.clone());
+
+        let level = file_manager.get("levels/one.level");
+        let broken_level = file_manager.get("levels/two.level");
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        let loaded_level = level.get().unwrap();
+        assert_eq!(loaded_level.tiles, vec![1, 2, 3]);
+        assert_eq!(loaded_level.scale, 2);
+        assert!(match *broken_level.get().err().unwrap() {
+            AssetError::Loader(LoaderError::Io(_)) => true,
+            _ => false,
+        });
+
+        // Files read by the loader are dependencies of the level.
+        source.insert("levels/one.tiles", vec![4u8]);
+        assert!(file_manager.reload(Path::new("levels/one.tiles")));
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        assert_eq!(level.get().unwrap().tiles, vec![4]);
+    }
+}
@@ -1,8 +1,13 @@
-use super::{file_manager::AssetHandle, texture::Texture};
+use super::{
+    file_manager::AssetHandle,
+    loader::{AssetLoader, LoadContext, LoaderError},
+    texture::Texture,
+};
 use crate::graphics::resources::{BindGroup, GPUResourceManager};
 use bytemuck::{Pod, Zeroable};
+use futures::future::BoxFuture;
 use nalgebra_glm::Vec4;
-use std::{convert::TryFrom, fmt::Debug, path::PathBuf, sync::Arc, borrow::Cow};
+use std::{convert::TryFrom, fmt::Debug, marker::PhantomData, path::PathBuf, sync::Arc, borrow::Cow};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -14,6 +19,7 @@ pub struct PBRMaterialUniform {
 unsafe impl Zeroable for PBRMaterialUniform {}
 unsafe impl Pod for PBRMaterialUniform {}
 
+/// A PBR material file. Texture paths are relative to the asset folder, not to the material file.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PBRMaterialRon {
     pub main_texture: String,
@@ -34,7 +40,7 @@ impl TryFrom<(PathBuf, Vec<u8>)> for PBRMaterialRon {
 }
 
 pub trait Material: Clone {
-    type BindMaterialType: BindMaterial + Debug + Send + Sync;
+    type BindMaterialType: BindMaterial + Debug + Send + Sync + 'static;
 
     fn load_textures(&self) -> Vec<PathBuf>;
     fn create_material(&self, textures: Vec<Arc<AssetHandle<Texture>>>) -> Self::BindMaterialType;
@@ -74,6 +80,55 @@ impl Material for PBRMaterialRon {
     }
 }
 
+/// Loads material files of type `T`, ex: `PBRMaterialRon`, and creates their bind material once their
+/// textures are loaded. Texture paths in material files are relative to the asset folder.
+pub struct MaterialLoader<T> {
+    device: Arc<wgpu::Device>,
+    gpu_resource_manager: Arc<GPUResourceManager>,
+    material: PhantomData<fn() -> T>,
+}
+
+impl<T> MaterialLoader<T> {
+    pub fn new(device: Arc<wgpu::Device>, gpu_resource_manager: Arc<GPUResourceManager>) -> Self {
+        Self {
+            device,
+            gpu_resource_manager,
+            material: PhantomData,
+        }
+    }
+}
+
+impl<T> AssetLoader for MaterialLoader<T>
+where
+    T: TryFrom<(PathBuf, Vec<u8>)> + Debug + Material + Send + Sync + 'static,
+{
+    type Asset = T::BindMaterialType;
+
+    fn load<'a>(
+        &'a self,
+        context: &'a mut LoadContext,
+        data: Vec<u8>,
+    ) -> BoxFuture<'a, Result<T::BindMaterialType, LoaderError>> {
+        Box::pin(async move {
+            let material = T::try_from((context.path().to_path_buf(), data)).map_err(|_| {
+                LoaderError::InvalidData(format!("{} isn't a valid material.", context.path().display()))
+            })?;
+
+            let mut textures = Vec::new();
+            for texture_path in material.load_textures() {
+                textures.push(context.load_texture_async(texture_path).await?);
+            }
+
+            let mut bind_material = material.create_material(textures);
+            bind_material.create_bindgroup(
+                self.device.clone(),
+                T::get_layout(self.gpu_resource_manager.clone()),
+            );
+            Ok(bind_material)
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct PBRMaterial {
     pub main_texture: Arc<AssetHandle<Texture>>,
@@ -120,6 +175,7 @@ impl BindMaterial for PBRMaterial {
         // Asset manager will panic if image doesn't exist, but we don't want that.
         // So use get_image_option instead.
 
+        // Used for the probe's cube maps, the material's textures use their own samplers.
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("PBRMaterialSampler"),
             address_mode_u: wgpu::AddressMode::Repeat,
@@ -190,6 +246,18 @@ impl BindMaterial for PBRMaterial {
                     binding: 5,
                     resource: wgpu::BindingResource::TextureView(&roughness_texture.view),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::Sampler(&main_texture.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: wgpu::BindingResource::Sampler(&normal_texture.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::Sampler(&roughness_texture.sampler),
+                },
             ]),
             label: None,
         });
@@ -197,3 +265,82 @@ impl BindMaterial for PBRMaterial {
         self.bind_group = Some(Arc::new(BindGroup::new(2, bind_group)));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MaterialLoader, PBRMaterialRon};
+    use crate::{
+        assets::{
+            loader::Loaders, texture_manager::TextureManager, AssetError, AssetSource, DirectorySource,
+            FileManager,
+        },
+        graphics::{pipelines::pbr::create_pbr_bindgroup_layout, resources::GPUResourceManager, shadows::ShadowQuality},
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn should_load_material() {
+        let (_, device, queue) = async_std::task::block_on(async {
+            let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+            let adapter = instance
+                .request_adapter(
+                    &wgpu::RequestAdapterOptions {
+                        power_preference: wgpu::PowerPreference::Default,
+                        compatible_surface: None,
+                    },
+                )
+                .await
+                .unwrap();
+
+            let adapter_features = adapter.features();
+            let (device, queue) = adapter
+                .request_device(
+                    &wgpu::DeviceDescriptor {
+                        features: adapter_features,
+                        limits: wgpu::Limits::default(),
+                        shader_validation: true,
+                    },
+                    None,
+                )
+                .await
+                .unwrap();
+            let arc_device = Arc::new(device);
+            let arc_queue = Arc::new(queue);
+            (adapter, arc_device, arc_queue)
+        });
+
+        let source: Arc<dyn AssetSource> = Arc::new(DirectorySource::new("./assets"));
+        let texture_manager = TextureManager::new(device.clone(), queue, source.clone());
+        let omni_manager = crate::graphics::shadows::OmniShadowManager::new(
+            device.clone(),
+            ShadowQuality::Medium
+        );
+        let gpu_resource_manager = Arc::new(GPUResourceManager::new(device.clone(), &omni_manager));
+
+        let pbr_bind_group_layout = create_pbr_bindgroup_layout(device.clone());
+        gpu_resource_manager.add_bind_group_layout("pbr_material_layout", pbr_bind_group_layout);
+
+        // Materials load their textures through the loaders.
+        let loaders = Arc::new(Loaders::new(Arc::new(texture_manager)));
+        let material_manager = Arc::new(
+            FileManager::with_loader(
+                MaterialLoader::<PBRMaterialRon>::new(device, gpu_resource_manager),
+                source,
+            )
+            .with_loaders(Arc::downgrade(&loaders)),
+        );
+        loaders.insert(material_manager.clone());
+
+        let material_handle = material_manager.get("material.ron");
+        let material = material_handle.get();
+        assert!(match *material.err().unwrap() {
+            AssetError::Loading => true,
+            _ => false,
+        });
+
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        let material = material_handle.get();
+        assert!(material.is_ok());
+    }
+}
@@ -1,14 +1,16 @@
 use super::{
     file_manager::AssetHandle,
+    loader::{AssetLoader, LoadContext, LoaderError},
     material::{PBRMaterial, PBRMaterialRon},
-    material_manager::MaterialManager,
+    MeshSettings,
 };
 use bytemuck::{Pod, Zeroable};
+use futures::future::BoxFuture;
 use nalgebra_glm::{Vec2, Vec3, Vec4};
 use std::{
     collections::HashMap,
     ffi::OsStr,
-    path::{Path, PathBuf},
+    path::Path,
     sync::Arc,
 };
 use crate::core::BoundingSphere;
@@ -71,15 +73,43 @@ pub struct Gltf {
     pub bounding_sphere: BoundingSphere,
 }
 
-impl Gltf {
-    pub async fn from_gltf(
-        device: Arc<wgpu::Device>,
-        material_manager: Arc<MaterialManager<PBRMaterialRon>>,
-        path: PathBuf,
-    ) -> Gltf {
-        let data: Vec<u8> = async_std::fs::read(path.clone()).await.unwrap();
+/// Loads `.gltf` files, their materials are loaded as `PBRMaterial` assets embedded in the gltf file.
+/// Import settings are read from `<file>.gltf.meta`, see `MeshSettings`.
+pub struct GltfLoader {
+    device: Arc<wgpu::Device>,
+}
+
+impl GltfLoader {
+    pub fn new(device: Arc<wgpu::Device>) -> Self {
+        Self { device }
+    }
+}
+
+impl AssetLoader for GltfLoader {
+    type Asset = Gltf;
+
+    fn load<'a>(
+        &'a self,
+        context: &'a mut LoadContext,
+        data: Vec<u8>,
+    ) -> BoxFuture<'a, Result<Gltf, LoaderError>> {
+        Box::pin(async move { Gltf::from_gltf(&self.device, context, &data) })
+    }
+}
 
-        let document = gltf::Gltf::from_slice(&data).unwrap();
+impl Gltf {
+    pub fn from_gltf(
+        device: &wgpu::Device,
+        context: &mut LoadContext,
+        data: &[u8],
+    ) -> Result<Gltf, LoaderError> {
+        let path = context.path().to_path_buf();
+        let settings: MeshSettings = context.settings()?;
+        // Buffers and textures are relative to the gltf file.
+        let folder = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let document = gltf::Gltf::from_slice(data)
+            .map_err(|error| LoaderError::InvalidData(format!("{}: {}", path.display(), error)))?;
 
         let files = document
             .buffers()
@@ -91,8 +121,7 @@ impl Gltf {
 
         let mut buffer_data = Vec::new();
         for file in files {
-            let buffer_path = path.clone().parent().unwrap().join(file);
-            let file = async_std::fs::read(buffer_path).await.unwrap();
+            let file = context.read(folder.join(file))?;
             buffer_data.push(gltf::buffer::Data(file));
         }
 
@@ -102,6 +131,7 @@ impl Gltf {
         let gltf_meshes = document.meshes().collect::<Vec<gltf::Mesh<'_>>>();
 
         let mut meshes = Vec::new();
+        let mut material_count = 0;
 
         for gltf_mesh in gltf_meshes {
             let name = gltf_mesh.name().unwrap_or("mesh").to_string();
@@ -120,8 +150,9 @@ impl Gltf {
                 let positions: Vec<_> = reader
                     .read_positions()
                     .map(|iter| iter.collect())
-                    .ok_or(format!("mesh primitive is missing positions"))
-                    .unwrap();
+                    .ok_or_else(|| {
+                        LoaderError::InvalidData(format!("{}: mesh primitive is missing positions", path.display()))
+                    })?;
 
                 let mut vertices: Vec<MeshVertexData> = positions
                     .iter()
@@ -131,10 +162,12 @@ impl Gltf {
                     })
                     .collect();
 
+                let mut had_normals = false;
                 if let Some(normals) = reader.read_normals() {
                     for (i, normal) in normals.enumerate() {
                         vertices[i].normal = Vec3::from(normal.clone());
                     }
+                    had_normals = true;
                 }
                 if let Some(uvs) = reader.read_tex_coords(0) {
                     for (i, uv) in uvs.into_f32().enumerate() {
@@ -152,10 +185,13 @@ impl Gltf {
                     had_tangents = true;
                 }
 
-                let indices: Vec<u32> = if let Some(index_enum) = reader.read_indices() {
+                let mut indices: Vec<u32> = if let Some(index_enum) = reader.read_indices() {
                     index_enum.into_u32().collect()
                 } else {
-                    panic!("model doesn't have indices");
+                    return Err(LoaderError::InvalidData(format!(
+                        "{}: model doesn't have indices",
+                        path.display()
+                    )));
                 };
 
                 let gltf_material: gltf::Material<'_> = primitive.material();
@@ -199,19 +235,58 @@ impl Gltf {
 
                 let has_pbr_texture = roughness_texture.is_some();
 
+                let texture_path = |texture: Option<String>, default: &str| match texture {
+                    Some(texture) => folder.join(texture).to_string_lossy().into_owned(),
+                    None => default.to_string(),
+                };
                 let material = PBRMaterialRon {
-                    main_texture: main_texture.unwrap_or("core/white.png".to_string()),
-                    normal_texture: normal_texture.unwrap_or("core/empty_normal.png".to_string()),
-                    roughness_texture: roughness_texture.unwrap_or("core/pbr_flat.png".to_string()),
+                    main_texture: texture_path(main_texture, "core/white.png"),
+                    normal_texture: texture_path(normal_texture, "core/empty_normal.png"),
+                    roughness_texture: texture_path(roughness_texture, "core/pbr_flat.png"),
                     roughness,
                     metallic,
                     roughness_override: if has_pbr_texture { 0.0 } else { 1.0 },
                     metallic_override: if has_pbr_texture { 0.0 } else { 1.0 },
                     color,
                 };
-                let material_handle = material_manager.insert(material, path.clone());
-                
+                let material = ron::ser::to_string(&material)
+                    .map_err(|error| LoaderError::InvalidData(error.to_string()))?;
+                let material_handle = context
+                    .load_embedded::<PBRMaterial>(&format!("material{}", material_count), material.into_bytes())?;
+                material_count += 1;
+
                 let primitive_topology = Self::get_primitive_mode(primitive.mode());
+                let is_triangle_list = primitive_topology == wgpu::PrimitiveTopology::TriangleList;
+
+                for vertex in vertices.iter_mut() {
+                    vertex.position *= settings.scale;
+                }
+                if settings.convert_axes {
+                    convert_axes(&mut vertices);
+                    // Mirroring the mesh flips the winding order, swap it back so the same faces are culled.
+                    if is_triangle_list {
+                        for triangle in indices.chunks_exact_mut(3) {
+                            triangle.swap(1, 2);
+                        }
+                    }
+                }
+
+                if !had_normals && settings.generate_normals && is_triangle_list {
+                    log::info!("No normals found generating normals instead!");
+                    generate_normals(&mut vertices, &indices);
+                }
+
+                if !had_tangents && settings.generate_tangents {
+                    log::info!("No tangents found generating tangents instead!",);
+                    mikktspace::generate_tangents(&mut TangentGeometry {
+                        vertices: &mut vertices,
+                        indices: &indices,
+                    });
+                }
+
+                if settings.optimize {
+                    weld_vertices(&mut vertices, &mut indices);
+                }
 
                 let index_buffer = Arc::new(device.create_buffer_with_data(
                     &bytemuck::cast_slice(&indices),
@@ -221,27 +296,21 @@ impl Gltf {
 
                 let bounding_sphere = BoundingSphere::from_points(vertices.iter().map(|x| x.position).collect());
 
-                let mut sub_mesh = SubMesh {
+                let vertex_buffer = device.create_buffer_with_data(
+                    &bytemuck::cast_slice(&vertices),
+                    wgpu::BufferUsage::VERTEX,
+                );
+
+                let sub_mesh = SubMesh {
                     vertices,
                     indices,
                     index_count,
                     mode: primitive_topology,
-                    vertex_buffer: None,
+                    vertex_buffer: Some(Arc::new(vertex_buffer)),
                     index_buffer,
                     bounding_sphere,
                 };
 
-                if !had_tangents {
-                    log::info!("No tangents found generating tangents instead!",);
-                    mikktspace::generate_tangents(&mut sub_mesh);
-                }
-
-                let vertex_buffer = device.create_buffer_with_data(
-                    &bytemuck::cast_slice(&sub_mesh.vertices),
-                    wgpu::BufferUsage::VERTEX,
-                );
-                sub_mesh.vertex_buffer = Some(Arc::new(vertex_buffer));
-
                 mesh.meshes.insert(material_handle, sub_mesh);
             }
 
@@ -252,7 +321,7 @@ impl Gltf {
 
         let bounding_sphere = BoundingSphere::from_bounding_spheres(meshes.iter().map(|x| &x.bounding_sphere).collect());
 
-        Gltf { meshes, bounding_sphere }
+        Ok(Gltf { meshes, bounding_sphere })
     }
 
     fn get_primitive_mode(mode: gltf::mesh::Mode) -> wgpu::PrimitiveTopology {
@@ -299,15 +368,69 @@ impl Gltf {
     }
 }
 
-fn vertex(sub_mesh: &SubMesh, face: usize, vert: usize) -> &MeshVertexData {
-    &sub_mesh.vertices[sub_mesh.indices[face * 3 + vert] as usize]
+// Flips the Z axis to go from glTF's right handed coordinates to harmony's left handed ones.
+fn convert_axes(vertices: &mut [MeshVertexData]) {
+    for vertex in vertices.iter_mut() {
+        vertex.position.z = -vertex.position.z;
+        vertex.normal.z = -vertex.normal.z;
+        // The mirror also flips the handedness of the bitangent.
+        vertex.tangent.z = -vertex.tangent.z;
+        vertex.tangent.w = -vertex.tangent.w;
+    }
+}
+
+// Averages the normals of the triangles around each vertex, larger triangles weigh more.
+fn generate_normals(vertices: &mut [MeshVertexData], indices: &[u32]) {
+    for triangle in indices.chunks_exact(3) {
+        let a = vertices[triangle[0] as usize].position;
+        let b = vertices[triangle[1] as usize].position;
+        let c = vertices[triangle[2] as usize].position;
+        let normal = (b - a).cross(&(c - a));
+        for index in triangle.iter() {
+            vertices[*index as usize].normal += normal;
+        }
+    }
+    for vertex in vertices.iter_mut() {
+        if vertex.normal.norm_squared() > 0.0 {
+            vertex.normal = vertex.normal.normalize();
+        }
+    }
+}
+
+// Merges vertices with the exact same data and points the indices at the merged vertices.
+fn weld_vertices(vertices: &mut Vec<MeshVertexData>, indices: &mut [u32]) {
+    let mut unique: HashMap<[u32; 12], u32> = HashMap::new();
+    let mut welded = Vec::with_capacity(vertices.len());
+    let remap = vertices
+        .iter()
+        .map(|vertex| {
+            let key: [u32; 12] = bytemuck::cast(*vertex);
+            *unique.entry(key).or_insert_with(|| {
+                welded.push(*vertex);
+                welded.len() as u32 - 1
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for index in indices.iter_mut() {
+        *index = remap[*index as usize];
+    }
+    *vertices = welded;
+}
+
+// A primitive's vertices and indices as mikktspace expects them.
+struct TangentGeometry<'a> {
+    vertices: &'a mut [MeshVertexData],
+    indices: &'a [u32],
 }
 
-fn vertex_mut(sub_mesh: &mut SubMesh, face: usize, vert: usize) -> &mut MeshVertexData {
-    &mut sub_mesh.vertices[sub_mesh.indices[face * 3 + vert] as usize]
+impl TangentGeometry<'_> {
+    fn vertex(&self, face: usize, vert: usize) -> &MeshVertexData {
+        &self.vertices[self.indices[face * 3 + vert] as usize]
+    }
 }
 
-impl mikktspace::Geometry for SubMesh {
+impl mikktspace::Geometry for TangentGeometry<'_> {
     fn num_faces(&self) -> usize {
         self.indices.len() / 3
     }
@@ -317,89 +440,114 @@ impl mikktspace::Geometry for SubMesh {
     }
 
     fn position(&self, face: usize, vert: usize) -> [f32; 3] {
-        vertex(self, face, vert).position.into()
+        self.vertex(face, vert).position.into()
     }
 
     fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
-        vertex(self, face, vert).normal.into()
+        self.vertex(face, vert).normal.into()
     }
 
     fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
-        vertex(self, face, vert).uv.into()
+        self.vertex(face, vert).uv.into()
     }
 
     fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
-        vertex_mut(self, face, vert).tangent = tangent.into();
+        let index = self.indices[face * 3 + vert] as usize;
+        self.vertices[index].tangent = tangent.into();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Gltf;
     use crate::{
-        assets::{material_manager::MaterialManager, texture_manager::TextureManager},
+        assets::AssetManager,
         graphics::{pipelines::pbr::create_pbr_bindgroup_layout, resources::GPUResourceManager, shadows::ShadowQuality},
     };
+    use super::{convert_axes, generate_normals, weld_vertices, MeshVertexData};
+    use nalgebra_glm::Vec3;
     use std::{path::PathBuf, sync::Arc};
 
+    #[test]
+    fn should_generate_normals_and_weld_vertices() {
+        let corners = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)];
+        // The second triangle duplicates the vertices of the first one.
+        let mut vertices = corners
+            .iter()
+            .chain(corners.iter())
+            .map(|position| MeshVertexData {
+                position: *position,
+                ..MeshVertexData::default()
+            })
+            .collect::<Vec<_>>();
+        let mut indices = vec![0, 1, 2, 3, 4, 5];
+
+        generate_normals(&mut vertices, &indices);
+        assert!(vertices.iter().all(|vertex| vertex.normal == Vec3::new(0.0, 0.0, 1.0)));
+
+        weld_vertices(&mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 3);
+        assert_eq!(indices, vec![0, 1, 2, 0, 1, 2]);
+
+        convert_axes(&mut vertices);
+        assert!(vertices.iter().all(|vertex| vertex.normal == Vec3::new(0.0, 0.0, -1.0)));
+    }
+
     #[test]
     fn should_load_mesh() {
-        futures::executor::block_on(async {
-            let (_, device, queue) = async_std::task::block_on(async {
-                let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
-                let adapter = instance
-                    .request_adapter(
-                        &wgpu::RequestAdapterOptions {
-                            power_preference: wgpu::PowerPreference::Default,
-                            compatible_surface: None,
-                        },
-                    )
-                    .await
-                    .unwrap();
-
-                let adapter_features = adapter.features();
-                let (device, queue) = adapter
-                    .request_device(
-                        &wgpu::DeviceDescriptor {
-                            features: adapter_features,
-                            limits: wgpu::Limits::default(),
-                            shader_validation: true,
-                        },
-                        None,
-                    )
-                    .await
-                    .unwrap();
-                let arc_device = Arc::new(device);
-                let arc_queue = Arc::new(queue);
-                (adapter, arc_device, arc_queue)
-            });
-
-            let texture_manager = TextureManager::new(device.clone(), queue.clone());
-            
-            let omni_manager = crate::graphics::shadows::OmniShadowManager::new(
-                device.clone(),
-                ShadowQuality::Medium
-            );
-            let gpu_resource_manager = Arc::new(GPUResourceManager::new(device.clone(), &omni_manager));
-
-            let pbr_bind_group_layout = create_pbr_bindgroup_layout(device.clone());
-            gpu_resource_manager
-                .add_bind_group_layout("pbr_material_layout", pbr_bind_group_layout);
-
-            let material_manager = Arc::new(MaterialManager::new(
-                device.clone(),
-                queue,
-                Arc::new(texture_manager),
-                gpu_resource_manager,
-                PathBuf::from("./assets/"),
-            ));
-
-            let _mesh = Gltf::from_gltf(
-                device.clone(),
-                material_manager,
-                PathBuf::from("./assets/example/meshes/cube/cube.gltf"),
-            )
-            .await;
+        let (_, device, queue) = async_std::task::block_on(async {
+            let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+            let adapter = instance
+                .request_adapter(
+                    &wgpu::RequestAdapterOptions {
+                        power_preference: wgpu::PowerPreference::Default,
+                        compatible_surface: None,
+                    },
+                )
+                .await
+                .unwrap();
+
+            let adapter_features = adapter.features();
+            let (device, queue) = adapter
+                .request_device(
+                    &wgpu::DeviceDescriptor {
+                        features: adapter_features,
+                        limits: wgpu::Limits::default(),
+                        shader_validation: true,
+                    },
+                    None,
+                )
+                .await
+                .unwrap();
+            let arc_device = Arc::new(device);
+            let arc_queue = Arc::new(queue);
+            (adapter, arc_device, arc_queue)
         });
+
+        let omni_manager = crate::graphics::shadows::OmniShadowManager::new(
+            device.clone(),
+            ShadowQuality::Medium
+        );
+        let gpu_resource_manager = Arc::new(GPUResourceManager::new(device.clone(), &omni_manager));
+
+        let pbr_bind_group_layout = create_pbr_bindgroup_layout(device.clone());
+        gpu_resource_manager
+            .add_bind_group_layout("pbr_material_layout", pbr_bind_group_layout);
+
+        let mut asset_manager = AssetManager::new(
+            PathBuf::from("./assets"),
+            device,
+            queue,
+            gpu_resource_manager,
+        );
+        let mesh_handle = asset_manager.get_mesh("example/meshes/cube/cube.gltf");
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        let gltf = mesh_handle.get().unwrap();
+        for material in gltf.meshes.iter().flat_map(|mesh| mesh.meshes.keys()) {
+            assert!(material.get().is_ok());
+        }
+
+        // The buffer file was read by the gltf loader so changing it reloads the mesh.
+        assert!(asset_manager.reload("example/meshes/cube/Cube.bin"));
     }
 }
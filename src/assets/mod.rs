@@ -1,11 +1,23 @@
 mod asset_manager;
 pub use asset_manager::AssetManager;
 
+mod asset_watcher;
+
+mod bundle;
+pub use bundle::{BundleBuilder, BundleCompression, BundleEntry, BundleSource};
+
+mod import_settings;
+pub use import_settings::{
+    AddressMode, FilterMode, MeshSettings, SamplerSettings, ShaderSettings, TextureCompression, TextureSettings,
+};
+
+mod source;
+pub use source::{AssetSource, DirectorySource, MemorySource, OverlaySource};
+
 pub mod image;
 pub use self::image::Image;
 
 pub mod material;
-mod material_manager;
 
 pub mod texture;
 mod texture_manager;
@@ -13,8 +25,10 @@ mod texture_manager;
 mod file_manager;
 pub use file_manager::{AssetCache, AssetError, AssetHandle, FileManager};
 
+mod loader;
+pub use loader::{AssetLoader, LoadContext, LoaderError};
+
 pub mod shader;
 mod shader_manager;
 
 pub mod mesh;
-mod mesh_manager;
@@ -1,6 +1,8 @@
+use super::{import_settings::read_settings, AssetSource, ShaderSettings};
+use crate::HarmonyError;
 use shaderc;
 use std::io::BufRead;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{borrow::Cow, sync::Arc};
 
 pub enum Shader {
@@ -18,14 +20,26 @@ pub struct ComputeShader {
 }
 
 impl Shader {
-    pub fn new<T: Into<PathBuf>>(device: Arc<wgpu::Device>, path: T) -> Arc<Self> {
-        let path = path.into();
-        let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
-        let path = path.parent().unwrap();
+    /// Compiles the shader at `path`. The `.shader` file, the glsl files it lists and their includes are read from `source`.
+    /// Macros listed in the shader's `.meta` file are defined for every stage.
+    pub fn new<T: Into<PathBuf>>(
+        device: Arc<wgpu::Device>,
+        source: &dyn AssetSource,
+        path: T,
+    ) -> Result<Arc<Self>, HarmonyError> {
+        let full_path = path.into();
+        let error = |message: String| HarmonyError::ShaderCompilation {
+            path: full_path.clone(),
+            message,
+        };
+        let file_name = full_path.file_name().unwrap().to_str().unwrap().to_string();
+        let path = full_path.parent().unwrap();
 
         // Compiler
-        let mut compiler = shaderc::Compiler::new().unwrap();
-        let mut options = shaderc::CompileOptions::new().unwrap();
+        let mut compiler = shaderc::Compiler::new()
+            .ok_or_else(|| error("Couldn't create the shader compiler.".to_string()))?;
+        let mut options = shaderc::CompileOptions::new()
+            .ok_or_else(|| error("Couldn't create the shader compile options.".to_string()))?;
 
         #[cfg(not(debug_assertions))]
         {
@@ -37,10 +51,16 @@ impl Shader {
         }
 
         options.add_macro_definition("EP", Some("main"));
+        let settings: ShaderSettings =
+            read_settings(source, &full_path).map_err(|e| error(e.to_string()))?;
+        for (name, value) in settings.defines.iter() {
+            let value = if value.is_empty() { None } else { Some(value.as_str()) };
+            options.add_macro_definition(name, value);
+        }
         options.set_include_callback(|file_path, _include_type, _, _| {
             let shader_path = path.clone().join(file_path);
-            // let mut contents: String = "".into();
-            let contents = std::fs::read_to_string(&shader_path).unwrap();
+            let contents = read_to_string(source, &shader_path)
+                .map_err(|error| format!("{}: {}", shader_path.display(), error))?;
             Result::Ok(shaderc::ResolvedInclude {
                 resolved_name: file_path.to_string(),
                 content: contents,
@@ -48,16 +68,16 @@ impl Shader {
         });
 
         let shader_path = path.join(file_name);
-        let file = std::fs::File::open(&shader_path).unwrap();
+        let file = source.read(&shader_path).map_err(|e| error(e.to_string()))?;
 
-        let shader_file = std::io::BufReader::new(&file);
+        let shader_file = std::io::BufReader::new(file.as_slice());
         let mut vert_file_name = String::new();
         let mut frag_file_name = String::new();
         let mut comp_file_name = String::new();
         let mut lines = shader_file.lines();
 
         while let Some(line) = lines.next() {
-            let current_line = line.unwrap();
+            let current_line = line.map_err(|e| error(e.to_string()))?;
             if current_line.contains("frag") {
                 frag_file_name = current_line;
             } else if current_line.contains("vert") {
@@ -69,15 +89,15 @@ impl Shader {
 
         // Pixel
         let shader_path = path.join(frag_file_name.clone());
-        let frag_contents = std::fs::read_to_string(&shader_path);
+        let frag_contents = read_to_string(source, &shader_path);
 
         // Vertex
         let shader_path = path.join(vert_file_name.clone());
-        let vert_contents = std::fs::read_to_string(&shader_path);
+        let vert_contents = read_to_string(source, &shader_path);
 
         // Vertex
         let shader_path = path.join(comp_file_name.clone());
-        let comp_contents = std::fs::read_to_string(&shader_path);
+        let comp_contents = read_to_string(source, &shader_path);
 
         options.add_macro_definition("EP", Some("main"));
 
@@ -90,7 +110,7 @@ impl Shader {
                     "main",
                     Some(&options),
                 )
-                .unwrap();
+                .map_err(|e| error(e.to_string()))?;
             Some(device.create_shader_module(wgpu::ShaderModuleSource::SpirV(Cow::Borrowed(spirv.as_binary()))))
         } else { None };
 
@@ -103,7 +123,7 @@ impl Shader {
                     "main",
                     Some(&options),
                 )
-                .unwrap();
+                .map_err(|e| error(e.to_string()))?;
             Some(device.create_shader_module(wgpu::ShaderModuleSource::SpirV(Cow::Borrowed(spirv.as_binary()))))
         } else { None };
 
@@ -116,28 +136,34 @@ impl Shader {
                     "main",
                     Some(&options),
                 )
-                .unwrap();
+                .map_err(|e| error(e.to_string()))?;
             Some(device.create_shader_module(wgpu::ShaderModuleSource::SpirV(Cow::Borrowed(spirv.as_binary()))))
         } else { None };
 
         if fragment.is_some() && vertex.is_some() {
-            return Arc::new(Shader::Core(CoreShader {
+            Ok(Arc::new(Shader::Core(CoreShader {
                 fragment: fragment.unwrap(),
                 vertex: vertex.unwrap()
-            }));
+            })))
         } else if compute.is_some() {
-            return Arc::new(Shader::Compute(ComputeShader {
+            Ok(Arc::new(Shader::Compute(ComputeShader {
                 compute: compute.unwrap(),
-            }));
+            })))
         } else {
-            panic!("Couldn't figure out shader type!");
+            Err(error("Couldn't figure out shader type!".to_string()))
         }
     }
 }
 
+fn read_to_string(source: &dyn AssetSource, path: &Path) -> std::io::Result<String> {
+    let file = source.read(path)?;
+    String::from_utf8(file).map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
 #[cfg(test)]
 mod tests {
     use super::Shader;
+    use crate::assets::DirectorySource;
     use std::sync::Arc;
 
     #[test]
@@ -168,8 +194,10 @@ mod tests {
                 .unwrap();
 
             let device = Arc::new(device);
+            let source = DirectorySource::new("./assets");
 
-            Shader::new(device, "./assets/core/shaders/pbr.shader");
+            assert!(Shader::new(device.clone(), &source, "core/shaders/pbr.shader").is_ok());
+            assert!(Shader::new(device, &source, "core/shaders/missing.shader").is_err());
         });
     }
 }
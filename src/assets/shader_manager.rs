@@ -1,18 +1,25 @@
 use super::{
-    file_manager::{AssetCache, AssetHandle},
+    file_manager::{AssetCache, AssetError, AssetHandle},
+    import_settings::asset_path,
     shader::Shader,
+    AssetSource,
 };
+use crate::HarmonyError;
 use futures::executor::{ThreadPool, ThreadPoolBuilder};
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 pub struct ShaderManager {
     pool: Arc<ThreadPool>,
     cache: AssetCache<Shader>,
     device: Arc<wgpu::Device>,
+    source: Arc<dyn AssetSource>,
 }
 
 impl ShaderManager {
-    pub fn new(device: Arc<wgpu::Device>) -> Self {
+    pub fn new(device: Arc<wgpu::Device>, source: Arc<dyn AssetSource>) -> Self {
         // TODO: One pool that we pass in is probably enough.
         let pool = Arc::new(ThreadPoolBuilder::new().pool_size(4).create().unwrap());
         let cache = Arc::new(dashmap::DashMap::new());
@@ -20,11 +27,27 @@ impl ShaderManager {
             pool,
             cache,
             device,
+            source,
         }
     }
 
     pub fn get<P: Into<PathBuf>>(&self, path: P) -> Arc<AssetHandle<Shader>> {
         let path = path.into();
+        match self.load(path.clone()) {
+            Ok(asset_handle) => asset_handle,
+            Err(error) => {
+                log::error!("{}", error);
+                Arc::new(AssetHandle::new(path, self.cache.clone()))
+            }
+        }
+    }
+
+    // Same as `get` but returns the compile error if the shader wasn't already loaded.
+    pub(crate) fn load<P: Into<PathBuf>>(
+        &self,
+        path: P,
+    ) -> Result<Arc<AssetHandle<Shader>>, HarmonyError> {
+        let path = path.into();
 
         let asset_handle = Arc::new(AssetHandle::new(path.clone(), self.cache.clone()));
 
@@ -33,25 +56,67 @@ impl ShaderManager {
 
             let asset_thread_handle = asset_handle.clone();
             let device = self.device.clone();
+            let source = self.source.clone();
 
             // TODO: Figure out why shaderc needs to be Send for this to use the pool..
             // TODO: Just fix this when naga comes out..
             // self.pool.spawn_ok(async move {
-            // TODO: Make sure we return errors!!
-            let shader = Shader::new(device, path.clone());
-            
-            log::info!("{:?} loaded.", path.file_name().unwrap());
-            cache.insert(asset_thread_handle.handle_id.clone(), Ok(shader));
+            match Shader::new(device, source.as_ref(), path.clone()) {
+                Ok(shader) => {
+                    log::info!("{:?} loaded.", path.file_name().unwrap());
+                    cache.insert(asset_thread_handle.handle_id.clone(), Ok(shader));
+                }
+                Err(error) => {
+                    cache.insert(
+                        asset_thread_handle.handle_id.clone(),
+                        Err(Arc::new(AssetError::InvalidData)),
+                    );
+                    return Err(error);
+                }
+            }
             // });
         }
 
-        asset_handle
+        Ok(asset_handle)
+    }
+
+    // Compiles a loaded shader again, `path` can also be the shader's `.meta` settings file.
+    // If compiling fails the error is logged and the old shader is kept. Returns the shader if it compiled.
+    pub(crate) fn reload(&self, path: &Path) -> Option<PathBuf> {
+        let path = if self.cache.contains_key(path) {
+            path.to_path_buf()
+        } else {
+            asset_path(path).filter(|path| self.cache.contains_key(path))?
+        };
+        match Shader::new(self.device.clone(), self.source.as_ref(), &path) {
+            Ok(shader) => {
+                log::info!("{:?} reloaded.", path.file_name().unwrap());
+                self.cache.insert(path.clone(), Ok(shader));
+                Some(path)
+            }
+            Err(error) => {
+                log::error!("{}", error);
+                None
+            }
+        }
+    }
+
+    // Compiles every loaded shader again, used when a glsl file changes since any shader could include it.
+    // Returns the shaders that compiled.
+    pub(crate) fn reload_all(&self) -> Vec<PathBuf> {
+        let paths = self
+            .cache
+            .iter()
+            .map(|item| item.key().clone())
+            .collect::<Vec<_>>();
+        paths.into_iter().filter_map(|path| self.reload(&path)).collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::ShaderManager;
+    use crate::assets::DirectorySource;
     use std::sync::Arc;
 
     #[test]
@@ -86,8 +151,8 @@ mod tests {
             (adapter, device)
         });
 
-        let shader_manager = ShaderManager::new(device);
-        let handle = shader_manager.get("./assets/core/shaders/pbr.shader");
+        let shader_manager = ShaderManager::new(device, Arc::new(DirectorySource::new("./assets")));
+        let handle = shader_manager.get("core/shaders/pbr.shader");
         let shader = handle.get();
         assert!(shader.is_ok());
     }
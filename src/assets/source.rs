@@ -0,0 +1,239 @@
+use dashmap::DashMap;
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use walkdir::WalkDir;
+
+/// Where the asset managers read files from. Paths are relative to the root of the source.
+pub trait AssetSource: Send + Sync {
+    /// Reads a whole file.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Returns true if the file exists.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Lists the files inside of a folder and its sub folders. An empty path lists every file.
+    fn list(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+}
+
+// Drops `.` components so `./core/white.png` and `core/white.png` refer to the same file.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Reads assets from a folder on disk.
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl AssetSource for DirectorySource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).is_file()
+    }
+
+    fn list(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let folder = self.root.join(path);
+        let folder = if folder.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            folder
+        };
+
+        // A missing folder has no files, ex: when every asset ships in a bundle.
+        if !folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for entry in WalkDir::new(folder) {
+            let entry = entry.map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+            if entry.file_type().is_file() {
+                let file = entry.path().strip_prefix(&self.root).unwrap_or(entry.path());
+                files.push(normalize(file));
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Keeps assets in memory, ex: for tests that shouldn't touch the disk or assets generated at runtime.
+#[derive(Default)]
+pub struct MemorySource {
+    files: DashMap<PathBuf, Arc<Vec<u8>>>,
+}
+
+impl MemorySource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a file.
+    pub fn insert<P: AsRef<Path>, D: Into<Vec<u8>>>(&self, path: P, data: D) {
+        self.files
+            .insert(normalize(path.as_ref()), Arc::new(data.into()));
+    }
+
+    /// Removes a file.
+    pub fn remove<P: AsRef<Path>>(&self, path: P) {
+        self.files.remove(&normalize(path.as_ref()));
+    }
+}
+
+impl AssetSource for MemorySource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.files.get(&normalize(path)) {
+            Some(file) => Ok(file.value().as_ref().clone()),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} isn't in memory.", path.display()),
+            )),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+
+    fn list(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let folder = normalize(path);
+        Ok(self
+            .files
+            .iter()
+            .filter(|file| file.key().starts_with(&folder))
+            .map(|file| file.key().clone())
+            .collect())
+    }
+}
+
+/// Stacks sources on top of each other, files in sources mounted later hide the same files in earlier ones.
+/// Used to mount mods or patches over the base content.
+pub struct OverlaySource {
+    layers: RwLock<Vec<Arc<dyn AssetSource>>>,
+}
+
+impl OverlaySource {
+    /// Creates an overlay with `base` as its bottom layer.
+    pub fn new(base: Arc<dyn AssetSource>) -> Self {
+        Self {
+            layers: RwLock::new(vec![base]),
+        }
+    }
+
+    /// Mounts a source on top of the existing ones.
+    pub fn mount(&self, source: Arc<dyn AssetSource>) {
+        self.layers.write().unwrap().push(source);
+    }
+
+    // Returns the top most layer that has the file.
+    fn find(&self, path: &Path) -> Option<Arc<dyn AssetSource>> {
+        self.layers
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|layer| layer.exists(path))
+            .cloned()
+    }
+}
+
+impl AssetSource for OverlaySource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.find(path) {
+            Some(layer) => layer.read(path),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} wasn't found in any mounted source.", path.display()),
+            )),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.find(path).is_some()
+    }
+
+    fn list(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        for layer in self.layers.read().unwrap().iter() {
+            for file in layer.list(path)? {
+                let file = normalize(&file);
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetSource, DirectorySource, MemorySource, OverlaySource};
+    use std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    #[test]
+    fn should_read_from_directory() {
+        let source = DirectorySource::new("./assets");
+        assert!(source.exists(Path::new("material.ron")));
+        assert!(source.read(Path::new("material.ron")).unwrap().starts_with(b"PBRMaterialRon"));
+        assert!(source
+            .list(Path::new("core"))
+            .unwrap()
+            .contains(&PathBuf::from("core/white.png")));
+    }
+
+    #[test]
+    fn should_read_from_memory() {
+        let source = MemorySource::new();
+        source.insert("./textures/test.png.meta", "(format: SRGB)");
+        assert!(source.exists(Path::new("textures/test.png.meta")));
+        assert_eq!(
+            source.read(Path::new("textures/test.png.meta")).unwrap(),
+            b"(format: SRGB)".to_vec()
+        );
+        assert_eq!(
+            source.list(Path::new("textures")).unwrap(),
+            vec![PathBuf::from("textures/test.png.meta")]
+        );
+
+        source.remove("textures/test.png.meta");
+        let error = source.read(Path::new("textures/test.png.meta")).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn should_prefer_mounted_sources() {
+        let base = MemorySource::new();
+        base.insert("material.ron", "base");
+        base.insert("shader.glsl", "base");
+        let overlay = OverlaySource::new(Arc::new(base));
+
+        let patch = MemorySource::new();
+        patch.insert("material.ron", "patch");
+        overlay.mount(Arc::new(patch));
+
+        assert_eq!(overlay.read(Path::new("material.ron")).unwrap(), b"patch".to_vec());
+        assert_eq!(overlay.read(Path::new("shader.glsl")).unwrap(), b"base".to_vec());
+        assert!(!overlay.exists(Path::new("missing.ron")));
+
+        let mut files = overlay.list(Path::new("")).unwrap();
+        files.sort();
+        assert_eq!(files, vec![PathBuf::from("material.ron"), PathBuf::from("shader.glsl")]);
+    }
+}
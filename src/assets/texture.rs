@@ -1,11 +1,13 @@
-use super::{image::ImageRon, Image};
-use std::{path::PathBuf, sync::Arc};
+use super::{image::ImageFormat, Image, TextureCompression, TextureSettings};
+use std::{borrow::Cow, path::PathBuf, sync::Arc};
 
 // Texture represents data on the GPU.
 pub struct Texture {
     pub(crate) path: PathBuf,
     pub inner: wgpu::Texture,
     pub view: wgpu::TextureView,
+    /// Sampler described by the texture's import settings.
+    pub sampler: wgpu::Sampler,
     pub extent: wgpu::Extent3d,
 }
 
@@ -23,7 +25,7 @@ impl Texture {
         device: Arc<wgpu::Device>,
         queue: Arc<wgpu::Queue>,
         image: Arc<Image>,
-        image_ron: Option<ImageRon>,
+        settings: &TextureSettings,
         path: PathBuf,
     ) -> Self {
         let extent = wgpu::Extent3d {
@@ -32,44 +34,104 @@ impl Texture {
             depth: 1,
         };
 
-        let format = if image_ron.is_some() {
-            image_ron.unwrap().format.into()
-        } else {
-            // Default to Rgba8UnormSrgb
-            wgpu::TextureFormat::Rgba8UnormSrgb
+        let compress = settings.compression == TextureCompression::Bc1
+            && Self::can_compress(&device, &image, settings.format);
+        let format = match (compress, settings.format) {
+            (true, ImageFormat::SRGB) => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+            (true, _) => wgpu::TextureFormat::Bc1RgbaUnorm,
+            (false, format) => format.into(),
         };
 
+        let mut mips = vec![image];
+        while settings.mips {
+            let mip = mips.last().unwrap();
+            if mip.width == 1 && mip.height == 1 {
+                break;
+            }
+            let mip = mip.downsample(settings.format);
+            // Compressed mips have to be made of whole blocks.
+            if compress && (mip.width % 4 != 0 || mip.height % 4 != 0) {
+                break;
+            }
+            mips.push(Arc::new(mip));
+        }
+
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             size: extent,
-            mip_level_count: 1,
+            mip_level_count: mips.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
             usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
             label: None,
         });
-        queue.write_texture(
-            wgpu::TextureCopyView {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &image.data[..],
-            wgpu::TextureDataLayout {
-                offset: 0,
-                bytes_per_row: (image.data.len() as f64 / extent.height as f64) as u32,
-                rows_per_image: extent.height,
-            },
-            extent,
-        );
+
+        for (mip_level, mip) in mips.iter().enumerate() {
+            let (data, bytes_per_row) = if compress {
+                // BC1 stores each 4x4 block in 8 bytes.
+                (Cow::Owned(mip.compress_bc1()), mip.width / 4 * 8)
+            } else {
+                (
+                    Cow::Borrowed(mip.data.as_slice()),
+                    (mip.data.len() as f64 / mip.height as f64) as u32,
+                )
+            };
+            queue.write_texture(
+                wgpu::TextureCopyView {
+                    texture: &texture,
+                    mip_level: mip_level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                &data[..],
+                wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row,
+                    rows_per_image: mip.height,
+                },
+                wgpu::Extent3d {
+                    width: mip.width,
+                    height: mip.height,
+                    depth: 1,
+                },
+            );
+        }
 
         let view = texture.create_default_view();
 
+        let address_mode = settings.sampler.address_mode.into();
+        let filter = settings.sampler.filter.into();
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("TextureSampler"),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: filter,
+            ..Default::default()
+        });
+
         Texture {
             path,
             inner: texture,
             view,
+            sampler,
             extent,
         }
     }
+
+    // Textures that can't be compressed are logged and stay uncompressed.
+    fn can_compress(device: &wgpu::Device, image: &Image, format: ImageFormat) -> bool {
+        let reason = if format == ImageFormat::HDR16 || format == ImageFormat::HDR32 {
+            "hdr textures can't be compressed to BC1"
+        } else if image.width % 4 != 0 || image.height % 4 != 0 {
+            "its size isn't a multiple of 4"
+        } else if !device.features().contains(wgpu::Features::TEXTURE_COMPRESSION_BC) {
+            "the device doesn't support BC compression"
+        } else {
+            return true;
+        };
+        log::warn!("{:?} won't be compressed, {}.", image.path, reason);
+        false
+    }
 }
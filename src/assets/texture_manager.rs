@@ -1,11 +1,15 @@
 use super::{
     file_manager::{AssetCache, AssetError, AssetHandle},
-    image::ImageRon,
+    import_settings::{asset_path, read_settings},
     texture::Texture,
-    Image,
+    AssetSource, Image, TextureSettings,
 };
 use futures::executor::{ThreadPool, ThreadPoolBuilder};
-use std::{convert::TryFrom, path::PathBuf, sync::Arc};
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use dashmap::DashSet;
 
 pub struct TextureManager {
@@ -13,25 +17,31 @@ pub struct TextureManager {
     queue: Arc<wgpu::Queue>,
     pool: Arc<ThreadPool>,
     image_cache: AssetCache<Image>,
-    ron_cache: AssetCache<ImageRon>,
+    settings_cache: AssetCache<TextureSettings>,
     texture_cache: AssetCache<Texture>,
     loaded: DashSet<PathBuf>,
+    source: Arc<dyn AssetSource>,
 }
 
 impl TextureManager {
-    pub fn new(device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>) -> Self {
+    pub fn new(
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        source: Arc<dyn AssetSource>,
+    ) -> Self {
         let pool = Arc::new(ThreadPoolBuilder::new().pool_size(4).create().unwrap());
         let image_cache = Arc::new(dashmap::DashMap::new());
-        let ron_cache = Arc::new(dashmap::DashMap::new());
+        let settings_cache = Arc::new(dashmap::DashMap::new());
         let texture_cache = Arc::new(dashmap::DashMap::new());
         Self {
             device,
             queue,
             pool,
             image_cache,
-            ron_cache,
+            settings_cache,
             texture_cache,
             loaded: DashSet::new(),
+            source,
         }
     }
 
@@ -40,159 +50,122 @@ impl TextureManager {
         let texture_handle = Arc::new(AssetHandle::new(path.clone(), self.texture_cache.clone()));
 
         if !self.loaded.contains(&path) {
-            let ext = path.extension().unwrap().to_str().unwrap().to_string(); 
             self.loaded.insert(path.clone());
-        
-            // Cross thread arcs passed to new thread.
-            let image_cache = self.image_cache.clone();
-            let ron_cache = self.ron_cache.clone();
-            let texture_cache = self.texture_cache.clone();
-            let texture_thread_handle = texture_handle.clone();
-            let device = self.device.clone();
-            let queue = self.queue.clone();
 
+            // Cross thread arcs passed to new thread.
+            let loader = self.loader();
             self.pool.spawn_ok(async move {
-                let mut ron_path = path.clone();
-                ron_path.set_extension(format!("{}{}", ext, ".ron"));
-                let image_file = async_std::fs::read(path.clone()).await;
-                let ron_file = async_std::fs::read(ron_path).await;
-
-                let result = match image_file {
-                    Ok(image_data) => {
-                        // Attempt to load ron file..
-                        let image_ron = if ron_file.is_ok() {
-                            Some(ImageRon::try_from((path.clone(), ron_file.unwrap())).unwrap())
-                        } else {
-                            None
-                        };
-
-                        let image = Arc::new(
-                            Image::try_from((image_ron, path.clone(), image_data)).unwrap(),
-                        );
-                        // Store image in cache.
-                        image_cache
-                            .insert(texture_thread_handle.handle_id.clone(), Ok(image.clone()));
-
-                        let result = Ok(Arc::new(Texture::new(
-                            device.clone(),
-                            queue.clone(),
-                            image,
-                            image_ron,
-                            path.clone(),
-                        )));
-
-                        let image_ron = match image_ron {
-                            Some(ron) => Ok(Arc::new(ron)),
-                            None => Err(Arc::new(AssetError::FileNotFound)),
-                        };
-
-                        ron_cache.insert(texture_thread_handle.handle_id.clone(), image_ron);
-
-                        // queue.submit(None);
-                        // device.poll(wgpu::Maintain::Wait);
-                        
-                        log::info!("{:?} loaded.", path);
-                        result
-                    }
-                    Err(error) => match error.kind() {
-                        std::io::ErrorKind::NotFound => Err(Arc::new(AssetError::FileNotFound)),
-                        _ => Err(Arc::new(AssetError::OtherError(error))),
-                    },
-                };
-
-                texture_cache.insert(texture_thread_handle.handle_id.clone(), result);
+                loader.load(path);
             });
         }
 
         texture_handle
     }
 
-    // Assures the asset is loaded before returning the asset handle.
+    // Assures the asset is loaded, or failed to load, before returning the asset handle.
     pub async fn get_async<P: Into<PathBuf>>(&self, path: P) -> Arc<AssetHandle<Texture>> {
         let path = path.into();
         let texture_handle = Arc::new(AssetHandle::new(path.clone(), self.texture_cache.clone()));
 
         if !self.loaded.contains(&path) {
-            let ext = path.extension().unwrap().to_str().unwrap().to_string(); 
             self.loaded.insert(path.clone());
-            
-            // Cross thread arcs passed to new thread.
-            let image_cache = self.image_cache.clone();
-            let ron_cache = self.ron_cache.clone();
-            let texture_cache = self.texture_cache.clone();
-            let texture_thread_handle = texture_handle.clone();
-            let device = self.device.clone();
-            let queue = self.queue.clone();
-
-            let mut ron_path = path.clone();
-            ron_path.set_extension(format!("{}{}", ext, ".ron"));
-            let image_file = async_std::fs::read(path.clone()).await;
-            let ron_file = async_std::fs::read(ron_path).await;
-
-            let result = match image_file {
-                Ok(image_data) => {
-                    // Attempt to load ron file..
-                    let image_ron = if ron_file.is_ok() {
-                        Some(ImageRon::try_from((path.clone(), ron_file.unwrap())).unwrap())
-                    } else {
-                        None
-                    };
-
-                    let image =
-                        Arc::new(Image::try_from((image_ron, path.clone(), image_data)).unwrap());
-                    // Store image in cache.
-                    image_cache.insert(texture_thread_handle.handle_id.clone(), Ok(image.clone()));
-
-                    let result = Ok(Arc::new(Texture::new(
-                        device.clone(),
-                        queue.clone(),
-                        image,
-                        image_ron,
-                        path.clone(),
-                    )));
-
-                    let image_ron = match image_ron {
-                        Some(ron) => Ok(Arc::new(ron)),
-                        None => Err(Arc::new(AssetError::FileNotFound)),
-                    };
-
-                    ron_cache.insert(texture_thread_handle.handle_id.clone(), image_ron);
-
-                    // queue.submit(None);
-                    // device.poll(wgpu::Maintain::Wait);
-                    
-                    log::info!("{:?} loaded.", path);
-                    result
-                }
-                Err(error) => match error.kind() {
-                    std::io::ErrorKind::NotFound => Err(Arc::new(AssetError::FileNotFound)),
-                    _ => Err(Arc::new(AssetError::OtherError(error))),
-                },
-            };
-
-            texture_cache.insert(texture_thread_handle.handle_id.clone(), result);
+            self.loader().load(path);
         } else {
-            loop {
-                let result = texture_handle.get();
-                if result.is_ok() {
-                    break;
-                }
+            // Another thread is loading the texture, let other tasks run until its result is in the cache.
+            while !self.texture_cache.contains_key(&path) {
+                async_std::task::yield_now().await;
             }
         }
 
         texture_handle
     }
+
+    // Loads a texture again if it was loaded before, `path` can also be the texture's `.meta` settings file.
+    // The old texture is dropped right away so materials that wait on the texture get the new one.
+    pub(crate) fn reload(&self, path: &Path) -> Option<PathBuf> {
+        let texture_path = if self.loaded.contains(path) {
+            path.to_path_buf()
+        } else {
+            asset_path(path)?
+        };
+        if self.loaded.remove(&texture_path).is_none() {
+            return None;
+        }
+
+        self.texture_cache.remove(&texture_path);
+        self.image_cache.remove(&texture_path);
+        self.settings_cache.remove(&texture_path);
+        self.get(texture_path.clone());
+        Some(texture_path)
+    }
+
+    fn loader(&self) -> TextureLoader {
+        TextureLoader {
+            device: self.device.clone(),
+            queue: self.queue.clone(),
+            image_cache: self.image_cache.clone(),
+            settings_cache: self.settings_cache.clone(),
+            texture_cache: self.texture_cache.clone(),
+            source: self.source.clone(),
+        }
+    }
+}
+
+// Everything needed to load a texture, so it can be moved to the thread pool.
+struct TextureLoader {
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+    image_cache: AssetCache<Image>,
+    settings_cache: AssetCache<TextureSettings>,
+    texture_cache: AssetCache<Texture>,
+    source: Arc<dyn AssetSource>,
+}
+
+impl TextureLoader {
+    fn load(&self, path: PathBuf) {
+        let result = self.source.read(&path).map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => Arc::new(AssetError::FileNotFound),
+            _ => Arc::new(AssetError::OtherError(error)),
+        });
+
+        let result = result.and_then(|image_data| {
+            let settings: TextureSettings = read_settings(self.source.as_ref(), &path)
+                .map_err(|error| Arc::new(AssetError::Loader(error)))?;
+            self.settings_cache.insert(path.clone(), Ok(Arc::new(settings)));
+
+            let image = Image::try_from((settings.format, path.clone(), image_data)).map_err(|error| {
+                log::error!("Couldn't load {:?}: {}", path, error);
+                Arc::new(AssetError::InvalidData)
+            })?;
+            let image = Arc::new(image);
+            // Store image in cache.
+            self.image_cache.insert(path.clone(), Ok(image.clone()));
+
+            let texture = Texture::new(
+                self.device.clone(),
+                self.queue.clone(),
+                image,
+                &settings,
+                path.clone(),
+            );
+
+            log::info!("{:?} loaded.", path);
+            Ok(Arc::new(texture))
+        });
+
+        self.texture_cache.insert(path, result);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::AssetError;
     use super::TextureManager;
+    use crate::assets::DirectorySource;
     use std::sync::Arc;
 
-    #[test]
-    fn should_load_texture() {
-        let (_, device, queue) = async_std::task::block_on(async {
+    fn create_device() -> (Arc<wgpu::Device>, Arc<wgpu::Queue>) {
+        async_std::task::block_on(async {
             let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
             let adapter = instance
                 .request_adapter(
@@ -216,14 +189,17 @@ mod tests {
                 )
                 .await
                 .unwrap();
-            let arc_device = Arc::new(device);
-            let arc_queue = Arc::new(queue);
-            (adapter, arc_device, arc_queue)
-        });
+            (Arc::new(device), Arc::new(queue))
+        })
+    }
 
-        let texture_manager = TextureManager::new(device, queue);
+    #[test]
+    fn should_load_texture() {
+        let (device, queue) = create_device();
 
-        let handle = texture_manager.get("./assets/core/white.png");
+        let texture_manager = TextureManager::new(device, queue, Arc::new(DirectorySource::new("./assets")));
+
+        let handle = texture_manager.get("core/white.png");
         let asset = handle.get();
         assert!(match *asset.err().unwrap() {
             AssetError::Loading => true,
@@ -235,4 +211,28 @@ mod tests {
         let asset = handle.get();
         assert!(asset.is_ok());
     }
+
+    #[test]
+    fn should_fail_reloading_corrupt_texture() {
+        let (device, queue) = create_device();
+        let folder = std::env::temp_dir().join("harmony_corrupt_texture");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::copy("./assets/core/white.png", folder.join("white.png")).unwrap();
+
+        let texture_manager = TextureManager::new(device, queue, Arc::new(DirectorySource::new(folder.clone())));
+        let handle = async_std::task::block_on(texture_manager.get_async("white.png"));
+        assert!(handle.get().is_ok());
+
+        // A texture that's still being written when it's reloaded.
+        std::fs::write(folder.join("white.png"), &[0x89, b'P', b'N', b'G']).unwrap();
+        assert!(texture_manager.reload(std::path::Path::new("white.png")).is_some());
+
+        // Waiting on the texture ends with the error instead of waiting forever.
+        let handle = async_std::task::block_on(texture_manager.get_async("white.png"));
+        assert!(match *handle.get().err().unwrap() {
+            AssetError::InvalidData => true,
+            _ => false,
+        });
+        std::fs::remove_dir_all(folder).unwrap();
+    }
 }
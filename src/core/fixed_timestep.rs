@@ -0,0 +1,119 @@
+// Splits variable frame times into fixed updates, shared by `Application` and `SceneRunner`
+// so recordings play back with the same fixed updates everywhere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FixedTimestep {
+    // Always positive, see `set_timestep`.
+    timestep: f32,
+    pub(crate) max_substeps: u32,
+    // Time that hasn't been consumed by fixed updates yet.
+    pub(crate) accumulator: f32,
+}
+
+// Used instead of timesteps that would never or endlessly run fixed updates.
+const DEFAULT_TIMESTEP: f32 = 1.0 / 60.0;
+
+impl FixedTimestep {
+    pub(crate) fn new(timestep: f32, max_substeps: u32) -> Self {
+        let mut fixed_timestep = Self {
+            timestep: DEFAULT_TIMESTEP,
+            max_substeps,
+            accumulator: 0.0,
+        };
+        fixed_timestep.set_timestep(timestep);
+        fixed_timestep
+    }
+
+    pub(crate) fn timestep(&self) -> f32 {
+        self.timestep
+    }
+
+    // Changes the time between fixed updates, timesteps that are zero, negative or not finite are replaced by
+    // the default of 60 updates per second.
+    pub(crate) fn set_timestep(&mut self, timestep: f32) {
+        self.timestep = if timestep > 0.0 && timestep.is_finite() {
+            timestep
+        } else {
+            log::error!(
+                "Invalid fixed timestep {}, using {} instead.",
+                timestep,
+                DEFAULT_TIMESTEP
+            );
+            DEFAULT_TIMESTEP
+        };
+    }
+
+    // Adds the frame's time and returns how many fixed updates to run.
+    pub(crate) fn advance(&mut self, frame_time: f32) -> u32 {
+        // Would leave the accumulator negative or NaN and stop every fixed update after it.
+        if !frame_time.is_finite() || frame_time < 0.0 {
+            log::warn!("Ignoring invalid frame time {}.", frame_time);
+            return 0;
+        }
+        self.accumulator += frame_time;
+
+        let mut substeps = 0;
+        while self.accumulator >= self.timestep {
+            if substeps >= self.max_substeps {
+                // We can't keep up so drop the remaining time instead of spiraling further behind.
+                log::warn!(
+                    "Frame took too long, skipping {:.2}ms of updates.",
+                    (self.accumulator - self.accumulator % self.timestep) * 1000.0
+                );
+                self.accumulator %= self.timestep;
+                break;
+            }
+            self.accumulator -= self.timestep;
+            substeps += 1;
+        }
+        substeps
+    }
+
+    // How far the frame is between the last fixed update and the next one.
+    pub(crate) fn alpha(&self) -> f32 {
+        self.accumulator / self.timestep
+    }
+
+    // Drops leftover time so the next fixed updates don't depend on the frames before, ex: when recording starts.
+    pub(crate) fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedTimestep;
+
+    #[test]
+    fn should_split_frames_into_fixed_updates() {
+        let mut fixed_timestep = FixedTimestep::new(0.01, 3);
+        assert_eq!(fixed_timestep.advance(0.004), 0);
+        assert_eq!(fixed_timestep.advance(0.008), 1);
+        assert!((fixed_timestep.alpha() - 0.2).abs() < 1e-4);
+
+        // Time beyond `max_substeps` is dropped.
+        assert_eq!(fixed_timestep.advance(0.1), 3);
+        assert!(fixed_timestep.alpha() < 1.0);
+
+        fixed_timestep.reset();
+        assert_eq!(fixed_timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn should_reject_invalid_timesteps() {
+        for timestep in [0.0, -0.01, std::f32::NAN, std::f32::INFINITY].iter() {
+            let mut fixed_timestep = FixedTimestep::new(*timestep, 3);
+            assert_eq!(fixed_timestep.timestep(), 1.0 / 60.0);
+            assert_eq!(fixed_timestep.advance(0.02), 1);
+
+            fixed_timestep.set_timestep(0.01);
+            fixed_timestep.set_timestep(*timestep);
+            assert_eq!(fixed_timestep.timestep(), 1.0 / 60.0);
+        }
+
+        let mut fixed_timestep = FixedTimestep::new(0.01, 3);
+        assert_eq!(fixed_timestep.advance(-1.0), 0);
+        assert_eq!(fixed_timestep.advance(std::f32::NAN), 0);
+        assert_eq!(fixed_timestep.alpha(), 0.0);
+        assert_eq!(fixed_timestep.advance(0.01), 1);
+    }
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+use winit::event::VirtualKeyCode;
+
+use super::{GamepadAxis, GamepadButton, MouseButton};
+
+/// A physical input an action or axis can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    /// A button on any connected gamepad.
+    GamepadButton(GamepadButton),
+}
+
+/// Bindings that move an axis towards -1.0 or 1.0.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisBinding {
+    pub negative: Vec<Binding>,
+    pub positive: Vec<Binding>,
+    /// Gamepad axes added on top of the buttons, read from any connected gamepad.
+    pub gamepad_axes: Vec<GamepadAxis>,
+}
+
+/// Maps action and axis names to bindings. Stored in `Input`, see `Input::set_action_map`.
+///
+/// Example RON file:
+/// ```ron
+/// (
+///     actions: {
+///         "jump": [Key(Space), GamepadButton(South)],
+///         "fire": [Mouse(Left), Key(LControl)],
+///     },
+///     axes: {
+///         "move_x": (negative: [Key(A)], positive: [Key(D)], gamepad_axes: [LeftStickX]),
+///     },
+/// )
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionMap {
+    pub actions: HashMap<String, Vec<Binding>>,
+    pub axes: HashMap<String, AxisBinding>,
+}
+
+impl ActionMap {
+    /// Parses an action map from a RON string.
+    pub fn from_ron(action_map: &str) -> Result<Self, ron::de::Error> {
+        ron::de::from_str(action_map)
+    }
+
+    /// Loads an action map from a RON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ron::de::Error> {
+        let file = std::fs::File::open(path)?;
+        ron::de::from_reader(file)
+    }
+
+    /// Writes the action map to a RON file, ex: to keep bindings the player changed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ron::Error> {
+        let action_map = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, action_map)?;
+        Ok(())
+    }
+
+    /// Adds a binding to an action, creating the action if needed.
+    pub fn bind<T: Into<String>>(&mut self, action: T, binding: Binding) {
+        let bindings = self.actions.entry(action.into()).or_insert_with(Vec::new);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes a binding from an action.
+    pub fn unbind(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|other| *other != binding);
+        }
+    }
+
+    /// Replaces all of an action's bindings with a single binding.
+    pub fn rebind<T: Into<String>>(&mut self, action: T, binding: Binding) {
+        self.actions.insert(action.into(), vec![binding]);
+    }
+
+    /// Replaces an axis' bindings.
+    pub fn bind_axis<T: Into<String>>(&mut self, axis: T, binding: AxisBinding) {
+        self.axes.insert(axis.into(), binding);
+    }
+
+    /// Returns the bindings of an action.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], |bindings| bindings.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionMap, Binding};
+    use crate::core::input::{Input, InputChange, MouseButton};
+    use winit::event::VirtualKeyCode;
+
+    const ACTIONS: &str = r#"(
+        actions: {
+            "jump": [Key(Space)],
+            "fire": [Mouse(Left)],
+        },
+        axes: {
+            "move_x": (negative: [Key(A)], positive: [Key(D)]),
+        },
+    )"#;
+
+    #[test]
+    fn should_query_actions() {
+        let mut input = Input::new();
+        input.set_action_map(ActionMap::from_ron(ACTIONS).unwrap());
+
+        input.apply(InputChange::Key(VirtualKeyCode::Space, true));
+        input.apply(InputChange::Key(VirtualKeyCode::D, true));
+        input.apply(InputChange::MouseButton(MouseButton::Left, true));
+        assert!(input.action_pressed("jump"));
+        assert!(input.action_down("fire"));
+        assert_eq!(input.axis("move_x"), 1.0);
+        assert!(!input.action_pressed("unknown"));
+
+        input.clear();
+        input.apply(InputChange::Key(VirtualKeyCode::A, true));
+        assert!(!input.action_pressed("jump"));
+        assert!(input.action_down("jump"));
+        assert_eq!(input.axis("move_x"), 0.0);
+
+        input.apply(InputChange::Key(VirtualKeyCode::Space, false));
+        assert!(input.action_released("jump"));
+    }
+
+    #[test]
+    fn should_rebind_actions() {
+        let mut input = Input::new();
+        input.set_action_map(ActionMap::from_ron(ACTIONS).unwrap());
+        input
+            .action_map_mut()
+            .rebind("jump", Binding::Key(VirtualKeyCode::W));
+
+        input.apply(InputChange::Key(VirtualKeyCode::Space, true));
+        assert!(!input.action_down("jump"));
+        input.apply(InputChange::Key(VirtualKeyCode::W, true));
+        assert!(input.action_down("jump"));
+        assert_eq!(
+            input.action_map().bindings("jump"),
+            &[Binding::Key(VirtualKeyCode::W)]
+        );
+    }
+
+    #[test]
+    fn should_load_action_map_file() {
+        let action_map = ActionMap::load("./assets/input.ron").unwrap();
+        assert_eq!(
+            action_map.bindings("toggle_mouse"),
+            &[Binding::Key(VirtualKeyCode::Tab)]
+        );
+        assert!(action_map.axes.contains_key("move_x"));
+    }
+
+    #[test]
+    fn should_ignore_input_captured_by_ui() {
+        let mut input = Input::new();
+        input.set_action_map(ActionMap::from_ron(ACTIONS).unwrap());
+        input.set_ui_capture(true, true);
+
+        input.apply(InputChange::Key(VirtualKeyCode::Space, true));
+        input.apply(InputChange::MouseButton(MouseButton::Left, true));
+        assert!(!input.action_down("jump"));
+        assert!(!input.is_mouse_button_down(MouseButton::Left));
+    }
+}
@@ -0,0 +1,355 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Identifies a connected gamepad. Ids of disconnected gamepads can be reused.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+/// Buttons named after their position on the gamepad, ex: `South` is A on an Xbox controller and Cross on a PlayStation controller.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// Sticks go from -1.0 to 1.0, up and right are positive. Triggers go from 0.0 to 1.0.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    ButtonPressed(GamepadId, GamepadButton),
+    ButtonReleased(GamepadId, GamepadButton),
+    /// The raw value without the dead zone applied.
+    AxisChanged(GamepadId, GamepadAxis, f32),
+}
+
+/// Strength of the gamepad's motors from 0.0 to 1.0.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rumble {
+    /// The low frequency motor.
+    pub strong: f32,
+    /// The high frequency motor.
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+/// Where gamepad events come from. The application polls the backend once per frame and passes the events to `Input`.
+/// Rumble requested with `Input::rumble` is passed on to the backend after `AppState::update`.
+pub trait GamepadBackend {
+    /// Appends the events that happened since the last call.
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+
+    /// Returns false if the gamepad isn't connected or can't rumble.
+    fn rumble(&mut self, gamepad: GamepadId, rumble: Rumble) -> bool;
+}
+
+// The state of a connected gamepad, stored in `Input`.
+#[derive(Debug, Default)]
+pub(crate) struct GamepadState {
+    pub(crate) buttons_down: HashSet<GamepadButton>,
+    pub(crate) axes: HashMap<GamepadAxis, f32>,
+}
+
+// Scales the value so it starts at 0.0 right outside of the dead zone instead of jumping to the dead zone's value.
+pub(crate) fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        return 0.0;
+    }
+    value.signum() * ((value.abs() - dead_zone) / (1.0 - dead_zone)).min(1.0)
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    events: Vec<GamepadEvent>,
+    rumbles: Vec<(GamepadId, Rumble)>,
+}
+
+/// A backend that returns events pushed from code, used for tests.
+/// Clones share their events so a clone can be kept around after handing the backend to the application.
+#[derive(Debug, Clone, Default)]
+pub struct MockGamepadBackend {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockGamepadBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues an event for the next poll.
+    pub fn push(&self, event: GamepadEvent) {
+        self.state.lock().unwrap().events.push(event);
+    }
+
+    /// Every rumble that was requested so far.
+    pub fn rumbles(&self) -> Vec<(GamepadId, Rumble)> {
+        self.state.lock().unwrap().rumbles.clone()
+    }
+}
+
+impl GamepadBackend for MockGamepadBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.append(&mut self.state.lock().unwrap().events);
+    }
+
+    fn rumble(&mut self, gamepad: GamepadId, rumble: Rumble) -> bool {
+        self.state.lock().unwrap().rumbles.push((gamepad, rumble));
+        true
+    }
+}
+
+#[cfg(feature = "gilrs")]
+pub use self::gilrs_backend::GilrsBackend;
+
+#[cfg(feature = "gilrs")]
+mod gilrs_backend {
+    use super::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, Rumble};
+    use gilrs::{
+        ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks},
+        Axis, Button, EventType, Gilrs,
+    };
+    use std::collections::HashMap;
+
+    /// The default backend, uses gilrs to read gamepads.
+    pub struct GilrsBackend {
+        gilrs: Gilrs,
+        // Effects stop playing once they are dropped.
+        effects: HashMap<GamepadId, Effect>,
+    }
+
+    impl GilrsBackend {
+        pub fn new() -> Result<Self, gilrs::Error> {
+            Ok(Self {
+                gilrs: Gilrs::new()?,
+                effects: HashMap::new(),
+            })
+        }
+    }
+
+    fn map_button(button: Button) -> Option<GamepadButton> {
+        match button {
+            Button::South => Some(GamepadButton::South),
+            Button::East => Some(GamepadButton::East),
+            Button::North => Some(GamepadButton::North),
+            Button::West => Some(GamepadButton::West),
+            Button::LeftTrigger => Some(GamepadButton::LeftBumper),
+            Button::RightTrigger => Some(GamepadButton::RightBumper),
+            Button::LeftTrigger2 => Some(GamepadButton::LeftTrigger),
+            Button::RightTrigger2 => Some(GamepadButton::RightTrigger),
+            Button::Select => Some(GamepadButton::Select),
+            Button::Start => Some(GamepadButton::Start),
+            Button::Mode => Some(GamepadButton::Mode),
+            Button::LeftThumb => Some(GamepadButton::LeftThumb),
+            Button::RightThumb => Some(GamepadButton::RightThumb),
+            Button::DPadUp => Some(GamepadButton::DPadUp),
+            Button::DPadDown => Some(GamepadButton::DPadDown),
+            Button::DPadLeft => Some(GamepadButton::DPadLeft),
+            Button::DPadRight => Some(GamepadButton::DPadRight),
+            _ => None,
+        }
+    }
+
+    fn map_axis(axis: Axis) -> Option<GamepadAxis> {
+        match axis {
+            Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+            Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+            Axis::RightStickX => Some(GamepadAxis::RightStickX),
+            Axis::RightStickY => Some(GamepadAxis::RightStickY),
+            _ => None,
+        }
+    }
+
+    impl GamepadBackend for GilrsBackend {
+        fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+            while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+                let id = GamepadId(id.into());
+                let event = match event {
+                    EventType::Connected => Some(GamepadEvent::Connected(id)),
+                    EventType::Disconnected => {
+                        self.effects.remove(&id);
+                        Some(GamepadEvent::Disconnected(id))
+                    }
+                    EventType::ButtonPressed(button, _) => {
+                        map_button(button).map(|button| GamepadEvent::ButtonPressed(id, button))
+                    }
+                    EventType::ButtonReleased(button, _) => {
+                        map_button(button).map(|button| GamepadEvent::ButtonReleased(id, button))
+                    }
+                    // Analog triggers are reported as buttons with a value.
+                    EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                        Some(GamepadEvent::AxisChanged(id, GamepadAxis::LeftTrigger, value))
+                    }
+                    EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                        Some(GamepadEvent::AxisChanged(id, GamepadAxis::RightTrigger, value))
+                    }
+                    EventType::AxisChanged(axis, value, _) => {
+                        map_axis(axis).map(|axis| GamepadEvent::AxisChanged(id, axis, value))
+                    }
+                    _ => None,
+                };
+                events.extend(event);
+            }
+        }
+
+        fn rumble(&mut self, gamepad: GamepadId, rumble: Rumble) -> bool {
+            let id = match self
+                .gilrs
+                .gamepads()
+                .find(|(id, gamepad_data)| {
+                    let index: usize = (*id).into();
+                    index == gamepad.0 && gamepad_data.is_ff_supported()
+                })
+            {
+                Some((id, _)) => id,
+                None => return false,
+            };
+
+            let duration = Ticks::from_ms(rumble.duration.as_millis() as u32);
+            let scheduling = Replay {
+                play_for: duration,
+                ..Default::default()
+            };
+            let effect = EffectBuilder::new()
+                .add_effect(BaseEffect {
+                    kind: BaseEffectType::Strong {
+                        magnitude: (rumble.strong.max(0.0).min(1.0) * u16::MAX as f32) as u16,
+                    },
+                    scheduling,
+                    ..Default::default()
+                })
+                .add_effect(BaseEffect {
+                    kind: BaseEffectType::Weak {
+                        magnitude: (rumble.weak.max(0.0).min(1.0) * u16::MAX as f32) as u16,
+                    },
+                    scheduling,
+                    ..Default::default()
+                })
+                .gamepads(&[id])
+                .repeat(Repeat::For(duration))
+                .finish(&mut self.gilrs);
+
+            match effect.and_then(|effect| effect.play().map(|_| effect)) {
+                Ok(effect) => {
+                    self.effects.insert(gamepad, effect);
+                    true
+                }
+                Err(error) => {
+                    log::warn!("Couldn't rumble gamepad {:?}: {}", gamepad, error);
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_dead_zone, GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId,
+        MockGamepadBackend, Rumble,
+    };
+    use crate::core::input::{ActionMap, Input};
+    use std::time::Duration;
+
+    #[test]
+    fn should_apply_dead_zone() {
+        assert_eq!(apply_dead_zone(0.05, 0.1), 0.0);
+        assert_eq!(apply_dead_zone(-0.1, 0.1), 0.0);
+        assert_eq!(apply_dead_zone(1.0, 0.1), 1.0);
+        assert!((apply_dead_zone(-0.55, 0.1) + 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn should_track_gamepads() {
+        let pad = GamepadId(0);
+        let mut backend = MockGamepadBackend::new();
+        backend.push(GamepadEvent::Connected(pad));
+        backend.push(GamepadEvent::ButtonPressed(pad, GamepadButton::South));
+        backend.push(GamepadEvent::AxisChanged(pad, GamepadAxis::LeftStickX, 0.05));
+
+        let mut input = Input::new();
+        input.set_action_map(
+            ActionMap::from_ron(
+                r#"(
+                    actions: { "jump": [Key(Space), GamepadButton(South)] },
+                    axes: { "move_x": (negative: [Key(A)], positive: [Key(D)], gamepad_axes: [LeftStickX]) },
+                )"#,
+            )
+            .unwrap(),
+        );
+
+        let mut events = Vec::new();
+        backend.poll(&mut events);
+        input.update_gamepad_events(&events);
+
+        assert!(input.is_gamepad_connected(pad));
+        assert_eq!(input.gamepad_events(), &[GamepadEvent::Connected(pad)]);
+        assert!(input.is_gamepad_button_pressed(pad, GamepadButton::South));
+        assert!(input.action_pressed("jump"));
+        // Inside of the dead zone.
+        assert_eq!(input.axis("move_x"), 0.0);
+
+        input.clear();
+        backend.push(GamepadEvent::AxisChanged(pad, GamepadAxis::LeftStickX, -1.0));
+        backend.push(GamepadEvent::Disconnected(pad));
+        let mut events = Vec::new();
+        backend.poll(&mut events);
+        input.update_gamepad_events(&events);
+
+        assert!(!input.is_gamepad_connected(pad));
+        assert!(!input.action_down("jump"));
+        assert_eq!(input.gamepad_events(), &[GamepadEvent::Disconnected(pad)]);
+    }
+
+    #[test]
+    fn should_queue_rumble() {
+        let pad = GamepadId(1);
+        let rumble = Rumble {
+            strong: 1.0,
+            weak: 0.5,
+            duration: Duration::from_millis(200),
+        };
+        let mut input = Input::new();
+        input.rumble(pad, rumble);
+
+        let backend = MockGamepadBackend::new();
+        let mut boxed: Box<dyn GamepadBackend> = Box::new(backend.clone());
+        for (gamepad, rumble) in input.take_rumbles() {
+            boxed.rumble(gamepad, rumble);
+        }
+        assert_eq!(backend.rumbles(), vec![(pad, rumble)]);
+        assert!(input.take_rumbles().is_empty());
+    }
+}
@@ -0,0 +1,755 @@
+use nalgebra_glm::Vec2;
+use std::collections::{HashMap, HashSet};
+use winit::event::{ModifiersState, MouseScrollDelta, VirtualKeyCode};
+
+mod actions;
+pub use actions::{ActionMap, AxisBinding, Binding};
+
+mod gamepad;
+#[cfg(feature = "gilrs")]
+pub use gamepad::GilrsBackend;
+pub use gamepad::{
+    GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, MockGamepadBackend, Rumble,
+};
+use gamepad::GamepadState;
+
+mod recording;
+pub use recording::{InputChange, InputRecording, RecordedFrame};
+
+const DEFAULT_DEAD_ZONE: f32 = 0.1;
+// Used to turn pixel scroll deltas into lines for `Input::mouse_wheel_movement`.
+const PIXELS_PER_LINE: f32 = 20.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[allow(missing_docs)]
+/// A button on a mouse.
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    X1,
+    X2,
+}
+
+fn map_mouse_button(button: winit::event::MouseButton) -> Option<MouseButton> {
+    match button {
+        winit::event::MouseButton::Left => Some(MouseButton::Left),
+        winit::event::MouseButton::Right => Some(MouseButton::Right),
+        winit::event::MouseButton::Middle => Some(MouseButton::Middle),
+        winit::event::MouseButton::Other(8) => Some(MouseButton::X1),
+        winit::event::MouseButton::Other(9) => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+// Presses, releases and movement since the edges were last cleared.
+#[derive(Debug, Default)]
+struct Edges {
+    keys_pressed: HashSet<VirtualKeyCode>,
+    keys_released: HashSet<VirtualKeyCode>,
+    mouse_buttons_pressed: HashSet<MouseButton>,
+    mouse_buttons_released: HashSet<MouseButton>,
+    mouse_delta: Vec2,
+    mouse_wheel_lines: Vec2,
+    mouse_wheel_pixels: Vec2,
+    text_input: String,
+    gamepad_buttons_pressed: HashSet<(GamepadId, GamepadButton)>,
+    gamepad_buttons_released: HashSet<(GamepadId, GamepadButton)>,
+    // Connected and disconnected events.
+    gamepad_events: Vec<GamepadEvent>,
+}
+
+/// The state of the keyboard, mouse and gamepads.
+///
+/// Presses, releases, scrolling, typed text and mouse movement are kept separately for fixed updates and for
+/// per frame updates. Each fixed update sees what happened since the previous fixed update and each frame sees
+/// what happened since the previous frame, so an edge is seen by exactly one fixed update and one frame
+/// even when several frames run between two fixed updates.
+#[derive(Debug)]
+pub struct Input {
+    keys_down: HashSet<VirtualKeyCode>,
+    mouse_buttons_down: HashSet<MouseButton>,
+    /// Current mouse_position
+    pub mouse_position: Vec2,
+    /// Raw mouse movement since the last frame, or since the last fixed update inside of fixed updates.
+    /// Not affected by cursor acceleration or the window's edges.
+    pub mouse_delta: Vec2,
+    modifiers: ModifiersState,
+    fixed_edges: Edges,
+    frame_edges: Edges,
+    // True while fixed updates run, queries then read `fixed_edges`.
+    in_fixed_update: bool,
+    // Set with `Application::set_relative_mouse`.
+    pub(crate) relative_mouse: bool,
+    action_map: ActionMap,
+    // Imgui wants the mouse or keyboard for itself, ex: the cursor is over a window or a text field has focus.
+    ui_wants_mouse: bool,
+    ui_wants_keyboard: bool,
+
+    gamepads: HashMap<GamepadId, GamepadState>,
+    dead_zone: f32,
+    rumbles: Vec<(GamepadId, Rumble)>,
+    // Changes applied since the last frame was recorded, `None` while not recording.
+    recorded: Option<Vec<InputChange>>,
+}
+
+impl Input {
+    pub(crate) fn new() -> Self {
+        Self {
+            keys_down: HashSet::new(),
+            mouse_buttons_down: HashSet::new(),
+            mouse_position: Vec2::zeros(),
+            mouse_delta: Vec2::zeros(),
+            modifiers: ModifiersState::empty(),
+            fixed_edges: Edges::default(),
+            frame_edges: Edges::default(),
+            in_fixed_update: false,
+            relative_mouse: false,
+            action_map: ActionMap::default(),
+            ui_wants_mouse: false,
+            ui_wants_keyboard: false,
+            gamepads: HashMap::new(),
+            dead_zone: DEFAULT_DEAD_ZONE,
+            rumbles: Vec::new(),
+            recorded: None,
+        }
+    }
+
+    /// Will return true if the specified key is down.
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// Will return true if the specified key is pressed.
+    pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.edges().keys_pressed.contains(&key)
+    }
+
+    /// Will return true if the specified key is released.
+    pub fn is_key_released(&self, key: VirtualKeyCode) -> bool {
+        self.edges().keys_released.contains(&key)
+    }
+
+    /// The modifier keys that are currently held.
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Returns true if either shift key is down.
+    pub fn is_shift_down(&self) -> bool {
+        self.modifiers.shift()
+    }
+
+    /// Returns true if either control key is down.
+    pub fn is_ctrl_down(&self) -> bool {
+        self.modifiers.ctrl()
+    }
+
+    /// Returns true if either alt key is down.
+    pub fn is_alt_down(&self) -> bool {
+        self.modifiers.alt()
+    }
+
+    /// Returns true if the windows/command key is down.
+    pub fn is_logo_down(&self) -> bool {
+        self.modifiers.logo()
+    }
+
+    /// Characters typed this frame, already adjusted for the keyboard layout and shift.
+    /// Control characters such as backspace are left out, use `is_key_pressed` for those.
+    pub fn text_input(&self) -> &str {
+        &self.edges().text_input
+    }
+
+    /// Scrolling this frame reported in lines by mouse wheels. Positive y scrolls up, positive x scrolls right.
+    pub fn mouse_wheel_lines(&self) -> Vec2 {
+        self.edges().mouse_wheel_lines
+    }
+
+    /// Scrolling this frame reported in pixels by touchpads.
+    pub fn mouse_wheel_pixels(&self) -> Vec2 {
+        self.edges().mouse_wheel_pixels
+    }
+
+    /// All scrolling this frame in lines, pixel deltas are converted using a fixed line height.
+    /// Good enough for zooming a camera, use `mouse_wheel_lines` and `mouse_wheel_pixels` to handle each kind differently.
+    pub fn mouse_wheel_movement(&self) -> Vec2 {
+        self.mouse_wheel_lines() + self.mouse_wheel_pixels() / PIXELS_PER_LINE
+    }
+
+    /// Returns true if the cursor is hidden and grabbed, only `mouse_delta` should be used in this mode.
+    pub fn is_relative_mouse(&self) -> bool {
+        self.relative_mouse
+    }
+
+    /// Returns true if the UI is using the mouse, mouse buttons aren't passed on to the game while this is true.
+    pub fn ui_wants_mouse(&self) -> bool {
+        self.ui_wants_mouse
+    }
+
+    /// Returns true if the UI is using the keyboard, ex: a text field has focus.
+    /// Keys aren't passed on to the game while this is true.
+    pub fn ui_wants_keyboard(&self) -> bool {
+        self.ui_wants_keyboard
+    }
+
+    /// The bindings used by `action_down`, `action_pressed`, `action_released` and `axis`.
+    pub fn action_map(&self) -> &ActionMap {
+        &self.action_map
+    }
+
+    /// Use this to change bindings at runtime.
+    pub fn action_map_mut(&mut self) -> &mut ActionMap {
+        &mut self.action_map
+    }
+
+    /// Replaces the bindings, ex: with bindings loaded using `ActionMap::load`.
+    pub fn set_action_map(&mut self, action_map: ActionMap) {
+        self.action_map = action_map;
+    }
+
+    /// Returns true if any of the action's bindings are down.
+    pub fn action_down(&self, action: &str) -> bool {
+        self.action_map
+            .bindings(action)
+            .iter()
+            .any(|binding| self.is_binding_down(*binding))
+    }
+
+    /// Returns true if any of the action's bindings were pressed this frame.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.action_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => self.is_key_pressed(*key),
+                Binding::Mouse(button) => self.is_mouse_button_pressed(*button),
+                Binding::GamepadButton(button) => self
+                    .edges()
+                    .gamepad_buttons_pressed
+                    .iter()
+                    .any(|(_, pressed)| pressed == button),
+            })
+    }
+
+    /// Returns true if any of the action's bindings were released this frame.
+    pub fn action_released(&self, action: &str) -> bool {
+        self.action_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => self.is_key_released(*key),
+                Binding::Mouse(button) => self.is_mouse_button_released(*button),
+                Binding::GamepadButton(button) => self
+                    .edges()
+                    .gamepad_buttons_released
+                    .iter()
+                    .any(|(_, released)| released == button),
+            })
+    }
+
+    /// Returns a value between -1.0 and 1.0, or 0.0 if the axis doesn't exist.
+    pub fn axis(&self, axis: &str) -> f32 {
+        let binding = match self.action_map.axes.get(axis) {
+            Some(binding) => binding,
+            None => return 0.0,
+        };
+        let is_down = |bindings: &[Binding]| bindings.iter().any(|binding| self.is_binding_down(*binding));
+
+        let mut value = 0.0;
+        if is_down(&binding.negative) {
+            value -= 1.0;
+        }
+        if is_down(&binding.positive) {
+            value += 1.0;
+        }
+        for gamepad_axis in binding.gamepad_axes.iter() {
+            for id in self.gamepads.keys() {
+                value += self.gamepad_axis(*id, *gamepad_axis);
+            }
+        }
+        value.max(-1.0).min(1.0)
+    }
+
+    fn is_binding_down(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.is_key_down(key),
+            Binding::Mouse(button) => self.is_mouse_button_down(button),
+            Binding::GamepadButton(button) => self
+                .gamepads
+                .values()
+                .any(|gamepad| gamepad.buttons_down.contains(&button)),
+        }
+    }
+
+    /// The ids of every connected gamepad.
+    pub fn gamepads(&self) -> Vec<GamepadId> {
+        let mut gamepads = self.gamepads.keys().copied().collect::<Vec<_>>();
+        gamepads.sort();
+        gamepads
+    }
+
+    /// Returns true if the gamepad is connected.
+    pub fn is_gamepad_connected(&self, gamepad: GamepadId) -> bool {
+        self.gamepads.contains_key(&gamepad)
+    }
+
+    /// `GamepadEvent::Connected` and `GamepadEvent::Disconnected` events from this frame.
+    pub fn gamepad_events(&self) -> &[GamepadEvent] {
+        &self.edges().gamepad_events
+    }
+
+    /// Will return true if the specified gamepad button is down.
+    pub fn is_gamepad_button_down(&self, gamepad: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&gamepad)
+            .map_or(false, |gamepad| gamepad.buttons_down.contains(&button))
+    }
+
+    /// Will return true if the specified gamepad button is pressed.
+    pub fn is_gamepad_button_pressed(&self, gamepad: GamepadId, button: GamepadButton) -> bool {
+        self.edges().gamepad_buttons_pressed.contains(&(gamepad, button))
+    }
+
+    /// Will return true if the specified gamepad button is released.
+    pub fn is_gamepad_button_released(&self, gamepad: GamepadId, button: GamepadButton) -> bool {
+        self.edges().gamepad_buttons_released.contains(&(gamepad, button))
+    }
+
+    /// Returns the axis' value with the dead zone applied, or 0.0 if the gamepad isn't connected.
+    pub fn gamepad_axis(&self, gamepad: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .get(&gamepad)
+            .and_then(|gamepad| gamepad.axes.get(&axis))
+            .map_or(0.0, |value| gamepad::apply_dead_zone(*value, self.dead_zone))
+    }
+
+    /// Axis values closer to 0.0 than the dead zone are reported as 0.0. Defaults to 0.1.
+    pub fn set_gamepad_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.max(0.0).min(0.99);
+    }
+
+    /// Rumbles the gamepad. Requests are sent to the `GamepadBackend` at the end of the frame.
+    pub fn rumble(&mut self, gamepad: GamepadId, rumble: Rumble) {
+        self.rumbles.push((gamepad, rumble));
+    }
+
+    pub(crate) fn take_rumbles(&mut self) -> Vec<(GamepadId, Rumble)> {
+        std::mem::replace(&mut self.rumbles, Vec::new())
+    }
+
+    pub(crate) fn update_gamepad_events(&mut self, events: &[GamepadEvent]) {
+        for event in events {
+            self.apply(InputChange::Gamepad(*event));
+        }
+    }
+
+    /// Will return true if the specified mouse button is down.
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_down.contains(&button)
+    }
+
+    /// Will return true if the specified mouse button is pressed.
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.edges().mouse_buttons_pressed.contains(&button)
+    }
+
+    /// Will return true if the specified mouse button is released.
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.edges().mouse_buttons_released.contains(&button)
+    }
+
+    pub(crate) fn update_events(&mut self, winit_event: &winit::event::Event<'_, ()>) {
+        let change = match winit_event {
+            winit::event::Event::WindowEvent { event, .. } => match event {
+                winit::event::WindowEvent::KeyboardInput { input, .. } => input.virtual_keycode.map(|key| {
+                    InputChange::Key(key, input.state == winit::event::ElementState::Pressed)
+                }),
+                winit::event::WindowEvent::MouseInput {
+                    device_id: _,
+                    state,
+                    button,
+                    ..
+                } => map_mouse_button(*button).map(|mouse_button| {
+                    InputChange::MouseButton(
+                        mouse_button,
+                        *state == winit::event::ElementState::Pressed,
+                    )
+                }),
+                winit::event::WindowEvent::CursorMoved { position, .. } => Some(
+                    InputChange::MousePosition(position.x as f32, position.y as f32),
+                ),
+                winit::event::WindowEvent::MouseWheel { delta, .. } => Some(match delta {
+                    MouseScrollDelta::LineDelta(x, y) => InputChange::ScrollLines(*x, *y),
+                    MouseScrollDelta::PixelDelta(position) => {
+                        InputChange::ScrollPixels(position.x as f32, position.y as f32)
+                    }
+                }),
+                winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+                    Some(InputChange::Modifiers(*modifiers))
+                }
+                winit::event::WindowEvent::ReceivedCharacter(character) => {
+                    Some(InputChange::Character(*character))
+                }
+                // Keys and buttons can't be released while the window doesn't have focus.
+                winit::event::WindowEvent::Focused(false) => {
+                    self.release_all();
+                    Some(InputChange::Modifiers(ModifiersState::empty()))
+                }
+                _ => None,
+            },
+            winit::event::Event::DeviceEvent { event, .. } => match event {
+                winit::event::DeviceEvent::MouseMotion { delta } => {
+                    Some(InputChange::MouseDelta(delta.0 as f32, delta.1 as f32))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(change) = change {
+            self.apply(change);
+        }
+    }
+
+    // Releases every held key and mouse button through `apply` so the releases are recorded and seen as edges.
+    fn release_all(&mut self) {
+        let mut keys = self.keys_down.iter().copied().collect::<Vec<_>>();
+        keys.sort();
+        for key in keys {
+            self.apply(InputChange::Key(key, false));
+        }
+        let buttons = self.mouse_buttons_down.iter().copied().collect::<Vec<_>>();
+        for button in buttons {
+            self.apply(InputChange::MouseButton(button, false));
+        }
+    }
+
+    /// Applies a change to the input state. Changes the UI captured are dropped, ex: key presses while a text field has focus.
+    pub(crate) fn apply(&mut self, change: InputChange) {
+        let applied = self.apply_change(change);
+        if applied {
+            if let Some(recorded) = self.recorded.as_mut() {
+                recorded.push(change);
+            }
+        }
+    }
+
+    // Presses are ignored while the UI has captured the device, releases always go through so nothing gets stuck.
+    fn apply_change(&mut self, change: InputChange) -> bool {
+        let ui_has_mouse = self.ui_wants_mouse && !self.relative_mouse;
+        match change {
+            InputChange::Key(key, true) => {
+                if self.ui_wants_keyboard {
+                    return false;
+                }
+                self.keys_down.insert(key);
+                self.update_edges(|edges| {
+                    edges.keys_pressed.insert(key);
+                });
+            }
+            InputChange::Key(key, false) => {
+                if !self.keys_down.remove(&key) {
+                    return false;
+                }
+                self.update_edges(|edges| {
+                    edges.keys_released.insert(key);
+                });
+            }
+            InputChange::MouseButton(button, true) => {
+                if ui_has_mouse {
+                    return false;
+                }
+                self.mouse_buttons_down.insert(button);
+                self.update_edges(|edges| {
+                    edges.mouse_buttons_pressed.insert(button);
+                });
+            }
+            InputChange::MouseButton(button, false) => {
+                if !self.mouse_buttons_down.remove(&button) {
+                    return false;
+                }
+                self.update_edges(|edges| {
+                    edges.mouse_buttons_released.insert(button);
+                });
+            }
+            InputChange::MousePosition(x, y) => self.mouse_position = Vec2::new(x, y),
+            // More than one motion event can arrive per frame.
+            InputChange::MouseDelta(x, y) => {
+                if ui_has_mouse {
+                    return false;
+                }
+                self.mouse_delta += Vec2::new(x, y);
+                self.update_edges(|edges| edges.mouse_delta += Vec2::new(x, y));
+            }
+            InputChange::ScrollLines(x, y) => {
+                if ui_has_mouse {
+                    return false;
+                }
+                self.update_edges(|edges| edges.mouse_wheel_lines += Vec2::new(x, y));
+            }
+            InputChange::ScrollPixels(x, y) => {
+                if ui_has_mouse {
+                    return false;
+                }
+                self.update_edges(|edges| edges.mouse_wheel_pixels += Vec2::new(x, y));
+            }
+            InputChange::Modifiers(modifiers) => self.modifiers = modifiers,
+            InputChange::Character(character) => {
+                if self.ui_wants_keyboard || character.is_control() {
+                    return false;
+                }
+                self.update_edges(|edges| edges.text_input.push(character));
+            }
+            InputChange::Gamepad(event) => self.apply_gamepad_event(event),
+            InputChange::KeyHeld(key) => {
+                self.keys_down.insert(key);
+            }
+            InputChange::MouseButtonHeld(button) => {
+                self.mouse_buttons_down.insert(button);
+            }
+            InputChange::GamepadPresent(id) => {
+                self.gamepads.entry(id).or_default();
+            }
+            InputChange::GamepadButtonHeld(id, button) => {
+                self.gamepads.entry(id).or_default().buttons_down.insert(button);
+            }
+        }
+        true
+    }
+
+    fn apply_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(id) => {
+                self.gamepads.insert(id, GamepadState::default());
+                self.update_edges(|edges| edges.gamepad_events.push(event));
+            }
+            GamepadEvent::Disconnected(id) => {
+                self.gamepads.remove(&id);
+                self.update_edges(|edges| edges.gamepad_events.push(event));
+            }
+            GamepadEvent::ButtonPressed(id, button) => {
+                self.gamepads.entry(id).or_default().buttons_down.insert(button);
+                self.update_edges(|edges| {
+                    edges.gamepad_buttons_pressed.insert((id, button));
+                });
+            }
+            GamepadEvent::ButtonReleased(id, button) => {
+                if self.gamepads.entry(id).or_default().buttons_down.remove(&button) {
+                    self.update_edges(|edges| {
+                        edges.gamepad_buttons_released.insert((id, button));
+                    });
+                }
+            }
+            GamepadEvent::AxisChanged(id, axis, value) => {
+                self.gamepads.entry(id).or_default().axes.insert(axis, value);
+            }
+        }
+    }
+
+    /// Starts collecting every applied change. The current state is recorded first so playback starts out the same,
+    /// held keys and buttons are recorded as held rather than pressed so playback doesn't see presses the recorded run didn't.
+    pub(crate) fn start_recording(&mut self) {
+        let mut changes = vec![
+            InputChange::MousePosition(self.mouse_position.x, self.mouse_position.y),
+            InputChange::Modifiers(self.modifiers),
+        ];
+
+        let mut keys = self.keys_down.iter().copied().collect::<Vec<_>>();
+        keys.sort();
+        changes.extend(keys.into_iter().map(InputChange::KeyHeld));
+        changes.extend(
+            self.mouse_buttons_down
+                .iter()
+                .map(|button| InputChange::MouseButtonHeld(*button)),
+        );
+
+        for id in self.gamepads() {
+            let gamepad = &self.gamepads[&id];
+            changes.push(InputChange::GamepadPresent(id));
+            changes.extend(
+                gamepad
+                    .buttons_down
+                    .iter()
+                    .map(|button| InputChange::GamepadButtonHeld(id, *button)),
+            );
+            changes.extend(gamepad.axes.iter().map(|(axis, value)| {
+                InputChange::Gamepad(GamepadEvent::AxisChanged(id, *axis, *value))
+            }));
+        }
+
+        self.recorded = Some(changes);
+    }
+
+    pub(crate) fn stop_recording(&mut self) {
+        self.recorded = None;
+    }
+
+    // Returns the changes recorded since the last call.
+    pub(crate) fn take_recorded_changes(&mut self) -> Vec<InputChange> {
+        self.recorded
+            .as_mut()
+            .map(|recorded| std::mem::replace(recorded, Vec::new()))
+            .unwrap_or_default()
+    }
+
+    // Drops everything that's held down, used before playing a recording.
+    pub(crate) fn reset(&mut self) {
+        let action_map = std::mem::take(&mut self.action_map);
+        let dead_zone = self.dead_zone;
+        let relative_mouse = self.relative_mouse;
+        *self = Self::new();
+        self.action_map = action_map;
+        self.dead_zone = dead_zone;
+        self.relative_mouse = relative_mouse;
+    }
+
+    // Called by the application with imgui's `want_capture_mouse` and `want_capture_keyboard` before events are handled.
+    pub(crate) fn set_ui_capture(&mut self, wants_mouse: bool, wants_keyboard: bool) {
+        self.ui_wants_mouse = wants_mouse;
+        self.ui_wants_keyboard = wants_keyboard;
+    }
+
+    // Edges read by queries, the fixed update's while fixed updates run and the frame's otherwise.
+    fn edges(&self) -> &Edges {
+        if self.in_fixed_update {
+            &self.fixed_edges
+        } else {
+            &self.frame_edges
+        }
+    }
+
+    fn update_edges<F: Fn(&mut Edges)>(&mut self, update: F) {
+        update(&mut self.fixed_edges);
+        update(&mut self.frame_edges);
+    }
+
+    // Called by the application before fixed updates run, queries return what happened since the last fixed update.
+    pub(crate) fn begin_fixed_update(&mut self) {
+        self.in_fixed_update = true;
+        self.mouse_delta = self.fixed_edges.mouse_delta;
+    }
+
+    // Called after each fixed update so the next one only sees new edges.
+    // Fixed updates that don't run keep their edges for the next frame that runs one.
+    pub(crate) fn end_fixed_update(&mut self) {
+        self.fixed_edges = Edges::default();
+        self.mouse_delta = Vec2::zeros();
+    }
+
+    // Called by the application before the per frame updates, queries return what happened since the last frame.
+    pub(crate) fn begin_frame_update(&mut self) {
+        self.in_fixed_update = false;
+        self.mouse_delta = self.frame_edges.mouse_delta;
+    }
+
+    // Called at the end of every frame.
+    pub(crate) fn end_frame_update(&mut self) {
+        self.frame_edges = Edges::default();
+        self.mouse_delta = Vec2::zeros();
+    }
+
+    // Drops the edges of both fixed and frame updates, used when every update sees every frame's input.
+    pub(crate) fn clear(&mut self) {
+        self.end_fixed_update();
+        self.end_frame_update();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Input, InputChange, MouseButton};
+    use nalgebra_glm::Vec2;
+    use winit::{
+        event::{Event, ModifiersState, VirtualKeyCode, WindowEvent},
+        window::WindowId,
+    };
+
+    fn window_event(event: WindowEvent<'static>) -> Event<'static, ()> {
+        Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event,
+        }
+    }
+
+    #[test]
+    fn should_track_text_and_modifiers() {
+        let mut input = Input::new();
+        input.update_events(&window_event(WindowEvent::ModifiersChanged(ModifiersState::SHIFT)));
+        for character in ['H', 'i', '\u{8}'].iter() {
+            input.update_events(&window_event(WindowEvent::ReceivedCharacter(*character)));
+        }
+
+        assert!(input.is_shift_down());
+        assert!(!input.is_ctrl_down());
+        assert_eq!(input.text_input(), "Hi");
+
+        input.clear();
+        assert_eq!(input.text_input(), "");
+        assert!(input.is_shift_down());
+    }
+
+    #[test]
+    fn should_release_everything_on_focus_loss() {
+        let mut input = Input::new();
+        input.apply(InputChange::Key(VirtualKeyCode::W, true));
+        input.apply(InputChange::MouseButton(MouseButton::Left, true));
+        input.start_recording();
+        input.begin_frame_update();
+        input.end_frame_update();
+
+        input.update_events(&window_event(WindowEvent::Focused(false)));
+        input.begin_frame_update();
+        assert!(!input.is_key_down(VirtualKeyCode::W));
+        assert!(input.is_key_released(VirtualKeyCode::W));
+        assert!(!input.is_mouse_button_down(MouseButton::Left));
+        assert!(input.is_mouse_button_released(MouseButton::Left));
+
+        let changes = input.take_recorded_changes();
+        assert!(changes.contains(&InputChange::Key(VirtualKeyCode::W, false)));
+        assert!(changes.contains(&InputChange::MouseButton(MouseButton::Left, false)));
+    }
+
+    #[test]
+    fn should_accumulate_scroll_deltas() {
+        let mut input = Input::new();
+        input.apply(InputChange::ScrollLines(0.0, 1.0));
+        input.apply(InputChange::ScrollLines(0.0, 2.0));
+        input.apply(InputChange::ScrollPixels(0.0, 40.0));
+
+        assert_eq!(input.mouse_wheel_lines(), Vec2::new(0.0, 3.0));
+        assert_eq!(input.mouse_wheel_pixels(), Vec2::new(0.0, 40.0));
+        assert_eq!(input.mouse_wheel_movement(), Vec2::new(0.0, 5.0));
+
+        input.clear();
+        assert_eq!(input.mouse_wheel_movement(), Vec2::zeros());
+    }
+
+    #[test]
+    fn should_keep_edges_until_a_fixed_update_runs() {
+        let mut input = Input::new();
+        input.apply(InputChange::Key(VirtualKeyCode::Space, true));
+        input.apply(InputChange::MouseDelta(1.0, 2.0));
+
+        // A frame without fixed updates only clears the frame's edges.
+        input.begin_frame_update();
+        assert!(input.is_key_pressed(VirtualKeyCode::Space));
+        assert_eq!(input.mouse_delta, Vec2::new(1.0, 2.0));
+        input.end_frame_update();
+
+        input.apply(InputChange::Key(VirtualKeyCode::Space, false));
+        input.begin_fixed_update();
+        assert!(input.is_key_pressed(VirtualKeyCode::Space));
+        assert!(input.is_key_released(VirtualKeyCode::Space));
+        assert_eq!(input.mouse_delta, Vec2::new(1.0, 2.0));
+        input.end_fixed_update();
+
+        // The second fixed update of the frame doesn't see the same edges again.
+        assert!(!input.is_key_pressed(VirtualKeyCode::Space));
+        assert_eq!(input.mouse_delta, Vec2::zeros());
+
+        input.begin_frame_update();
+        assert!(!input.is_key_pressed(VirtualKeyCode::Space));
+        assert!(input.is_key_released(VirtualKeyCode::Space));
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+use super::{GamepadButton, GamepadEvent, GamepadId, MouseButton};
+
+/// A single change to the state of `Input`. Everything `Input` learns from winit or the gamepad backend
+/// goes through one of these, which is what makes recordings possible.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputChange {
+    Key(VirtualKeyCode, bool),
+    MouseButton(MouseButton, bool),
+    MousePosition(f32, f32),
+    MouseDelta(f32, f32),
+    ScrollLines(f32, f32),
+    ScrollPixels(f32, f32),
+    Modifiers(ModifiersState),
+    Character(char),
+    Gamepad(GamepadEvent),
+    /// Something that was already held down when recording started. Only sets the state, no presses are seen.
+    KeyHeld(VirtualKeyCode),
+    MouseButtonHeld(MouseButton),
+    GamepadPresent(GamepadId),
+    GamepadButtonHeld(GamepadId, GamepadButton),
+}
+
+/// The input of a single rendered frame.
+///
+/// Recordings are kept per frame rather than per fixed update. Recording and playback both start from an empty
+/// accumulator and playback replays each frame's time, so the same fixed updates run with the same input as long
+/// as `AppConfig::fixed_timestep` and `AppConfig::max_substeps` didn't change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// The time the frame took, playback uses this instead of the clock so the same fixed updates run.
+    pub frame_time: f32,
+    pub changes: Vec<InputChange>,
+}
+
+/// Input recorded with `Application::start_recording`, feed it back with `Application::play_recording`.
+///
+/// Playback is only deterministic when the scene starts out in the same state as when recording started.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    /// Parses a recording from a RON string.
+    pub fn from_ron(recording: &str) -> Result<Self, ron::de::Error> {
+        ron::de::from_str(recording)
+    }
+
+    /// Loads a recording from a RON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ron::de::Error> {
+        let file = std::fs::File::open(path)?;
+        ron::de::from_reader(file)
+    }
+
+    /// Writes the recording to a RON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ron::Error> {
+        let recording = ron::ser::to_string(self)?;
+        std::fs::write(path, recording)?;
+        Ok(())
+    }
+
+    /// The total time of all frames in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.frame_time).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InputChange, InputRecording, RecordedFrame};
+    use crate::core::input::{GamepadButton, GamepadEvent, GamepadId, Input, MouseButton};
+    use winit::event::{ModifiersState, VirtualKeyCode};
+
+    #[test]
+    fn should_replay_recorded_changes() {
+        let mut input = Input::new();
+        input.start_recording();
+        input.apply(InputChange::Key(VirtualKeyCode::W, true));
+        input.apply(InputChange::MouseButton(MouseButton::Left, true));
+        input.apply(InputChange::MouseDelta(2.0, -1.0));
+        input.apply(InputChange::Modifiers(ModifiersState::CTRL));
+        input.apply(InputChange::Character('a'));
+        input.apply(InputChange::Gamepad(GamepadEvent::Connected(GamepadId(0))));
+        input.apply(InputChange::Gamepad(GamepadEvent::ButtonPressed(
+            GamepadId(0),
+            GamepadButton::South,
+        )));
+
+        let recording = InputRecording {
+            frames: vec![RecordedFrame {
+                frame_time: 0.016,
+                changes: input.take_recorded_changes(),
+            }],
+        };
+        let recording = InputRecording::from_ron(&ron::ser::to_string(&recording).unwrap()).unwrap();
+
+        let mut playback = Input::new();
+        for change in recording.frames[0].changes.iter() {
+            playback.apply(*change);
+        }
+        assert!(playback.is_key_pressed(VirtualKeyCode::W));
+        assert!(playback.is_mouse_button_down(MouseButton::Left));
+        assert_eq!(playback.mouse_delta, input.mouse_delta);
+        assert!(playback.is_ctrl_down());
+        assert_eq!(playback.text_input(), "a");
+        assert!(playback.is_gamepad_button_pressed(GamepadId(0), GamepadButton::South));
+    }
+
+    #[test]
+    fn should_only_record_changes_that_reached_the_game() {
+        let mut input = Input::new();
+        input.apply(InputChange::Key(VirtualKeyCode::Space, true));
+        input.start_recording();
+        input.set_ui_capture(false, true);
+        input.apply(InputChange::Key(VirtualKeyCode::A, true));
+        input.apply(InputChange::Key(VirtualKeyCode::Space, false));
+
+        // Keys held when the recording started come first so playback starts out in the same state.
+        assert_eq!(
+            input.take_recorded_changes(),
+            vec![
+                InputChange::MousePosition(0.0, 0.0),
+                InputChange::Modifiers(ModifiersState::empty()),
+                InputChange::KeyHeld(VirtualKeyCode::Space),
+                InputChange::Key(VirtualKeyCode::Space, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_replay_held_keys_without_presses() {
+        // Mirrors `Application::start_recording`, which clears the edges before recording.
+        let mut input = Input::new();
+        input.apply(InputChange::Key(VirtualKeyCode::W, true));
+        input.apply(InputChange::MouseButton(MouseButton::Left, true));
+        input.apply(InputChange::Gamepad(GamepadEvent::ButtonPressed(GamepadId(0), GamepadButton::South)));
+        input.clear();
+        input.start_recording();
+
+        input.begin_fixed_update();
+        let live = (
+            input.is_key_pressed(VirtualKeyCode::W),
+            input.is_key_down(VirtualKeyCode::W),
+            input.is_mouse_button_pressed(MouseButton::Left),
+            input.is_gamepad_button_pressed(GamepadId(0), GamepadButton::South),
+        );
+        input.end_fixed_update();
+
+        let mut playback = Input::new();
+        playback.reset();
+        for change in input.take_recorded_changes() {
+            playback.apply(change);
+        }
+        playback.begin_fixed_update();
+        let replayed = (
+            playback.is_key_pressed(VirtualKeyCode::W),
+            playback.is_key_down(VirtualKeyCode::W),
+            playback.is_mouse_button_pressed(MouseButton::Left),
+            playback.is_gamepad_button_pressed(GamepadId(0), GamepadButton::South),
+        );
+
+        assert_eq!(live, (false, true, false, false));
+        assert_eq!(replayed, live);
+        assert!(playback.is_mouse_button_down(MouseButton::Left));
+        assert!(playback.is_gamepad_button_down(GamepadId(0), GamepadButton::South));
+    }
+}
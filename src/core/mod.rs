@@ -13,5 +13,11 @@ pub use frustum::{Frustum, GpuFrustum};
 pub use plane::{Plane, GpuPlane};
 pub use bounding_sphere::BoundingSphere;
 
+mod fixed_timestep;
+pub(crate) use fixed_timestep::FixedTimestep;
+
+mod schedule;
+pub use schedule::{Stage, StagedSchedule, SystemDesc};
+
 mod performance_metrics;
 pub use performance_metrics::PerformanceMetrics;
\ No newline at end of file
@@ -0,0 +1,416 @@
+use legion::prelude::*;
+use std::collections::HashMap;
+
+/// Stages systems are grouped into. Stages run in the order they are declared.
+/// Commands queued by systems are flushed at the end of every stage.
+///
+/// The game stages run every fixed update as part of `Scene::game_schedule`.
+/// The render stages run once per frame on the render thread, after the frame's game stages and while the next frame's
+/// game stages run.
+/// Render stages run against the `RenderWorld` snapshot instead of the scene's world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    PreUpdate,
+    /// Default stage for game systems.
+    Update,
+    /// Harmony's `culling` system runs here.
+    PostUpdate,
+    /// Runs right after the scene has been copied into the `RenderWorld`, use it to add or adjust render data.
+    Extract,
+    /// Default stage for render systems. Harmony's `shadows` and `render_mesh` systems run here.
+    Render,
+    /// Runs after the frame's command buffers have been submitted.
+    PostRender,
+}
+
+impl Stage {
+    pub const GAME: [Stage; 3] = [Stage::PreUpdate, Stage::Update, Stage::PostUpdate];
+    pub const RENDER: [Stage; 3] = [Stage::Extract, Stage::Render, Stage::PostRender];
+
+    /// Returns true if this stage is part of the render schedule.
+    pub fn is_render(self) -> bool {
+        Self::RENDER.contains(&self)
+    }
+}
+
+/// A system with an optional label and ordering constraints.
+///
+/// Example:
+/// ```ignore
+/// SystemDesc::new(camera::create()).label("camera").before("culling")
+/// ```
+pub struct SystemDesc {
+    system: Box<dyn Schedulable>,
+    label: Option<String>,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+impl SystemDesc {
+    pub fn new(system: Box<dyn Schedulable>) -> Self {
+        Self {
+            system,
+            label: None,
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Name other systems can use in `before` and `after`. More than one system can share a label.
+    pub fn label<T: Into<String>>(mut self, label: T) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Runs this system before any system with the given label.
+    pub fn before<T: Into<String>>(mut self, label: T) -> Self {
+        self.before.push(label.into());
+        self
+    }
+
+    /// Runs this system after any system with the given label.
+    pub fn after<T: Into<String>>(mut self, label: T) -> Self {
+        self.after.push(label.into());
+        self
+    }
+}
+
+impl From<Box<dyn Schedulable>> for SystemDesc {
+    fn from(system: Box<dyn Schedulable>) -> Self {
+        SystemDesc::new(system)
+    }
+}
+
+/// A schedule made out of stages. Systems inside a stage keep the order they were added in unless
+/// a `before` or `after` constraint says otherwise.
+///
+/// Legion runs systems that don't access the same data in parallel, so constraints inside a stage split the stage
+/// into steps: a system only starts once every system it has to run after has finished. Commands are flushed
+/// between steps as well.
+///
+/// Constraints can point at systems in other stages of the same schedule, as long as they agree with the stage order.
+/// Labels that don't exist in this schedule are ignored, ex: game systems can't be ordered against render systems
+/// since the render stages always run after the game stages.
+pub struct StagedSchedule {
+    stages: Vec<Stage>,
+    systems: HashMap<Stage, Vec<SystemDesc>>,
+    thread_local_fn: Option<Box<dyn FnMut(&mut World, &mut Resources) + Send>>,
+}
+
+impl StagedSchedule {
+    /// Creates a schedule containing the game stages.
+    pub fn game() -> Self {
+        Self::new(Stage::GAME.to_vec())
+    }
+
+    /// Creates a schedule containing the render stages.
+    pub fn render() -> Self {
+        Self::new(Stage::RENDER.to_vec())
+    }
+
+    fn new(stages: Vec<Stage>) -> Self {
+        Self {
+            stages,
+            systems: HashMap::new(),
+            thread_local_fn: None,
+        }
+    }
+
+    /// Returns true if this schedule runs the given stage.
+    pub fn has_stage(&self, stage: Stage) -> bool {
+        self.stages.contains(&stage)
+    }
+
+    /// Adds a system to a stage.
+    ///
+    /// *Note*: Panics if the stage isn't part of this schedule.
+    pub fn add_system<S: Into<SystemDesc>>(&mut self, stage: Stage, system: S) -> &mut Self {
+        if !self.has_stage(stage) {
+            panic!("Stage {:?} isn't part of this schedule.", stage);
+        }
+        self.systems
+            .entry(stage)
+            .or_insert_with(Vec::new)
+            .push(system.into());
+        self
+    }
+
+    // Runs on the thread executing the schedule at the end of the second to last stage, or the only stage of single
+    // stage schedules. Used to submit the frame's command buffers.
+    pub(crate) fn set_thread_local_fn(&mut self, thread_local_fn: Box<dyn FnMut(&mut World, &mut Resources) + Send>) {
+        self.thread_local_fn = Some(thread_local_fn);
+    }
+
+    // Returns the labels of each stage's systems in the order they will run.
+    pub(crate) fn labels(&self) -> Vec<(Stage, Vec<Option<String>>)> {
+        self.stages
+            .iter()
+            .map(|stage| {
+                let systems = self.systems.get(stage).map_or(&[][..], |systems| systems.as_slice());
+                let labels = self.order(*stage)
+                    .into_iter()
+                    .flatten()
+                    .map(|index| systems[index].label.clone())
+                    .collect();
+                (*stage, labels)
+            })
+            .collect()
+    }
+
+    /// Builds the legion schedule.
+    ///
+    /// *Note*: Panics if the constraints contain a cycle or ask a system to run before a system in an earlier stage.
+    pub fn build(mut self) -> Schedule {
+        let orders = self
+            .stages
+            .iter()
+            .map(|stage| self.order(*stage))
+            .collect::<Vec<_>>();
+
+        let mut builder = Schedule::builder();
+        let thread_local_stage = self.stages.len().saturating_sub(2);
+        for (stage_index, (stage, steps)) in self.stages.iter().zip(orders).enumerate() {
+            let mut systems = self
+                .systems
+                .remove(stage)
+                .unwrap_or_default()
+                .into_iter()
+                .map(Some)
+                .collect::<Vec<_>>();
+
+            for step in steps {
+                for index in step {
+                    builder = builder.add_system(systems[index].take().unwrap().system);
+                }
+                builder = builder.flush();
+            }
+
+            if stage_index == thread_local_stage {
+                if let Some(thread_local_fn) = self.thread_local_fn.take() {
+                    builder = builder.add_thread_local_fn(thread_local_fn);
+                }
+            }
+        }
+
+        builder.build()
+    }
+
+    // Sorts a stage's systems by their constraints, keeping the order they were added in where possible.
+    // The sorted systems are split into steps so each system only depends on systems in earlier steps.
+    fn order(&self, stage: Stage) -> Vec<Vec<usize>> {
+        let systems = match self.systems.get(&stage) {
+            Some(systems) => systems,
+            None => return Vec::new(),
+        };
+        let stage_of = self.label_stages();
+        let labels_in_stage = |label: &String| {
+            systems
+                .iter()
+                .enumerate()
+                .filter(|(_, system)| system.label.as_ref() == Some(label))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+
+        // dependencies[i] holds the systems that have to run before system i.
+        let mut dependencies = vec![Vec::new(); systems.len()];
+        for (index, system) in systems.iter().enumerate() {
+            for label in system.before.iter() {
+                self.check_stage_order(system, label, stage, &stage_of, true);
+                for other in labels_in_stage(label) {
+                    dependencies[other].push(index);
+                }
+            }
+            for label in system.after.iter() {
+                self.check_stage_order(system, label, stage, &stage_of, false);
+                for other in labels_in_stage(label) {
+                    dependencies[index].push(other);
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(systems.len());
+        let mut added = vec![false; systems.len()];
+        while order.len() < systems.len() {
+            let next = (0..systems.len()).find(|index| {
+                !added[*index] && dependencies[*index].iter().all(|dependency| added[*dependency])
+            });
+            match next {
+                Some(index) => {
+                    added[index] = true;
+                    order.push(index);
+                }
+                None => {
+                    let cycle = (0..systems.len())
+                        .filter(|index| !added[*index])
+                        .map(|index| systems[index].label.clone().unwrap_or_else(|| "<unlabeled>".to_string()))
+                        .collect::<Vec<_>>();
+                    panic!("Systems in stage {:?} have cyclic ordering constraints: {:?}", stage, cycle);
+                }
+            }
+        }
+
+        // A new step starts whenever a system has to run after a system in the current step.
+        let mut steps = Vec::new();
+        let mut step: Vec<usize> = Vec::new();
+        for index in order {
+            if dependencies[index].iter().any(|dependency| step.contains(dependency)) {
+                steps.push(std::mem::take(&mut step));
+            }
+            step.push(index);
+        }
+        if !step.is_empty() {
+            steps.push(step);
+        }
+
+        steps
+    }
+
+    fn label_stages(&self) -> HashMap<&str, Vec<Stage>> {
+        let mut stage_of: HashMap<&str, Vec<Stage>> = HashMap::new();
+        for stage in self.stages.iter() {
+            for system in self.systems.get(stage).into_iter().flatten() {
+                if let Some(label) = system.label.as_ref() {
+                    stage_of.entry(label.as_str()).or_insert_with(Vec::new).push(*stage);
+                }
+            }
+        }
+        stage_of
+    }
+
+    fn check_stage_order(
+        &self,
+        system: &SystemDesc,
+        label: &str,
+        stage: Stage,
+        stage_of: &HashMap<&str, Vec<Stage>>,
+        before: bool,
+    ) {
+        for other_stage in stage_of.get(label).into_iter().flatten() {
+            let valid = if before { stage <= *other_stage } else { stage >= *other_stage };
+            if !valid {
+                panic!(
+                    "System {:?} in stage {:?} can't run {} {:?} which is in stage {:?}.",
+                    system.label,
+                    stage,
+                    if before { "before" } else { "after" },
+                    label,
+                    other_stage
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Stage, StagedSchedule, SystemDesc};
+    use legion::prelude::*;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    struct Log(Vec<&'static str>);
+
+    fn create_system(name: &'static str) -> Box<dyn Schedulable> {
+        SystemBuilder::new(name)
+            .write_resource::<Log>()
+            .build(move |_, _, log, _| log.0.push(name))
+    }
+
+    fn labeled(name: &'static str) -> SystemDesc {
+        SystemDesc::new(create_system(name)).label(name)
+    }
+
+    #[test]
+    fn should_order_systems_by_constraints() {
+        let mut schedule = StagedSchedule::game();
+        schedule
+            .add_system(Stage::PostUpdate, labeled("culling"))
+            .add_system(Stage::Update, labeled("physics"))
+            .add_system(Stage::Update, labeled("camera").before("physics").before("culling"))
+            .add_system(Stage::Update, labeled("animation").after("physics"))
+            .add_system(Stage::PreUpdate, labeled("input"));
+
+        let labels = schedule.labels();
+        let update = &labels.iter().find(|(stage, _)| *stage == Stage::Update).unwrap().1;
+        let update = update.iter().map(|label| label.as_deref().unwrap()).collect::<Vec<_>>();
+        assert_eq!(update, vec!["camera", "physics", "animation"]);
+
+        let universe = Universe::new();
+        let mut world = universe.create_world();
+        let mut resources = Resources::default();
+        resources.insert(Log(Vec::new()));
+        schedule.build().execute(&mut world, &mut resources);
+
+        let log = resources.get::<Log>().unwrap();
+        assert_eq!(log.0, vec!["input", "camera", "physics", "animation", "culling"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_constraints_against_stage_order() {
+        let mut schedule = StagedSchedule::game();
+        schedule
+            .add_system(Stage::PreUpdate, labeled("input"))
+            .add_system(Stage::Update, labeled("camera").before("input"));
+        schedule.build();
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_cycles() {
+        let mut schedule = StagedSchedule::game();
+        schedule
+            .add_system(Stage::Update, labeled("a").after("b"))
+            .add_system(Stage::Update, labeled("b").after("a"));
+        schedule.build();
+    }
+
+    #[test]
+    fn should_order_systems_that_run_in_parallel() {
+        // The systems don't share any resources so legion would run them at the same time without a constraint.
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let create_system = |name: &'static str, delay: u64| {
+            let log = log.clone();
+            SystemBuilder::new(name).build(move |_, _, _, _| {
+                std::thread::sleep(Duration::from_millis(delay));
+                log.lock().unwrap().push(name);
+            })
+        };
+
+        let mut schedule = StagedSchedule::game();
+        schedule
+            .add_system(Stage::Update, SystemDesc::new(create_system("slow", 50)).label("slow"))
+            .add_system(Stage::Update, SystemDesc::new(create_system("fast", 0)).after("slow"));
+
+        let universe = Universe::new();
+        let mut world = universe.create_world();
+        schedule.build().execute(&mut world, &mut Resources::default());
+        assert_eq!(*log.lock().unwrap(), vec!["slow", "fast"]);
+    }
+
+    #[test]
+    fn should_run_thread_local_fn_in_single_stage_schedule() {
+        let mut schedule = StagedSchedule::new(vec![Stage::Render]);
+        schedule.add_system(Stage::Render, labeled("render"));
+        schedule.set_thread_local_fn(Box::new(|_, resources| {
+            resources.get_mut::<Log>().unwrap().0.push("submit");
+        }));
+
+        let universe = Universe::new();
+        let mut world = universe.create_world();
+        let mut resources = Resources::default();
+        resources.insert(Log(Vec::new()));
+        schedule.build().execute(&mut world, &mut resources);
+
+        assert_eq!(resources.get::<Log>().unwrap().0, vec!["render", "submit"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_render_stage_in_game_schedule() {
+        StagedSchedule::game().add_system(Stage::Render, create_system("render"));
+    }
+}
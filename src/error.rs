@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+/// Errors that can happen while creating an `Application`.
+#[derive(Debug)]
+pub enum HarmonyError {
+    // Thrown when the window couldn't be created.
+    Window(winit::error::OsError),
+    // Thrown when no graphics adapter is compatible with the window(or at all when headless).
+    NoAdapter,
+    // Thrown when the adapter doesn't support features harmony requires.
+    MissingFeatures(wgpu::Features),
+    // Thrown when the adapter refused to create a device.
+    RequestDevice(wgpu::RequestDeviceError),
+    // Thrown when one of harmony's core assets couldn't be found in the asset folder.
+    MissingCoreAsset(PathBuf),
+    // Thrown when a shader fails to load or compile.
+    ShaderCompilation { path: PathBuf, message: String },
+    // Thrown when the asset folder can't be watched for changes.
+    AssetWatch(notify::Error),
+    // Thrown when an asset bundle can't be opened or isn't a valid bundle.
+    AssetBundle { path: PathBuf, error: std::io::Error },
+}
+
+impl std::fmt::Display for HarmonyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HarmonyError::Window(error) => write!(f, "Couldn't create the window: {}", error),
+            HarmonyError::NoAdapter => write!(f, "Couldn't find a compatible graphics adapter."),
+            HarmonyError::MissingFeatures(features) => write!(
+                f,
+                "The graphics adapter is missing required features: {:?}",
+                features
+            ),
+            HarmonyError::RequestDevice(error) => {
+                write!(f, "Couldn't create the graphics device: {:?}", error)
+            }
+            HarmonyError::MissingCoreAsset(path) => write!(
+                f,
+                "Couldn't find core asset: {}. Make sure the asset path contains harmony's core folder.",
+                path.display()
+            ),
+            HarmonyError::ShaderCompilation { path, message } => {
+                write!(f, "Couldn't compile shader {}: {}", path.display(), message)
+            }
+            HarmonyError::AssetWatch(error) => {
+                write!(f, "Couldn't watch the asset folder for changes: {}", error)
+            }
+            HarmonyError::AssetBundle { path, error } => {
+                write!(f, "Couldn't mount asset bundle {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HarmonyError {}
+
+impl From<winit::error::OsError> for HarmonyError {
+    fn from(error: winit::error::OsError) -> Self {
+        HarmonyError::Window(error)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for HarmonyError {
+    fn from(error: wgpu::RequestDeviceError) -> Self {
+        HarmonyError::RequestDevice(error)
+    }
+}
+
+impl From<notify::Error> for HarmonyError {
+    fn from(error: notify::Error) -> Self {
+        HarmonyError::AssetWatch(error)
+    }
+}
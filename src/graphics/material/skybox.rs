@@ -18,15 +18,17 @@ pub enum SkyboxType {
     RealTime,
 }
 
+// GPU resources are reference counted so the skybox can be copied into the render world.
+#[derive(Clone)]
 pub struct Skybox {
     pub size: f32,
     pub skybox_type: SkyboxType,
     pub clear_color: Vec3,
-    pub(crate) color_texture: Option<wgpu::Texture>,
-    pub(crate) color_view: Option<wgpu::TextureView>,
-    pub(crate) cubemap_sampler: Option<wgpu::Sampler>,
-    pub(crate) cubemap_bind_group: Option<wgpu::BindGroup>,
-    pub(crate) pbr_bind_group: Option<wgpu::BindGroup>,
+    pub(crate) color_texture: Option<Arc<wgpu::Texture>>,
+    pub(crate) color_view: Option<Arc<wgpu::TextureView>>,
+    pub(crate) cubemap_sampler: Option<Arc<wgpu::Sampler>>,
+    pub(crate) cubemap_bind_group: Option<Arc<wgpu::BindGroup>>,
+    pub(crate) pbr_bind_group: Option<Arc<wgpu::BindGroup>>,
 }
 
 // TODO: Currently this blocks until the skybox texture has loaded, we should move away from that and turn this into an actual asset
@@ -38,7 +40,7 @@ impl Skybox {
         T: Into<String>,
     {
         // Create a new render graph for this process..
-        let mut graph = { RenderGraph::new(&mut app.resources, false) };
+        let mut graph = { RenderGraph::new(&mut app.resources, None) };
 
         let asset_manager = app.resources.get::<AssetManager>().unwrap();
         let device = app.resources.get::<Arc<wgpu::Device>>().unwrap();
@@ -124,9 +126,9 @@ impl Skybox {
 
         Self {
             size,
-            color_texture: Some(color.texture),
-            color_view: Some(color_view),
-            cubemap_sampler: Some(cubemap_sampler),
+            color_texture: Some(Arc::new(color.texture)),
+            color_view: Some(Arc::new(color_view)),
+            cubemap_sampler: Some(Arc::new(cubemap_sampler)),
             cubemap_bind_group: None,
             pbr_bind_group: None,
             clear_color: Vec3::zeros(),
@@ -203,7 +205,7 @@ impl Skybox {
             ]),
             label: None,
         });
-        self.cubemap_bind_group = Some(bind_group);
+        self.cubemap_bind_group = Some(Arc::new(bind_group));
     }
 
     pub(crate) fn create_bind_group2(
@@ -227,7 +229,7 @@ impl Skybox {
             ]),
             label: None,
         });
-        self.cubemap_bind_group = Some(bind_group);
+        self.cubemap_bind_group = Some(Arc::new(bind_group));
     }
 
     // async fn save(
@@ -1,5 +1,5 @@
 pub mod renderer;
-pub use renderer::Renderer;
+pub use renderer::{Capabilities, RenderWindow, Renderer};
 
 pub mod material;
 
@@ -17,6 +17,11 @@ pub mod systems;
 
 pub mod pipeline_manager;
 
+mod render_world;
+pub use render_world::{RenderMaterials, RenderWorld, SourceEntity, TransformSlot};
+
+pub(crate) mod render_thread;
+
 pub mod shadows;
 
 pub(crate) mod lighting;
\ No newline at end of file
@@ -2,6 +2,7 @@ use ordered_float::OrderedFloat;
 use std::collections::{hash_map::DefaultHasher, HashMap};
 use std::{
     hash::{Hash, Hasher},
+    path::{Path, PathBuf},
     sync::Arc, borrow::Cow,
 };
 
@@ -374,6 +375,31 @@ impl PipelineManager {
     }
 
 
+    /// Rebuilds every pipeline that uses one of the shaders, ex: after the shaders were hot reloaded.
+    /// Shader paths are relative to the asset path like `PipelineDesc::shader`.
+    pub fn rebuild_pipelines(
+        &mut self,
+        shaders: &[PathBuf],
+        device: &wgpu::Device,
+        asset_manager: &AssetManager,
+        gpu_resource_manager: &GPUResourceManager,
+    ) {
+        let uses_shader = |shader: &str| shaders.iter().any(|path| Path::new(shader) == path);
+        for pipeline_hashmap in self.pipelines.values_mut() {
+            for pipeline_type in pipeline_hashmap.values_mut() {
+                match pipeline_type {
+                    PipelineType::Pipeline(pipeline) if uses_shader(&pipeline.desc.shader) => {
+                        *pipeline = pipeline.desc.build(asset_manager, device, gpu_resource_manager);
+                    }
+                    PipelineType::ComputePipeline(pipeline) if uses_shader(&pipeline.desc.shader) => {
+                        *pipeline = pipeline.desc.build(asset_manager, device, gpu_resource_manager);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
     /// A node is an encoder you want to run at some step inside of the pipeline workflow.
     pub fn add_node<T: Into<String>>(&mut self, name: T, dependency: Vec<&str>) {
         let name = name.into();
@@ -62,6 +62,21 @@ pub fn create_pbr_bindgroup_layout(device: Arc<wgpu::Device>) -> wgpu::BindGroup
                     dimension: wgpu::TextureViewDimension::D2,
                 },
             ),
+            wgpu::BindGroupLayoutEntry::new(
+                6,
+                wgpu::ShaderStage::FRAGMENT,
+                wgpu::BindingType::Sampler { comparison: false },
+            ),
+            wgpu::BindGroupLayoutEntry::new(
+                7,
+                wgpu::ShaderStage::FRAGMENT,
+                wgpu::BindingType::Sampler { comparison: false },
+            ),
+            wgpu::BindGroupLayoutEntry::new(
+                8,
+                wgpu::ShaderStage::FRAGMENT,
+                wgpu::BindingType::Sampler { comparison: false },
+            ),
         ]),
         label: Some(Cow::Borrowed("pbr_material_layout")),
     })
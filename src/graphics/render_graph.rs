@@ -32,12 +32,12 @@ pub struct RenderGraph {
 /// DEPRECIATED DO NOT USE.
 impl RenderGraph {
     /// DEPRECIATED DO NOT USE.
-    pub(crate) fn new(resources: &mut Resources, create_command_queue: bool) -> Self {
+    pub(crate) fn new(resources: &mut Resources, command_queue_capacity: Option<usize>) -> Self {
         let mut dep_graph = DepGraph::new();
         dep_graph.register_node("root".to_string());
 
-        if create_command_queue {
-            let command_queue = CommandBufferQueue::new(50);
+        if let Some(capacity) = command_queue_capacity {
+            let command_queue = CommandBufferQueue::new(capacity);
             resources.insert(command_queue);
         }
 
@@ -0,0 +1,269 @@
+use crossbeam::channel::{self, Receiver, Sender};
+use legion::prelude::*;
+use std::{sync::Arc, thread::JoinHandle};
+
+use super::{renderer::FrameTexture, RenderMaterials, RenderWorld};
+use crate::{
+    assets::material::PBRMaterialRon,
+    core::{PerformanceMetrics, StagedSchedule},
+    AssetManager,
+};
+
+/// Which of the render thread's schedules a job runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RenderPass {
+    /// The render schedule, including the systems added with `AppBuilder::add_render_system`.
+    Main,
+    /// The camera passes of a window opened with `Application::open_window`.
+    Window,
+}
+
+// A frame handed to the render thread, the world and resources are handed back once the pass has been submitted.
+pub(crate) struct RenderJob {
+    pub(crate) pass: RenderPass,
+    pub(crate) world: RenderWorld,
+    pub(crate) resources: Resources,
+}
+
+// How a resource render systems read gets to the render thread and back.
+// The application's resources are shared with game systems and `AppState`, while a frame renders the render thread
+// works on its own `Resources` made out of these.
+pub(crate) struct RenderResource {
+    // Called with the application's resources and the render thread's before the frame renders.
+    send: fn(&mut Resources, &mut Resources),
+    // Called with the render thread's resources and the application's once the frame has been submitted.
+    receive: fn(&mut Resources, &mut Resources),
+}
+
+impl RenderResource {
+    // The resource is moved to the render thread while a frame renders, it isn't in `Application::resources`
+    // until the frame has been submitted.
+    pub(crate) fn owned<T: Resource>() -> Self {
+        Self {
+            send: move_resource::<T>,
+            receive: move_resource::<T>,
+        }
+    }
+
+    // The render thread gets a clone of the resource, ex: an `Arc`. Changes the render thread makes are dropped.
+    pub(crate) fn shared<T: Resource + Clone>() -> Self {
+        Self {
+            send: clone_resource::<T>,
+            receive: |_, _| {},
+        }
+    }
+
+    pub(crate) fn send(&self, resources: &mut Resources, render_resources: &mut Resources) {
+        (self.send)(resources, render_resources);
+    }
+
+    pub(crate) fn receive(&self, render_resources: &mut Resources, resources: &mut Resources) {
+        (self.receive)(render_resources, resources);
+    }
+}
+
+fn move_resource<T: Resource>(from: &mut Resources, to: &mut Resources) {
+    if let Some(resource) = from.remove::<T>() {
+        to.insert(resource);
+    }
+}
+
+fn clone_resource<T: Resource + Clone>(from: &mut Resources, to: &mut Resources) {
+    let resource = from.get::<T>().map(|resource| (*resource).clone());
+    if let Some(resource) = resource {
+        to.insert(resource);
+    }
+}
+
+// The resources harmony's render systems read. `PerformanceMetrics` is shared with game systems, the render thread
+// gets its own and its timings are merged back. The `AssetManager` stays with the game, the loaded materials are
+// sent as `RenderMaterials` instead.
+pub(crate) fn harmony_render_resources() -> Vec<RenderResource> {
+    use super::{
+        lighting::cluster::Clustering,
+        pipeline_manager::PipelineManager,
+        renderer::DepthTexture,
+        resources::{CurrentRenderTarget, GPUResourceManager},
+        shadows::{OmniShadowManager, ShadowCamera},
+        CommandBufferQueue, RenderGraph,
+    };
+    use crate::scene::resources::InterpolationAlpha;
+
+    vec![
+        RenderResource::shared::<Arc<wgpu::Device>>(),
+        RenderResource::shared::<Arc<wgpu::Queue>>(),
+        RenderResource::shared::<Arc<GPUResourceManager>>(),
+        RenderResource::shared::<wgpu::SwapChainDescriptor>(),
+        RenderResource::shared::<InterpolationAlpha>(),
+        RenderResource::owned::<Arc<FrameTexture>>(),
+        RenderResource::owned::<CommandBufferQueue>(),
+        RenderResource::owned::<PipelineManager>(),
+        RenderResource::owned::<DepthTexture>(),
+        RenderResource::owned::<Clustering>(),
+        RenderResource::owned::<ShadowCamera>(),
+        RenderResource::owned::<OmniShadowManager>(),
+        RenderResource::owned::<CurrentRenderTarget>(),
+        RenderResource::owned::<RenderGraph>(),
+        RenderResource {
+            send: |resources, render_resources| {
+                let materials = resources
+                    .get::<AssetManager>()
+                    .map(|asset_manager| asset_manager.get_all_materials::<PBRMaterialRon>())
+                    .unwrap_or_default();
+                render_resources.insert(RenderMaterials(materials));
+            },
+            receive: |_, _| {},
+        },
+        RenderResource {
+            send: |_, render_resources| {
+                render_resources.insert(PerformanceMetrics::new());
+            },
+            receive: |render_resources, resources| {
+                let render_metrics = render_resources.remove::<PerformanceMetrics>();
+                let metrics = resources.get_mut::<PerformanceMetrics>();
+                if let (Some(render_metrics), Some(mut metrics)) = (render_metrics, metrics) {
+                    metrics.data.extend(render_metrics.data);
+                }
+            },
+        },
+    ]
+}
+
+// Runs the render schedules on their own thread so the game can update the next frame while a frame is rendered.
+// Legion schedules can't be sent between threads, so they're built on the render thread and stay there.
+pub(crate) struct RenderThread {
+    jobs: Option<Sender<RenderJob>>,
+    finished: Receiver<RenderJob>,
+    in_flight: bool,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RenderThread {
+    pub(crate) fn new(render_schedule: StagedSchedule, window_render_schedule: StagedSchedule) -> Self {
+        let (jobs, pending) = channel::unbounded::<RenderJob>();
+        let (done, finished) = channel::unbounded();
+        let thread = std::thread::Builder::new()
+            .name("harmony render".to_string())
+            .spawn(move || {
+                let mut render_schedule = render_schedule.build();
+                let mut window_render_schedule = window_render_schedule.build();
+                for mut job in pending.iter() {
+                    let schedule = match job.pass {
+                        RenderPass::Main => &mut render_schedule,
+                        RenderPass::Window => &mut window_render_schedule,
+                    };
+                    schedule.execute(&mut job.world.world, &mut job.resources);
+                    // Presents the frame right away instead of waiting for the application to take the job back.
+                    job.resources.remove::<Arc<FrameTexture>>();
+                    if done.send(job).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("Couldn't start the render thread.");
+
+        Self {
+            jobs: Some(jobs),
+            finished,
+            in_flight: false,
+            thread: Some(thread),
+        }
+    }
+
+    // Starts rendering a job without waiting for it. Only one job is rendered at a time.
+    pub(crate) fn start(&mut self, job: RenderJob) {
+        assert!(!self.in_flight, "A frame is already being rendered.");
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send(job)
+            .expect("The render thread stopped.");
+        self.in_flight = true;
+    }
+
+    // Waits for the job that's being rendered, returns None if nothing is being rendered.
+    pub(crate) fn wait(&mut self) -> Option<RenderJob> {
+        if !self.in_flight {
+            return None;
+        }
+        self.in_flight = false;
+        Some(
+            self.finished
+                .recv()
+                .expect("The render thread panicked while rendering a frame."),
+        )
+    }
+}
+
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        // Closing the channel ends the render thread's loop once the job in flight is done.
+        self.jobs.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderJob, RenderPass, RenderResource, RenderThread};
+    use crate::{
+        core::{Stage, StagedSchedule, SystemDesc},
+        graphics::RenderWorld,
+    };
+    use legion::prelude::*;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    struct Frames(u32);
+
+    #[test]
+    fn should_render_while_the_application_continues() {
+        let release = Arc::new(AtomicBool::new(false));
+        let system_release = release.clone();
+        let mut schedule = StagedSchedule::render();
+        schedule.add_system(
+            Stage::Render,
+            SystemDesc::new(
+                SystemBuilder::new("count_frames")
+                    .write_resource::<Frames>()
+                    .build(move |_, _, frames, _| {
+                        // Keeps the frame rendering until the test has checked that it didn't wait.
+                        while !system_release.load(Ordering::SeqCst) {
+                            std::thread::yield_now();
+                        }
+                        frames.0 += 1;
+                    }),
+            ),
+        );
+        let mut render_thread = RenderThread::new(schedule, StagedSchedule::render());
+
+        let mut resources = Resources::default();
+        resources.insert(Frames(0));
+        let render_resources = vec![RenderResource::owned::<Frames>()];
+        let mut job_resources = Resources::default();
+        for render_resource in render_resources.iter() {
+            render_resource.send(&mut resources, &mut job_resources);
+        }
+        assert!(!resources.contains::<Frames>());
+
+        render_thread.start(RenderJob {
+            pass: RenderPass::Main,
+            world: RenderWorld::new(),
+            resources: job_resources,
+        });
+        // The frame is still rendering, the application can keep going.
+        assert!(render_thread.in_flight);
+        release.store(true, Ordering::SeqCst);
+
+        let mut job = render_thread.wait().unwrap();
+        for render_resource in render_resources.iter() {
+            render_resource.receive(&mut job.resources, &mut resources);
+        }
+        assert_eq!(resources.get::<Frames>().unwrap().0, 1);
+        assert!(render_thread.wait().is_none());
+    }
+}
@@ -0,0 +1,336 @@
+use legion::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    assets::{material::PBRMaterial, AssetHandle},
+    graphics::material::Skybox,
+    scene::components::{CameraData, DirectionalLightData, Mesh, PointLightData, Transform},
+};
+
+/// Points a render world entity back at the scene entity it was extracted from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceEntity(pub Entity);
+
+/// The slot of an extracted mesh's transform in the renderer's `transform` buffers and bind groups.
+/// Slots stay with their scene entity until it's deleted, after that the slot is reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransformSlot(pub u32);
+
+/// The PBR materials that were loaded when the frame was extracted. Render systems read this instead of the
+/// `AssetManager`, which stays with the game while the frame renders.
+pub struct RenderMaterials(pub Vec<Arc<AssetHandle<PBRMaterial>>>);
+
+/// A snapshot of the render-relevant parts of a scene. The render schedule runs against this world instead
+/// of the scene's world, so render systems never see the scene while it's being updated.
+///
+/// Extracted each frame: `Mesh` and `Transform`, `PointLightData` and `Transform`, `DirectionalLightData`,
+/// `CameraData` and `Skybox`. Every extracted entity also gets a `SourceEntity` and meshes get a `TransformSlot`.
+///
+/// The render schedule runs against this world on the render thread while the game updates the next frame,
+/// the next snapshot is extracted once the frame has been submitted.
+pub struct RenderWorld {
+    _universe: Universe,
+    /// The extracted entities. The world is kept between snapshots, only the extracted entities are replaced.
+    pub world: World,
+    // Entities of the current snapshot, deleted before the next one is extracted.
+    extracted: Vec<Entity>,
+    // Shadow map allocation and age of each point light, owned by the render side.
+    shadow_state: HashMap<Entity, ((u32, u32), u32)>,
+    // Transform slots of meshes, slots of deleted meshes are put in `free_transform_slots`.
+    transform_slots: HashMap<Entity, u32>,
+    free_transform_slots: Vec<u32>,
+}
+
+impl RenderWorld {
+    pub fn new() -> Self {
+        let universe = Universe::new();
+        let world = universe.create_world();
+        Self {
+            _universe: universe,
+            world,
+            extracted: Vec::new(),
+            shadow_state: HashMap::new(),
+            transform_slots: HashMap::new(),
+            free_transform_slots: Vec::new(),
+        }
+    }
+
+    /// Replaces the snapshot with the current state of `scene_world`.
+    /// World matrices of the scene's transforms are updated before they are copied.
+    pub fn extract(&mut self, scene_world: &mut World) {
+        self.store_shadow_state();
+
+        let transform_query = <(Write<Transform>,)>::query();
+        for (mut transform,) in transform_query.iter_mut(scene_world) {
+            transform.update();
+        }
+
+        let meshes = <(Read<Mesh>, Read<Transform>)>::query()
+            .iter_entities(scene_world)
+            .map(|(entity, (mesh, transform))| (entity, (*mesh).clone(), (*transform).clone()))
+            .collect::<Vec<_>>();
+        self.free_removed_transform_slots(&meshes.iter().map(|(entity, _, _)| *entity).collect());
+        let meshes = meshes
+            .into_iter()
+            .map(|(entity, mesh, transform)| {
+                let slot = self.allocate_transform_slot(entity);
+                (SourceEntity(entity), TransformSlot(slot), mesh, transform)
+            })
+            .collect::<Vec<_>>();
+
+        let point_lights = <(Read<PointLightData>, Read<Transform>)>::query()
+            .iter_entities(scene_world)
+            .map(|(entity, (light, transform))| {
+                let mut light = (*light).clone();
+                if let Some((shadow_texture_id, age)) = self.shadow_state.get(&entity) {
+                    light.shadow_texture_id = *shadow_texture_id;
+                    light.age = *age;
+                }
+                (SourceEntity(entity), light, (*transform).clone())
+            })
+            .collect::<Vec<_>>();
+
+        let directional_lights = <(Read<DirectionalLightData>,)>::query()
+            .iter_entities(scene_world)
+            .map(|(entity, (light,))| (SourceEntity(entity), (*light).clone()))
+            .collect::<Vec<_>>();
+
+        let cameras = <(Read<CameraData>,)>::query()
+            .iter_entities(scene_world)
+            .map(|(entity, (camera,))| (SourceEntity(entity), (*camera).clone()))
+            .collect::<Vec<_>>();
+
+        let skyboxes = <(Read<Skybox>,)>::query()
+            .iter_entities(scene_world)
+            .map(|(entity, (skybox,))| (SourceEntity(entity), (*skybox).clone()))
+            .collect::<Vec<_>>();
+
+        // Lights that were removed from the scene don't need their shadow state anymore.
+        self.shadow_state
+            .retain(|entity, _| point_lights.iter().any(|(source, _, _)| source.0 == *entity));
+
+        // Reuses the world's storage instead of creating a new world every frame.
+        for entity in self.extracted.drain(..) {
+            self.world.delete(entity);
+        }
+        self.extracted.extend_from_slice(self.world.insert((), meshes));
+        self.extracted.extend_from_slice(self.world.insert((), point_lights));
+        self.extracted.extend_from_slice(self.world.insert((), directional_lights));
+        self.extracted.extend_from_slice(self.world.insert((), cameras));
+        self.extracted.extend_from_slice(self.world.insert((), skyboxes));
+    }
+
+    // Turns off cameras that are shown in other windows.
+    pub(crate) fn deactivate_cameras(&mut self, cameras: &[Entity]) {
+        let query = <(Read<SourceEntity>, Write<CameraData>)>::query();
+        for (source, mut camera) in query.iter_mut(&mut self.world) {
+            if cameras.contains(&source.0) {
+                camera.active = false;
+            }
+        }
+    }
+
+    // Makes `camera` the only active camera. Culling only ran for the main camera so it's turned off for every mesh.
+    pub(crate) fn select_camera(&mut self, camera: Entity) {
+        let query = <(Read<SourceEntity>, Write<CameraData>)>::query();
+        for (source, mut camera_data) in query.iter_mut(&mut self.world) {
+            camera_data.active = source.0 == camera;
+        }
+
+        let query = <(Read<Mesh>, Write<Transform>)>::query();
+        for (_, mut transform) in query.iter_mut(&mut self.world) {
+            transform.cull = false;
+        }
+    }
+
+    /// The number of transform slots handed out so far, including free ones.
+    pub fn transform_slot_count(&self) -> u32 {
+        (self.transform_slots.len() + self.free_transform_slots.len()) as u32
+    }
+
+    fn allocate_transform_slot(&mut self, entity: Entity) -> u32 {
+        if let Some(slot) = self.transform_slots.get(&entity) {
+            return *slot;
+        }
+        let slot = match self.free_transform_slots.pop() {
+            Some(slot) => slot,
+            None => self.transform_slot_count(),
+        };
+        self.transform_slots.insert(entity, slot);
+        slot
+    }
+
+    // Gives the slots of meshes that are no longer in the scene back so new meshes can reuse their buffers.
+    fn free_removed_transform_slots(&mut self, meshes: &HashSet<Entity>) {
+        let free_transform_slots = &mut self.free_transform_slots;
+        self.transform_slots.retain(|entity, slot| {
+            if meshes.contains(entity) {
+                return true;
+            }
+            free_transform_slots.push(*slot);
+            false
+        });
+    }
+
+    // The shadow system allocates shadow maps on the render side, keep them around for the next snapshot.
+    fn store_shadow_state(&mut self) {
+        let query = <(Read<SourceEntity>, Read<PointLightData>)>::query();
+        for (source, light) in query.iter(&self.world) {
+            self.shadow_state
+                .insert(source.0, (light.shadow_texture_id, light.age));
+        }
+    }
+}
+
+impl Default for RenderWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderWorld, SourceEntity, TransformSlot};
+    use crate::{
+        assets::{mesh::Gltf, AssetHandle},
+        scene::components::{CameraData, DirectionalLightData, Mesh, PointLightData, Transform},
+    };
+    use legion::prelude::*;
+    use nalgebra_glm::Vec3;
+    use std::sync::Arc;
+
+    fn create_transform(position: Vec3) -> Transform {
+        Transform {
+            position,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_extract_render_components() {
+        let universe = Universe::new();
+        let mut scene_world = universe.create_world();
+        let light = scene_world.insert(
+            (),
+            vec![(PointLightData::default(), create_transform(Vec3::new(1.0, 2.0, 3.0)))],
+        )[0];
+        scene_world.insert((), vec![(DirectionalLightData::default(),)]);
+        scene_world.insert(
+            (),
+            vec![(CameraData::new_perspective(70.0, 256.0, 256.0, 1.0, 100.0),)],
+        );
+
+        let mut render_world = RenderWorld::new();
+        render_world.extract(&mut scene_world);
+
+        // The scene's matrices are updated during extraction.
+        let transform = scene_world.get_component::<Transform>(light).unwrap();
+        assert_eq!(transform.matrix.column(3).z, 3.0);
+
+        let lights = <(Read<SourceEntity>, Read<PointLightData>, Read<Transform>)>::query()
+            .iter(&render_world.world)
+            .map(|(source, _, transform)| (source.0, transform.position))
+            .collect::<Vec<_>>();
+        assert_eq!(lights, vec![(light, Vec3::new(1.0, 2.0, 3.0))]);
+        assert_eq!(<Read<DirectionalLightData>>::query().iter(&render_world.world).count(), 1);
+        assert_eq!(<Read<CameraData>>::query().iter(&render_world.world).count(), 1);
+    }
+
+    #[test]
+    fn should_keep_shadow_state_between_snapshots() {
+        let universe = Universe::new();
+        let mut scene_world = universe.create_world();
+        scene_world.insert((), vec![(PointLightData::default(), create_transform(Vec3::zeros()))]);
+
+        let mut render_world = RenderWorld::new();
+        render_world.extract(&mut scene_world);
+        for mut light in <Write<PointLightData>>::query().iter_mut(&mut render_world.world) {
+            light.shadow_texture_id = (1, 2);
+            light.age = 3;
+        }
+
+        render_world.extract(&mut scene_world);
+        let light = <Read<PointLightData>>::query()
+            .iter(&render_world.world)
+            .next()
+            .map(|light| (light.shadow_texture_id, light.age));
+        assert_eq!(light, Some(((1, 2), 3)));
+    }
+
+    #[test]
+    fn should_select_window_camera() {
+        let universe = Universe::new();
+        let mut scene_world = universe.create_world();
+        let cameras = scene_world.insert(
+            (),
+            vec![
+                (CameraData::new_perspective(70.0, 256.0, 256.0, 1.0, 100.0),),
+                (CameraData::new_perspective(70.0, 256.0, 256.0, 1.0, 100.0),),
+            ],
+        ).to_vec();
+
+        let mut render_world = RenderWorld::new();
+        render_world.extract(&mut scene_world);
+        render_world.deactivate_cameras(&cameras[1..]);
+        let active = |render_world: &RenderWorld| {
+            <(Read<SourceEntity>, Read<CameraData>)>::query()
+                .iter(&render_world.world)
+                .filter(|(_, camera)| camera.active)
+                .map(|(source, _)| source.0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(active(&render_world), vec![cameras[0]]);
+
+        render_world.select_camera(cameras[1]);
+        assert_eq!(active(&render_world), vec![cameras[1]]);
+    }
+
+    #[test]
+    fn should_reuse_transform_slots_of_deleted_meshes() {
+        let universe = Universe::new();
+        let mut scene_world = universe.create_world();
+        let mesh_handle = AssetHandle::<Gltf>::new("cube.gltf".into(), Arc::new(dashmap::DashMap::new()));
+        let mesh = Mesh::new(Arc::new(mesh_handle));
+        let entities = scene_world
+            .insert(
+                (),
+                vec![
+                    (mesh.clone(), create_transform(Vec3::zeros())),
+                    (mesh.clone(), create_transform(Vec3::zeros())),
+                ],
+            )
+            .to_vec();
+        // Transforms without a mesh don't need a slot.
+        scene_world.insert((), vec![(PointLightData::default(), create_transform(Vec3::zeros()))]);
+
+        let mut render_world = RenderWorld::new();
+        let slots = |render_world: &RenderWorld| {
+            let mut slots = <(Read<SourceEntity>, Read<TransformSlot>)>::query()
+                .iter(&render_world.world)
+                .map(|(source, slot)| (source.0, slot.0))
+                .collect::<Vec<_>>();
+            slots.sort_by_key(|(_, slot)| *slot);
+            slots
+        };
+        render_world.extract(&mut scene_world);
+        let first = slots(&render_world);
+        assert_eq!(first.len(), 2);
+        assert_eq!(render_world.transform_slot_count(), 2);
+
+        // Slots stay the same between snapshots.
+        render_world.extract(&mut scene_world);
+        assert_eq!(slots(&render_world), first);
+        // Old snapshots are removed from the world.
+        assert_eq!(<Read<SourceEntity>>::query().iter(&render_world.world).count(), 3);
+
+        scene_world.delete(entities[0]);
+        let added = scene_world.insert((), vec![(mesh, create_transform(Vec3::zeros()))])[0];
+        render_world.extract(&mut scene_world);
+        let freed_slot = first.iter().find(|(entity, _)| *entity == entities[0]).unwrap().1;
+        assert!(slots(&render_world).contains(&(added, freed_slot)));
+        assert_eq!(render_world.transform_slot_count(), 2);
+    }
+}
@@ -1,65 +1,285 @@
-use super::{resources::GPUResourceManager, pipeline_manager::PipelineManager, shadows::ShadowQuality};
-use legion::systems::resource::Resources;
-use std::sync::Arc;
+use super::{
+    pipeline_manager::PipelineManager,
+    resources::{GPUResourceManager, RenderTarget},
+    shadows::ShadowQuality,
+};
+use crate::{AppConfig, HarmonyError};
+use legion::{prelude::Entity, systems::resource::Resources};
+use std::{collections::HashMap, sync::Arc};
+use winit::window::WindowId;
 
 pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 pub const FRAME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+// Features harmony can't render without.
+pub const REQUIRED_FEATURES: wgpu::Features = wgpu::Features::empty();
+// Features harmony uses when the adapter supports them.
+// Without push constants point light shadows fall back to a uniform buffer,
+// without BC compression textures with `compression: Bc1` stay uncompressed.
+pub const OPTIONAL_FEATURES: wgpu::Features = wgpu::Features::from_bits_truncate(
+    wgpu::Features::PUSH_CONSTANTS.bits() | wgpu::Features::TEXTURE_COMPRESSION_BC.bits(),
+);
 
 pub struct DepthTexture(pub wgpu::TextureView);
 
+/// Describes the adapter and device harmony is rendering with. Stored in resources.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    pub adapter_info: wgpu::AdapterInfo,
+    /// Features enabled on the device.
+    pub features: wgpu::Features,
+}
+
+impl Capabilities {
+    /// Returns true if push constants are available.
+    pub fn push_constants(&self) -> bool {
+        self.features.contains(wgpu::Features::PUSH_CONSTANTS)
+    }
+}
+
+/// The texture the current frame is being rendered into.
+/// Stored in resources as `Arc<FrameTexture>` while the render schedule runs.
+pub enum FrameTexture {
+    /// A texture from the window's swap chain.
+    SwapChain(wgpu::SwapChainTexture),
+    /// An offscreen render target used when running headless.
+    Offscreen(Arc<RenderTarget>),
+}
+
+impl FrameTexture {
+    /// The view render passes should use as their color attachment.
+    pub fn view(&self) -> &wgpu::TextureView {
+        match self {
+            FrameTexture::SwapChain(texture) => &texture.view,
+            FrameTexture::Offscreen(target) => &target.texture_view,
+        }
+    }
+}
+
+/// An extra window with its own surface, swap chain and depth buffer. See `Application::open_window`.
+pub struct RenderWindow {
+    pub window: winit::window::Window,
+    surface: wgpu::Surface,
+    pub(crate) sc_desc: wgpu::SwapChainDescriptor,
+    swap_chain: Option<wgpu::SwapChain>,
+    pub(crate) depth_texture: DepthTexture,
+    /// The camera entity shown in this window. Nothing is rendered while this is `None`.
+    pub camera: Option<Entity>,
+}
+
+impl RenderWindow {
+    /// Returns true if the window has no area to render into, for example when it's minimized.
+    pub fn is_minimized(&self) -> bool {
+        self.sc_desc.width == 0 || self.sc_desc.height == 0
+    }
+
+    // Recreates the swap chain and depth buffer using the window's swap chain descriptor.
+    fn recreate(&mut self, device: &wgpu::Device) {
+        if self.is_minimized() {
+            return;
+        }
+        self.swap_chain = Some(device.create_swap_chain(&self.surface, &self.sc_desc));
+        self.depth_texture = Renderer::create_depth_texture(device, &self.sc_desc);
+    }
+}
+
 pub struct Renderer {
-    pub(crate) surface: wgpu::Surface,
+    instance: wgpu::Instance,
+    pub(crate) surface: Option<wgpu::Surface>,
     pub size: winit::dpi::PhysicalSize<u32>,
     adapter: wgpu::Adapter,
-    pub(crate) swap_chain: wgpu::SwapChain,
-    pub window: winit::window::Window,
+    pub(crate) swap_chain: Option<wgpu::SwapChain>,
+    /// The frame output used when running without a window.
+    pub(crate) headless_target: Option<Arc<RenderTarget>>,
+    /// The window we are rendering to. This is `None` when running headless.
+    pub window: Option<winit::window::Window>,
+    /// Windows opened with `Application::open_window`.
+    pub(crate) windows: HashMap<WindowId, RenderWindow>,
 }
 
 impl Renderer {
     pub(crate) async fn new(
         window: winit::window::Window,
         size: winit::dpi::PhysicalSize<u32>,
+        config: &AppConfig,
         resources: &mut Resources,
-    ) -> Self {
-        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+    ) -> Result<Self, HarmonyError> {
+        let instance = wgpu::Instance::new(config.backend.into());
         let surface = unsafe { instance.create_surface(&window) };
 
-        let adapter = instance
-            .request_adapter(
-                &wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance,
-                    compatible_surface: Some(&surface),
-                },
-            )
+        let (adapter, device) =
+            Self::create_device(&instance, Some(&surface), config, resources).await?;
+
+        let sc_desc = Self::create_sc_desc(size, config);
+        let swap_chain = device.create_swap_chain(&surface, &sc_desc);
+
+        Self::insert_resources(device, sc_desc, config.shadow_quality, resources);
+
+        Ok(Self {
+            instance,
+            surface: Some(surface),
+            size,
+            adapter,
+            swap_chain: Some(swap_chain),
+            headless_target: None,
+            window: Some(window),
+            windows: HashMap::new(),
+        })
+    }
+
+    /// Creates a renderer that draws each frame into an offscreen `RenderTarget` instead of a window.
+    pub(crate) async fn new_headless(
+        size: winit::dpi::PhysicalSize<u32>,
+        config: &AppConfig,
+        resources: &mut Resources,
+    ) -> Result<Self, HarmonyError> {
+        let instance = wgpu::Instance::new(config.backend.into());
+
+        let (adapter, device) = Self::create_device(&instance, None, config, resources).await?;
+
+        let sc_desc = Self::create_sc_desc(size, config);
+        let headless_target = Arc::new(Self::create_headless_target(&device, &sc_desc));
+
+        Self::insert_resources(device, sc_desc, config.shadow_quality, resources);
+
+        Ok(Self {
+            instance,
+            surface: None,
+            size,
+            adapter,
+            swap_chain: None,
+            headless_target: Some(headless_target),
+            window: None,
+            windows: HashMap::new(),
+        })
+    }
+
+    async fn request_adapter(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface>,
+        config: &AppConfig,
+    ) -> Option<wgpu::Adapter> {
+        if config.force_fallback_adapter || config.adapter_name.is_some() {
+            let adapter = instance
+                .enumerate_adapters(config.backend.into())
+                .find(|adapter| {
+                    let info = adapter.get_info();
+                    let name_matches = config
+                        .adapter_name
+                        .as_ref()
+                        .map_or(true, |name| info.name.contains(name.as_str()));
+                    let type_matches = !config.force_fallback_adapter
+                        || info.device_type == wgpu::DeviceType::Cpu;
+                    name_matches && type_matches
+                });
+
+            if adapter.is_some() || config.force_fallback_adapter {
+                return adapter;
+            }
+            log::warn!(
+                "Couldn't find an adapter named {:?}, using the default adapter.",
+                config.adapter_name
+            );
+        }
+
+        instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: config.power_preference.into(),
+                compatible_surface: surface,
+            })
             .await
-            .unwrap();
+    }
+
+    async fn create_device(
+        instance: &wgpu::Instance,
+        surface: Option<&wgpu::Surface>,
+        config: &AppConfig,
+        resources: &mut Resources,
+    ) -> Result<(wgpu::Adapter, Arc<wgpu::Device>), HarmonyError> {
+        let adapter = Self::request_adapter(instance, surface, config)
+            .await
+            .ok_or(HarmonyError::NoAdapter)?;
 
         let adapter_features = adapter.features();
+        if !adapter_features.contains(REQUIRED_FEATURES) {
+            return Err(HarmonyError::MissingFeatures(
+                REQUIRED_FEATURES - adapter_features,
+            ));
+        }
+
+        let features = REQUIRED_FEATURES | (adapter_features & OPTIONAL_FEATURES);
+        let max_push_constant_size = if features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            128
+        } else {
+            0
+        };
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    features: adapter_features & wgpu::Features::PUSH_CONSTANTS,
+                    features,
                     limits:  wgpu::Limits {
-                        max_push_constant_size: 128,
+                        max_push_constant_size,
                         ..wgpu::Limits::default()
                     },
                     shader_validation: true,
                 },
                 None,
             )
-            .await
-            .unwrap();
+            .await?;
+
+        let capabilities = Capabilities {
+            adapter_info: adapter.get_info(),
+            features,
+        };
+        log::info!(
+            "Using adapter: {} ({:?})",
+            capabilities.adapter_info.name,
+            capabilities.adapter_info.backend
+        );
+        if !capabilities.push_constants() {
+            log::warn!("Push constants aren't supported, shadows will use uniform buffers instead.");
+        }
+
+        resources.insert(capabilities);
+        resources.insert(Arc::new(queue));
 
-        let sc_desc = wgpu::SwapChainDescriptor {
+        Ok((adapter, Arc::new(device)))
+    }
+
+    fn create_sc_desc(
+        size: winit::dpi::PhysicalSize<u32>,
+        config: &AppConfig,
+    ) -> wgpu::SwapChainDescriptor {
+        wgpu::SwapChainDescriptor {
             usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
             format: FRAME_FORMAT,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-        };
-        let swap_chain = device.create_swap_chain(&surface, &sc_desc);
+            present_mode: config.present_mode.into(),
+        }
+    }
 
+    fn create_headless_target(
+        device: &wgpu::Device,
+        sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> RenderTarget {
+        RenderTarget::new(
+            device,
+            sc_desc.width as f32,
+            sc_desc.height as f32,
+            1,
+            1,
+            sc_desc.format,
+            wgpu::TextureUsage::OUTPUT_ATTACHMENT
+                | wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC,
+        )
+    }
+
+    fn create_depth_texture(
+        device: &wgpu::Device,
+        sc_desc: &wgpu::SwapChainDescriptor,
+    ) -> DepthTexture {
         let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
                 width: sc_desc.width,
@@ -73,15 +293,23 @@ impl Renderer {
             usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
             label: None,
         });
-        let device = Arc::new(device);
+        DepthTexture(depth_texture.create_default_view())
+    }
+
+    fn insert_resources(
+        device: Arc<wgpu::Device>,
+        sc_desc: wgpu::SwapChainDescriptor,
+        shadow_quality: ShadowQuality,
+        resources: &mut Resources,
+    ) {
+        let depth_texture = Self::create_depth_texture(&device, &sc_desc);
 
         // Omni Shadow manager
-        // TODO: Expose this as configurable to the user.
         let omni_manager = crate::graphics::shadows::OmniShadowManager::new(
             device.clone(),
-            ShadowQuality::Medium
+            shadow_quality,
         );
-        
+
         let gpu_resource_manager = Arc::new(GPUResourceManager::new(device.clone(), &omni_manager));
         let pipeline_manager = PipelineManager::new();
 
@@ -89,22 +317,183 @@ impl Renderer {
         resources.insert(pipeline_manager);
         resources.insert(gpu_resource_manager);
         resources.insert(sc_desc);
-        resources.insert(Arc::new(queue));
-        resources.insert(device.clone());
-        resources.insert(DepthTexture(depth_texture.create_default_view()));
-        
-        Self {
-            surface,
-            size,
-            adapter,
-            swap_chain,
+        resources.insert(device);
+        resources.insert(depth_texture);
+    }
+
+    /// Returns true if this renderer draws into an offscreen target instead of a window.
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
+    }
+
+    /// The offscreen target frames are rendered into when running headless.
+    pub fn headless_target(&self) -> Option<Arc<RenderTarget>> {
+        self.headless_target.clone()
+    }
+
+    /// Returns true if the window has no area to render into, for example when it's minimized.
+    pub fn is_minimized(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
+    }
+
+    /// Recreates the swap chain(or headless target) and depth buffer using the current swap chain descriptor.
+    pub(crate) fn resize(&mut self, resources: &mut Resources) {
+        let depth_texture = {
+            let device = resources.get::<Arc<wgpu::Device>>().unwrap();
+            let sc_desc = resources.get::<wgpu::SwapChainDescriptor>().unwrap();
+
+            self.size = winit::dpi::PhysicalSize::new(sc_desc.width, sc_desc.height);
+            // Swap chains can't be empty, they get recreated once the window is restored.
+            if self.is_minimized() {
+                return;
+            }
+
+            if let Some(surface) = self.surface.as_ref() {
+                self.swap_chain = Some(device.create_swap_chain(surface, &sc_desc));
+            } else {
+                self.headless_target =
+                    Some(Arc::new(Self::create_headless_target(&device, &sc_desc)));
+            }
+
+            Self::create_depth_texture(&device, &sc_desc)
+        };
+        resources.insert(depth_texture);
+    }
+
+    /// Creates a surface, swap chain and depth buffer for an extra window.
+    pub(crate) fn add_window(
+        &mut self,
+        window: winit::window::Window,
+        camera: Option<Entity>,
+        config: &AppConfig,
+        device: &wgpu::Device,
+    ) -> WindowId {
+        let surface = unsafe { self.instance.create_surface(&window) };
+        let sc_desc = Self::create_sc_desc(window.inner_size(), config);
+        let depth_texture = Self::create_depth_texture(device, &sc_desc);
+        let id = window.id();
+        let mut render_window = RenderWindow {
             window,
+            surface,
+            sc_desc,
+            swap_chain: None,
+            depth_texture,
+            camera,
+        };
+        render_window.recreate(device);
+        self.windows.insert(id, render_window);
+        id
+    }
+
+    /// Returns the extra window with the given id.
+    pub fn get_window(&self, id: WindowId) -> Option<&RenderWindow> {
+        self.windows.get(&id)
+    }
+
+    /// Returns the extra window with the given id.
+    pub fn get_window_mut(&mut self, id: WindowId) -> Option<&mut RenderWindow> {
+        self.windows.get_mut(&id)
+    }
+
+    // Cameras shown in extra windows, the main window doesn't render these.
+    pub(crate) fn window_cameras(&self) -> Vec<Entity> {
+        self.windows
+            .values()
+            .filter_map(|window| window.camera)
+            .collect()
+    }
+
+    /// Resizes an extra window's swap chain and depth buffer.
+    pub(crate) fn resize_window(
+        &mut self,
+        id: WindowId,
+        size: winit::dpi::PhysicalSize<u32>,
+        device: &wgpu::Device,
+    ) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.sc_desc.width = size.width;
+            window.sc_desc.height = size.height;
+            window.recreate(device);
         }
     }
 
-    pub(crate) fn render(&mut self) -> wgpu::SwapChainFrame {
-        let output = self.swap_chain.get_current_frame().unwrap();
+    /// Changes the present mode of every extra window.
+    pub(crate) fn set_windows_present_mode(
+        &mut self,
+        present_mode: wgpu::PresentMode,
+        device: &wgpu::Device,
+    ) {
+        for window in self.windows.values_mut() {
+            window.sc_desc.present_mode = present_mode;
+            window.recreate(device);
+        }
+    }
+
+    /// Returns the texture the next frame of an extra window should be rendered into.
+    /// See `render` for when `Ok(None)` is returned.
+    pub(crate) fn render_window(
+        &mut self,
+        id: WindowId,
+        device: &wgpu::Device,
+    ) -> Result<Option<FrameTexture>, wgpu::SwapChainError> {
+        let window = match self.windows.get_mut(&id) {
+            Some(window) if !window.is_minimized() && window.camera.is_some() => window,
+            _ => return Ok(None),
+        };
+
+        let swap_chain = match window.swap_chain.as_mut() {
+            Some(swap_chain) => swap_chain,
+            None => return Ok(None),
+        };
+
+        match swap_chain.get_current_frame() {
+            Ok(frame) => Ok(Some(FrameTexture::SwapChain(frame.output))),
+            Err(wgpu::SwapChainError::Timeout) => Ok(None),
+            Err(wgpu::SwapChainError::Outdated) | Err(wgpu::SwapChainError::Lost) => {
+                window.recreate(device);
+                Ok(None)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns the texture the next frame should be rendered into.
+    /// Returns `Ok(None)` when the frame should be skipped: the window is minimized, acquiring the frame timed out,
+    /// or the swap chain was outdated or lost and had to be recreated.
+    /// An error is only returned when the device ran out of memory and can't render anymore.
+    pub(crate) fn render(
+        &mut self,
+        resources: &Resources,
+    ) -> Result<Option<FrameTexture>, wgpu::SwapChainError> {
+        if self.is_minimized() {
+            return Ok(None);
+        }
 
-        output
+        let swap_chain = match self.swap_chain.as_mut() {
+            Some(swap_chain) => swap_chain,
+            None => {
+                return Ok(Some(FrameTexture::Offscreen(
+                    self.headless_target.as_ref().unwrap().clone(),
+                )))
+            }
+        };
+
+        match swap_chain.get_current_frame() {
+            Ok(frame) => Ok(Some(FrameTexture::SwapChain(frame.output))),
+            Err(wgpu::SwapChainError::Timeout) => {
+                log::warn!("Timed out acquiring the next frame, skipping it.");
+                Ok(None)
+            }
+            Err(error @ wgpu::SwapChainError::Outdated)
+            | Err(error @ wgpu::SwapChainError::Lost) => {
+                log::warn!("{}, recreating the swap chain.", error);
+                let device = resources.get::<Arc<wgpu::Device>>().unwrap();
+                let sc_desc = resources.get::<wgpu::SwapChainDescriptor>().unwrap();
+                let surface = self.surface.as_ref().unwrap();
+                self.swap_chain = Some(device.create_swap_chain(surface, &sc_desc));
+                Ok(None)
+            }
+            Err(error) => Err(error),
+        }
     }
 }
@@ -28,6 +28,12 @@ impl<'a> ArcRenderPass<'a> {
             .set_bind_group(bind_group.index, &bind_group.group, &[]);
     }
 
+    pub fn set_bind_group_internal_with_offsets(&mut self, bind_group: Arc<BindGroup>, offsets: &[wgpu::DynamicOffset]) {
+        let bind_group = self.internal_bind_group_arena.alloc(bind_group);
+        self.render_pass
+            .set_bind_group(bind_group.index, &bind_group.group, offsets);
+    }
+
     pub fn set_bind_group(
         &mut self,
         slot: u32,
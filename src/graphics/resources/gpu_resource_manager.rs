@@ -288,6 +288,13 @@ impl GPUResourceManager {
         }
     }
 
+    /// Returns true if a multi-buffer was added for the item.
+    pub fn has_multi_buffer<T: Into<String>>(&self, key: T, item_index: u32) -> bool {
+        self.multi_buffer
+            .get(&key.into())
+            .map_or(false, |item_hash_map| item_hash_map.contains_key(&item_index))
+    }
+
     /// Let's you retrieve a multi-buffer.
     pub fn get_multi_buffer<T: Into<String>>(&self, key: T, item_index: u32) -> Arc<wgpu::Buffer> {
         self.multi_buffer
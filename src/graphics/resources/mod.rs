@@ -2,10 +2,12 @@ mod bind_group;
 mod gpu_resource_manager;
 mod probe;
 mod probe_manager;
+mod readback;
 mod render_target;
 
 pub use bind_group::BindGroup;
 pub use gpu_resource_manager::GPUResourceManager;
+pub use readback::{read_texture, save_hdr, to_hdr_image, to_rgba_image, HdrImage};
 pub use render_target::RenderTarget;
 
 pub(crate) use probe::CurrentRenderTarget;
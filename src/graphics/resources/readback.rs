@@ -0,0 +1,257 @@
+use super::RenderTarget;
+use std::path::Path;
+
+/// An image with linear floating point colors. Used for HDR readback and export.
+pub type HdrImage = image::ImageBuffer<image::Rgb<f32>, Vec<f32>>;
+
+// wgpu requires each row in a texture to buffer copy to be aligned to 256 bytes.
+const COPY_BYTES_PER_ROW_ALIGNMENT: u32 = 256;
+
+/// Returns the size of a single pixel in bytes for formats that can be read back.
+pub(crate) fn bytes_per_pixel(format: wgpu::TextureFormat) -> Option<u32> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm
+        | wgpu::TextureFormat::Rgba8UnormSrgb
+        | wgpu::TextureFormat::Bgra8Unorm
+        | wgpu::TextureFormat::Bgra8UnormSrgb => Some(4),
+        wgpu::TextureFormat::Rgba16Float => Some(8),
+        wgpu::TextureFormat::Rgba32Float => Some(16),
+        _ => None,
+    }
+}
+
+/// Copies the first layer of a texture into a mappable buffer and returns the tightly packed pixel data.
+/// The texture needs to be created with `wgpu::TextureUsage::COPY_SRC`.
+/// Returns `None` if the format isn't supported or the buffer couldn't be mapped.
+/// Note: This blocks until the GPU has finished all submitted work.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> Option<Vec<u8>> {
+    let unpadded_bytes_per_row = width * bytes_per_pixel(format)?;
+    let padding = (COPY_BYTES_PER_ROW_ALIGNMENT
+        - unpadded_bytes_per_row % COPY_BYTES_PER_ROW_ALIGNMENT)
+        % COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback buffer"),
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("readback"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::TextureCopyView {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        wgpu::BufferCopyView {
+            buffer: &buffer,
+            layout: wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: padded_bytes_per_row,
+                rows_per_image: height,
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        },
+    );
+    queue.submit(vec![encoder.finish()]);
+
+    let buffer_slice = buffer.slice(..);
+    let buffer_future = buffer_slice.map_async(wgpu::MapMode::Read);
+    device.poll(wgpu::Maintain::Wait);
+    if futures::executor::block_on(buffer_future).is_err() {
+        log::error!("Couldn't map readback buffer.");
+        return None;
+    }
+
+    let data = {
+        let padded_data = buffer_slice.get_mapped_range();
+        let mut data = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in padded_data.chunks(padded_bytes_per_row as usize) {
+            data.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+        data
+    };
+    buffer.unmap();
+
+    Some(data)
+}
+
+/// Converts tightly packed pixel data into an 8 bit RGBA image.
+/// Floating point formats are clamped and converted to sRGB.
+pub fn to_rgba_image(
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+) -> Option<image::RgbaImage> {
+    let pixels = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => data,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => data
+            .chunks(4)
+            .flat_map(|pixel| vec![pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect(),
+        wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float => {
+            read_floats(format, &data)?
+                .chunks(4)
+                .flat_map(|pixel| {
+                    vec![
+                        to_srgb_byte(pixel[0]),
+                        to_srgb_byte(pixel[1]),
+                        to_srgb_byte(pixel[2]),
+                        (pixel[3].max(0.0).min(1.0) * 255.0).round() as u8,
+                    ]
+                })
+                .collect()
+        }
+        _ => return None,
+    };
+
+    image::RgbaImage::from_raw(width, height, pixels)
+}
+
+/// Converts tightly packed pixel data into a linear floating point image.
+/// 8 bit formats are normalized and sRGB formats are converted to linear.
+pub fn to_hdr_image(
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+) -> Option<HdrImage> {
+    let pixels: Vec<f32> = match format {
+        wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float => read_floats(format, &data)?
+            .chunks(4)
+            .flat_map(|pixel| vec![pixel[0], pixel[1], pixel[2]])
+            .collect(),
+        _ => to_rgba_image(format, width, height, data)?
+            .pixels()
+            .flat_map(|pixel| {
+                let is_srgb = format == wgpu::TextureFormat::Rgba8UnormSrgb
+                    || format == wgpu::TextureFormat::Bgra8UnormSrgb;
+                (0..3)
+                    .map(|i| {
+                        let value = pixel[i] as f32 / 255.0;
+                        if is_srgb {
+                            srgb_to_linear(value)
+                        } else {
+                            value
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+    };
+
+    HdrImage::from_raw(width, height, pixels)
+}
+
+/// Saves a floating point image as a radiance(.hdr) file.
+pub fn save_hdr<P: AsRef<Path>>(image: &HdrImage, path: P) -> image::ImageResult<()> {
+    let file = std::fs::File::create(path)?;
+    let encoder = image::hdr::HdrEncoder::new(std::io::BufWriter::new(file));
+    let pixels: Vec<image::Rgb<f32>> = image.pixels().cloned().collect();
+    encoder.encode(&pixels, image.width() as usize, image.height() as usize)
+}
+
+impl RenderTarget {
+    /// Reads the render target back from the GPU as an 8 bit RGBA image.
+    /// The render target needs to be created with `wgpu::TextureUsage::COPY_SRC`.
+    pub fn read_rgba(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<image::RgbaImage> {
+        let data = read_texture(device, queue, &self.texture, self.format, self.width, self.height)?;
+        to_rgba_image(self.format, self.width, self.height, data)
+    }
+
+    /// Reads the render target back from the GPU as a linear floating point image.
+    /// The render target needs to be created with `wgpu::TextureUsage::COPY_SRC`.
+    pub fn read_hdr(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<HdrImage> {
+        let data = read_texture(device, queue, &self.texture, self.format, self.width, self.height)?;
+        to_hdr_image(self.format, self.width, self.height, data)
+    }
+}
+
+fn read_floats(format: wgpu::TextureFormat, data: &[u8]) -> Option<Vec<f32>> {
+    match format {
+        wgpu::TextureFormat::Rgba16Float => Some(
+            data.chunks(2)
+                .map(|bytes| f16_to_f32(u16::from_le_bytes([bytes[0], bytes[1]])))
+                .collect(),
+        ),
+        wgpu::TextureFormat::Rgba32Float => Some(
+            data.chunks(4)
+                .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => std::f32::INFINITY,
+        31 => std::f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+fn to_srgb_byte(linear: f32) -> u8 {
+    let linear = linear.max(0.0).min(1.0);
+    let srgb = if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
+
+fn srgb_to_linear(srgb: f32) -> f32 {
+    if srgb <= 0.04045 {
+        srgb / 12.92
+    } else {
+        ((srgb + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{f16_to_f32, to_rgba_image};
+
+    #[test]
+    fn should_convert_half_floats() {
+        assert_eq!(f16_to_f32(0x0000), 0.0);
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x3800), 0.5);
+        assert!(f16_to_f32(0x7c00).is_infinite());
+    }
+
+    #[test]
+    fn should_swizzle_bgra_frames() {
+        let image = to_rgba_image(
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+            1,
+            1,
+            vec![10, 20, 30, 255],
+        )
+        .unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [30, 20, 10, 255]);
+    }
+}
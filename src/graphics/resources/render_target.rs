@@ -13,6 +13,7 @@ pub struct RenderTarget {
 
     pub width: u32,
     pub height: u32,
+    pub format: wgpu::TextureFormat,
 }
 
 impl RenderTarget {
@@ -70,6 +71,7 @@ impl RenderTarget {
             depth_texture_view: None,
             width: width as u32,
             height: height as u32,
+            format,
         }
     }
 
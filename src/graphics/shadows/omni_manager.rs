@@ -1,8 +1,9 @@
 use nalgebra_glm::{Vec3, Vec2, Vec4, Mat4};
 use std::{sync::Arc, borrow::Cow};
-use crate::{core::BoundingSphere, scene::components, graphics::{resources::{ArcRenderPass, GPUResourceManager}, pipeline_manager::{PipelineDesc, PipelineManager}}, AssetManager, assets::mesh::MeshVertexData};
+use crate::{core::BoundingSphere, scene::components, graphics::{resources::{ArcRenderPass, BindGroup, GPUResourceManager}, pipeline_manager::{PipelineDesc, PipelineManager}, TransformSlot}, AssetManager, assets::mesh::MeshVertexData};
 use legion::{systems::{SubWorld, SystemQuery}, prelude::*, filter::{And, EntityFilterTuple, ComponentFilter, Passthrough}};
 use bytemuck::{Zeroable, Pod};
+use serde::{Deserialize, Serialize};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -14,6 +15,10 @@ pub struct ShadowPush {
 unsafe impl Pod for ShadowPush { }
 unsafe impl Zeroable for ShadowPush { }
 
+// Each face's ShadowPush gets its own slot in the fallback uniform buffer.
+// Dynamic offsets need to be aligned to 256 bytes.
+const SHADOW_UNIFORM_SLOT_SIZE: wgpu::BufferAddress = 256;
+
 #[derive(Clone)]
 pub struct ShadowTexture {
     texture: Arc<wgpu::Texture>,
@@ -29,8 +34,11 @@ pub struct OmniShadowManager {
     pub(crate) quad_textures: Vec<ShadowTexture>,
     pub(crate) sampler: Arc<wgpu::Sampler>,
     pub(crate) max_casters_per_frame: u32,
+    // False when the device doesn't support push constants, shadow matrices are passed in a uniform buffer instead.
+    pub(crate) use_push_constants: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ShadowQuality {
     Low,
     Medium,
@@ -136,15 +144,65 @@ impl OmniShadowManager {
             quad_textures,
             sampler,
             max_casters_per_frame: 5,
+            use_push_constants: device.features().contains(wgpu::Features::PUSH_CONSTANTS),
         }
     }
 
-    pub fn create_pipeline(device: Arc<wgpu::Device>, asset_manager: &AssetManager, gpu_resource_manager: Arc<GPUResourceManager>, pipeline_manager: &mut PipelineManager) {
+    // Creates the uniform buffer and bind group used instead of push constants.
+    fn create_uniform_fallback(device: &wgpu::Device, gpu_resource_manager: &GPUResourceManager, max_casters_per_frame: u32) {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: Cow::Borrowed(&[wgpu::BindGroupLayoutEntry::new(
+                0,
+                wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                wgpu::BindingType::UniformBuffer {
+                    dynamic: true,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<ShadowPush>() as _),
+                },
+            )]),
+            label: Some("shadow_uniform"),
+        });
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("shadow_uniform"),
+            size: SHADOW_UNIFORM_SLOT_SIZE * max_casters_per_frame as u64 * 6,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: Cow::Borrowed(&[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(buffer.slice(0..std::mem::size_of::<ShadowPush>() as u64)),
+            }]),
+            label: Some("shadow_uniform"),
+        });
+
+        gpu_resource_manager.add_bind_group_layout("shadow_uniform", bind_group_layout);
+        gpu_resource_manager.add_single_bind_group("shadow_uniform", BindGroup::new(1, bind_group));
+        gpu_resource_manager.add_buffer("shadow_uniform", buffer);
+    }
+
+    pub fn create_pipeline(device: Arc<wgpu::Device>, asset_manager: &AssetManager, gpu_resource_manager: Arc<GPUResourceManager>, pipeline_manager: &mut PipelineManager, max_casters_per_frame: u32) {
+        let use_push_constants = device.features().contains(wgpu::Features::PUSH_CONSTANTS);
+
         // Create pipeline..
         let mut pipeline_desc = PipelineDesc::default();
-        pipeline_desc.shader = "core/shaders/shadow.shader".to_string();
         pipeline_desc.color_states = vec![]; // Clear out color states.
-        pipeline_desc.layouts = vec!["locals".to_string()];
+        if use_push_constants {
+            pipeline_desc.shader = "core/shaders/shadow.shader".to_string();
+            pipeline_desc.layouts = vec!["locals".to_string()];
+            pipeline_desc.push_constant_ranges = vec![
+                wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                    range: 0..80,
+                },
+            ];
+        } else {
+            Self::create_uniform_fallback(&device, &gpu_resource_manager, max_casters_per_frame);
+            pipeline_desc.shader = "core/shaders/shadow_uniform.shader".to_string();
+            pipeline_desc.layouts = vec!["locals".to_string(), "shadow_uniform".to_string()];
+        }
         pipeline_desc.depth_state = Some(wgpu::DepthStencilStateDescriptor {
             format: wgpu::TextureFormat::Depth32Float,
             depth_write_enabled: true,
@@ -154,12 +212,6 @@ impl OmniShadowManager {
             stencil_read_mask: 0,
             stencil_write_mask: 0,
         });
-        pipeline_desc.push_constant_ranges = vec![
-            wgpu::PushConstantRange {
-                stages: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
-                range: 0..80,
-            },
-        ];
         pipeline_desc.depth_bias = 2;
         pipeline_desc.depth_bias_slope_scale = 2.0.into();
         pipeline_desc.depth_bias_clamp = 0.0.into();
@@ -180,12 +232,21 @@ impl OmniShadowManager {
         sorted_point_lights: Vec<(f32, Vec3, (u32, u32))>, // (light attenuation, world position)
         pipeline_manager: &PipelineManager,
         resource_manager: Arc<GPUResourceManager>,
+        queue: &wgpu::Queue,
         encoder: &mut wgpu::CommandEncoder,
         shadow_camera: &mut ShadowCamera,
-        mesh_query: &mut SystemQuery<(Read<components::Mesh>, Read<components::Transform>), EntityFilterTuple<And<(ComponentFilter<components::Mesh>, ComponentFilter<components::Transform>)>, And<(Passthrough, Passthrough)>, And<(Passthrough, Passthrough)>>>,
+        mesh_query: &mut SystemQuery<(Read<components::Mesh>, Read<components::Transform>, Read<TransformSlot>), EntityFilterTuple<And<(ComponentFilter<components::Mesh>, ComponentFilter<components::Transform>, ComponentFilter<TransformSlot>)>, And<(Passthrough, Passthrough, Passthrough)>, And<(Passthrough, Passthrough, Passthrough)>>>,
         world: &mut SubWorld
     ) {
         let pipeline = pipeline_manager.get("shadow", None).unwrap();
+        let shadow_uniform = if self.use_push_constants {
+            None
+        } else {
+            Some((
+                resource_manager.get_buffer("shadow_uniform"),
+                resource_manager.get_bind_group("shadow_uniform", 1).unwrap(),
+            ))
+        };
     
         self.reset_used();
 
@@ -208,7 +269,7 @@ impl OmniShadowManager {
 
             let meshes = mesh_query
                 .iter(world)
-                .filter(|(mesh, transform)| {
+                .filter(|(mesh, transform, _)| {
                     let mesh_data = mesh.mesh_handle.get();
                     
                     if mesh_data.is_err() || transform.cull {
@@ -220,11 +281,9 @@ impl OmniShadowManager {
                     bounding_sphere.center = (transform.matrix * Vec4::new(bounding_sphere.center.x, bounding_sphere.center.y, bounding_sphere.center.z, 1.0)).xyz();
                     return bounding_sphere.intersects_sphere(&light_bounds);
                 })
-                .map(|(mesh, transform)| {
+                .map(|(mesh, _, slot)| {
                     let mesh = mesh.mesh_handle.get().unwrap();
-                    // Arc<Mesh> hard transform on clone.
-                    // TODO: Figure out performance impacts of cloning here..
-                    (mesh.clone(), transform.clone())
+                    (mesh.clone(), *slot)
                 })
                 .collect::<Vec<_>>();
             
@@ -252,18 +311,28 @@ impl OmniShadowManager {
 
                 shadow_camera.resize_range(current_quad.tex_size as f32, current_quad.tex_size as f32, 0.1, light_range);
                 shadow_camera.set_cubic_camera(pos, face as u32);
-                render_pass.set_push_constants(wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT, 0, bytemuck::cast_slice(&[ShadowPush {
+                let shadow_push = ShadowPush {
                     matrix: shadow_camera.get_matrix(),
                     light_pos: Vec4::new(pos.x, pos.y, pos.z, 1.0),
-                }]));
+                };
+                match &shadow_uniform {
+                    Some((buffer, bind_group)) => {
+                        let offset = (total as u64 * 6 + face as u64) * SHADOW_UNIFORM_SLOT_SIZE;
+                        queue.write_buffer(buffer, offset, bytemuck::bytes_of(&shadow_push));
+                        render_pass.set_bind_group_internal_with_offsets(bind_group.clone(), &[offset as wgpu::DynamicOffset]);
+                    }
+                    None => {
+                        render_pass.set_push_constants(wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT, 0, bytemuck::cast_slice(&[shadow_push]));
+                    }
+                }
 
                 // Step 2: Render shadow maps to that space.
-                for (asset_mesh, transform) in meshes.iter() {
+                for (asset_mesh, slot) in meshes.iter() {
                     resource_manager.set_multi_bind_group(
                         &mut render_pass,
                         "transform",
                         0,
-                        transform.index,
+                        slot.0,
                     );
 
                     for mesh in asset_mesh.meshes.iter() {
@@ -0,0 +1,129 @@
+use legion::prelude::*;
+use nalgebra_glm::Vec4;
+use std::{convert::TryInto, sync::Arc};
+
+use crate::{
+    graphics::{
+        lighting::cluster::{FAR_PLANE_DISTANCE, FROXELS_X, FROXELS_Y, FROXELS_Z},
+        pipelines::{DirectionalLight, LightingUniform, PointLight, MAX_LIGHTS},
+        resources::GPUResourceManager,
+        CommandBufferQueue, CommandQueueItem,
+    },
+    scene::components,
+};
+
+// Uploads the lights to the GPU. Light positions are stored in view space so this runs for every camera that's
+// rendered, after the `shadows` system has assigned the shadow maps.
+pub fn create() -> Box<dyn Schedulable> {
+    SystemBuilder::new("upload_lighting")
+        .read_resource::<Arc<GPUResourceManager>>()
+        .read_resource::<Arc<wgpu::Device>>()
+        .write_resource::<CommandBufferQueue>()
+        .with_query(<(Read<components::PointLightData>, Read<components::Transform>)>::query())
+        .with_query(<(Read<components::CameraData>,)>::query())
+        .with_query(<(Read<components::DirectionalLightData>,)>::query())
+        .build(
+            |_,
+             world,
+             (resource_manager, device, command_buffer_queue),
+             (point_light_query, camera_query, directional_light_query)| {
+                let camera_view = match camera_query.iter(&world).find(|(camera,)| camera.active) {
+                    Some((camera,)) => camera.view,
+                    None => return,
+                };
+
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("lighting"),
+                });
+
+                // ******************************************************************************
+                // This section is where we upload our lighting uniforms to the GPU
+                // ******************************************************************************
+                if directional_light_query.iter(&world).count() > 0 || point_light_query.iter(&world).count() > 0  {
+                    let mut directional_light_data_vec: Vec<DirectionalLight> = directional_light_query
+                        .iter(&world)
+                        .map(|(data,)| DirectionalLight {
+                            direction: Vec4::new(
+                                data.direction.x,
+                                data.direction.y,
+                                data.direction.z,
+                                0.0,
+                            ),
+                            color: Vec4::new(data.color.x, data.color.y, data.color.z, data.intensity),
+                        })
+                        .collect();
+
+                    // TODO: Use some sort of distance calculation to get the closest lights.
+                    let mut point_light_data_vec: Vec<PointLight> = point_light_query
+                        .iter(&world)
+                        .map(|(data, transform)| {
+                            let position = Vec4::new(
+                                transform.position.x,
+                                transform.position.y,
+                                transform.position.z,
+                                1.0,
+                            );
+                            PointLight {
+                                attenuation: Vec4::new(data.attenuation, if data.shadow { 1.0 } else { 0.0 }, data.shadow_texture_id.0 as f32, data.shadow_texture_id.1 as f32),
+                                color: Vec4::new(data.color.x, data.color.y, data.color.z, data.intensity),
+                                position,
+                                view_position: camera_view * position,
+                                shadow_matrix: nalgebra_glm::perspective_fov_lh_no(
+                                    90f32.to_radians(),
+                                    512.0,
+                                    512.0,
+                                    0.1,
+                                    data.attenuation,
+                                ),
+                                ..Default::default()
+                            }
+                        })
+                        .collect();
+
+                    let total_dir_lights = directional_light_data_vec.len() as u32;
+                    let total_point_lights = point_light_data_vec.len() as u32;
+
+                    // Fill in missing data if we don't have it.
+                    point_light_data_vec.resize_with(MAX_LIGHTS, || PointLight::default());
+                    directional_light_data_vec
+                        .resize_with(4, || DirectionalLight::default());
+
+                    let light_uniform = LightingUniform {
+                        cluster_count: [FROXELS_X, FROXELS_Y, FROXELS_Z, 0],
+                        light_num: Vec4::new(
+                            total_dir_lights as f32,
+                            total_point_lights as f32,
+                            0.0,
+                            FAR_PLANE_DISTANCE,
+                        ),
+                        directional_lights: directional_light_data_vec
+                            .as_slice()
+                            .try_into()
+                            .unwrap(),
+                        point_lights: point_light_data_vec.as_slice().try_into().unwrap(),
+                    };
+
+                    let lighting_buffer = device.create_buffer_with_data(
+                        bytemuck::bytes_of(&light_uniform),
+                        wgpu::BufferUsage::COPY_SRC,
+                    );
+
+                    encoder.copy_buffer_to_buffer(
+                        &lighting_buffer,
+                        0,
+                        &resource_manager.global_lighting_buffer,
+                        0,
+                        std::mem::size_of::<LightingUniform>() as u64,
+                    );
+                }
+
+
+                command_buffer_queue
+                    .push(CommandQueueItem {
+                        buffer: encoder.finish(),
+                        name: "lighting".to_string(),
+                    })
+                    .unwrap();
+            },
+        )
+}
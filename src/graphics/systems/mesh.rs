@@ -1,16 +1,12 @@
 use crate::{
-    assets::{
-        material::{PBRMaterial, PBRMaterialRon},
-        AssetHandle,
-    },
+    assets::{material::PBRMaterial, AssetHandle},
     graphics::{
         pipeline_manager::PipelineManager,
-        renderer::DepthTexture,
+        renderer::{DepthTexture, FrameTexture},
         resources::{ArcRenderPass, GPUResourceManager},
-        CommandBufferQueue, CommandQueueItem,
+        CommandBufferQueue, CommandQueueItem, RenderMaterials, TransformSlot,
     },
-    scene::components,
-    AssetManager,
+    scene::{components, resources::InterpolationAlpha},
 };
 use components::transform::LocalUniform;
 use legion::prelude::*;
@@ -19,22 +15,23 @@ use std::{borrow::Cow, sync::Arc};
 pub fn create() -> Box<dyn Schedulable> {
     SystemBuilder::new("render_mesh")
         .write_resource::<crate::core::PerformanceMetrics>()
-        .write_resource::<AssetManager>()
+        .read_resource::<RenderMaterials>()
         .write_resource::<CommandBufferQueue>()
         .read_resource::<Arc<wgpu::Device>>()
         .read_resource::<Arc<wgpu::Queue>>()
-        .read_resource::<Arc<wgpu::SwapChainTexture>>()
+        .read_resource::<Arc<FrameTexture>>()
         .read_resource::<Arc<GPUResourceManager>>()
         .read_resource::<DepthTexture>()
         .read_resource::<PipelineManager>()
-        .with_query(<(Write<components::Transform>,)>::query())
-        .with_query(<(Read<components::Mesh>, Read<components::Transform>)>::query())
+        .read_resource::<InterpolationAlpha>()
+        .with_query(<(Read<TransformSlot>, Write<components::Transform>)>::query())
+        .with_query(<(Read<components::Mesh>, Read<components::Transform>, Read<TransformSlot>)>::query())
         .build(
             |_,
              mut world,
              (
                 perf_metrics,
-                asset_manager,
+                materials,
                 command_buffer_queue,
                 device,
                 queue,
@@ -42,6 +39,7 @@ pub fn create() -> Box<dyn Schedulable> {
                 resource_manager,
                 depth_texture,
                 pipeline_manager,
+                interpolation_alpha,
             ),
              (transform_query, mesh_query)| {
                 // Create mesh encoder
@@ -63,18 +61,18 @@ pub fn create() -> Box<dyn Schedulable> {
                     // });
 
                     // FIXME: Align and use `LayoutVerified`
-                    for (mut transform,) in transform_query.iter_mut(mut_world) {
+                    for (slot, mut transform) in transform_query.iter_mut(mut_world) {
                         if transform.cull {
                             continue;
                         }
                         transform.update();
                         let transform_buffer =
-                            resource_manager.get_multi_buffer("transform", transform.index);
+                            resource_manager.get_multi_buffer("transform", slot.0);
                         queue.write_buffer(
                             &transform_buffer,
                             0,
                             bytemuck::bytes_of(&LocalUniform {
-                                world: transform.matrix,
+                                world: transform.interpolated_matrix(interpolation_alpha.0),
                             }),
                         );
                     }
@@ -84,11 +82,11 @@ pub fn create() -> Box<dyn Schedulable> {
                 // This section is where we actually render our meshes.
                 // ******************************************************************************
                 // Collect materials in to their groups.
-                let asset_materials: Vec<Arc<AssetHandle<PBRMaterial>>> = asset_manager.get_all_materials::<PBRMaterialRon>();
+                let asset_materials: Vec<Arc<AssetHandle<PBRMaterial>>> = materials.0.clone();
                 {
                     let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                         color_attachments: Cow::Borrowed(&[wgpu::RenderPassColorAttachmentDescriptor {
-                            attachment: &output.view,
+                            attachment: output.view(),
                             resolve_target: None,
                             ops: wgpu::Operations {
                                 load: wgpu::LoadOp::Load,
@@ -131,7 +129,7 @@ pub fn create() -> Box<dyn Schedulable> {
                                 material.bind_group.as_ref().unwrap().clone(),
                             );
 
-                            for (mesh_component, transform) in mesh_query.iter(&world) {
+                            for (mesh_component, transform, slot) in mesh_query.iter(&world) {
                                 if transform.cull {
                                     continue;
                                 }
@@ -140,7 +138,7 @@ pub fn create() -> Box<dyn Schedulable> {
                                     &mut render_pass,
                                     "transform",
                                     0,
-                                    transform.index,
+                                    slot.0,
                                 );
 
                                 // If mesh is ready render it!
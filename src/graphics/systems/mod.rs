@@ -4,15 +4,49 @@ pub mod mesh;
 pub mod render;
 pub mod skybox;
 pub mod froxel;
+pub mod lighting;
 pub mod shadow;
+pub mod transform;
 
-use legion::prelude::*;
-use legion::systems::schedule::Builder;
-pub fn create_render_schedule_builder() -> Builder {
-    Schedule::builder()
-        .add_system(crate::graphics::systems::froxel::create())
-        .add_system(crate::graphics::systems::globals::create())
-        .add_system(skybox::create())
-    // .add_system(line::create())
-    // .add_system(mesh::create())
+use crate::core::{Stage, StagedSchedule, SystemDesc};
+
+/// Creates the render schedule with harmony's render systems in `Stage::Render`.
+/// GPU buffers for new transform slots are created in `Stage::Extract`.
+/// Command buffers are submitted between `Stage::Render` and `Stage::PostRender`.
+pub fn create_render_schedule() -> StagedSchedule {
+    let mut schedule = StagedSchedule::render();
+    schedule
+        .add_system(Stage::Extract, SystemDesc::new(transform::create()).label("transform_slots"))
+        .add_system(Stage::Render, SystemDesc::new(froxel::create()).label("compute_froxels"))
+        .add_system(Stage::Render, SystemDesc::new(globals::create()).label("encoder_globals"))
+        .add_system(Stage::Render, SystemDesc::new(skybox::create()).label("render_skybox"))
+        // .add_system(Stage::Render, line::create())
+        .add_system(Stage::Render, SystemDesc::new(shadow::create()).label("shadows"))
+        .add_system(
+            Stage::Render,
+            SystemDesc::new(lighting::create()).label("upload_lighting").after("shadows"),
+        )
+        .add_system(
+            Stage::Render,
+            SystemDesc::new(mesh::create()).label("render_mesh").after("shadows"),
+        );
+    schedule.set_thread_local_fn(Box::new(render::create()));
+    schedule
+}
+
+/// Creates the schedule windows opened with `Application::open_window` render with.
+/// Only the camera dependent systems run, shadow maps are reused from the main window's pass.
+/// `transform_slots` only creates missing buffers, it runs here too so new meshes show up while the main window is minimized.
+/// Render systems added to the application only run for the main window.
+pub fn create_window_render_schedule() -> StagedSchedule {
+    let mut schedule = StagedSchedule::render();
+    schedule
+        .add_system(Stage::Extract, SystemDesc::new(transform::create()).label("transform_slots"))
+        .add_system(Stage::Render, SystemDesc::new(froxel::create()).label("compute_froxels"))
+        .add_system(Stage::Render, SystemDesc::new(globals::create()).label("encoder_globals"))
+        .add_system(Stage::Render, SystemDesc::new(skybox::create()).label("render_skybox"))
+        .add_system(Stage::Render, SystemDesc::new(lighting::create()).label("upload_lighting"))
+        .add_system(Stage::Render, SystemDesc::new(mesh::create()).label("render_mesh"));
+    schedule.set_thread_local_fn(Box::new(render::create()));
+    schedule
 }
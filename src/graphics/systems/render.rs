@@ -2,7 +2,7 @@ use crate::graphics::{pipeline_manager::PipelineManager, CommandBufferQueue};
 use legion::prelude::*;
 use std::sync::Arc;
 
-pub fn create() -> Box<dyn Fn(&mut World, &mut Resources) -> ()> {
+pub fn create() -> Box<dyn Fn(&mut World, &mut Resources) + Send> {
     let thread = Box::new(|_world: &mut World, resources: &mut Resources| {
         let mut command_buffers = Vec::new();
 
@@ -1,7 +1,7 @@
 use crate::graphics::{
     material::{skybox::SkyboxType, Skybox},
     pipeline_manager::{Pipeline, PipelineManager},
-    renderer::DepthTexture,
+    renderer::{DepthTexture, FrameTexture},
     resources::{CurrentRenderTarget, GPUResourceManager},
     CommandBufferQueue, CommandQueueItem,
 };
@@ -15,7 +15,7 @@ pub fn create() -> Box<dyn Schedulable> {
         .read_resource::<Arc<GPUResourceManager>>()
         .read_resource::<PipelineManager>()
         .read_resource::<Arc<wgpu::Device>>()
-        .read_resource::<Arc<wgpu::SwapChainTexture>>()
+        .read_resource::<Arc<FrameTexture>>()
         .read_resource::<DepthTexture>()
         .with_query(<(Read<Skybox>,)>::query())
         .build(
@@ -38,7 +38,7 @@ pub fn create() -> Box<dyn Schedulable> {
                 let view_attachment = if current_render_target.0.is_some() {
                     &current_render_target.0.as_ref().unwrap().1
                 } else {
-                    &output.view
+                    output.view()
                 };
 
                 let depth_attachment = if current_render_target.0.is_some() {
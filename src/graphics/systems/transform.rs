@@ -0,0 +1,46 @@
+use legion::prelude::*;
+use std::{borrow::Cow, sync::Arc};
+
+use crate::{
+    graphics::{
+        resources::{BindGroup, GPUResourceManager},
+        TransformSlot,
+    },
+    scene::components::transform::LocalUniform,
+};
+
+// Creates the uniform buffer and bind group of a transform slot the first time it's used.
+// Slots of deleted meshes are reused by the render world so their buffers are reused as well.
+pub fn create() -> Box<dyn Schedulable> {
+    SystemBuilder::new("transform_slots")
+        .read_resource::<Arc<wgpu::Device>>()
+        .read_resource::<Arc<GPUResourceManager>>()
+        .with_query(<Read<TransformSlot>>::query())
+        .build(|_, world, (device, resource_manager), slot_query| {
+            for slot in slot_query.iter(&world) {
+                if !resource_manager.has_multi_buffer("transform", slot.0) {
+                    create_bindings(device, resource_manager, slot.0);
+                }
+            }
+        })
+}
+
+fn create_bindings(device: &wgpu::Device, resource_manager: &GPUResourceManager, slot: u32) {
+    let bind_group_layout = resource_manager.get_bind_group_layout("locals").unwrap();
+    let local_buffer = device.create_buffer_with_data(
+        bytemuck::bytes_of(&LocalUniform::default()),
+        wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+    );
+
+    let local_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &bind_group_layout,
+        entries: Cow::Borrowed(&[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(local_buffer.slice(..)),
+        }]),
+        label: None,
+    });
+
+    resource_manager.add_multi_bind_group("transform", BindGroup::new(0, local_bind_group), slot);
+    resource_manager.add_multi_buffer("transform", local_buffer, slot);
+}
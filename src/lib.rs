@@ -6,13 +6,22 @@
 pub mod core;
 pub mod graphics;
 pub mod scene;
+pub mod testing;
 
+mod app_config;
 mod application;
 mod assets;
+mod error;
+mod plugin;
 mod winit_state;
 
+pub use app_config::{AppConfig, Backend, FullscreenMode, PowerPreference, PresentMode};
 pub use application::{AppState, Application};
-pub use assets::AssetManager;
+pub use assets::{
+    AddressMode, AssetError, AssetHandle, AssetLoader, AssetManager, AssetSource, BundleBuilder, BundleSource,
+    DirectorySource, FilterMode, LoadContext, LoaderError, MemorySource, MeshSettings, OverlaySource, SamplerSettings,
+    ShaderSettings, TextureCompression, TextureSettings,
+};
+pub use error::HarmonyError;
+pub use plugin::{AppBuilder, Plugin};
 pub use winit_state::WinitState;
-
-pub struct TransformCount(u32);
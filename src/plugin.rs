@@ -0,0 +1,234 @@
+use legion::prelude::*;
+use std::path::PathBuf;
+use winit::event_loop::EventLoop;
+
+use crate::{
+    core::{input::GamepadBackend, Stage, SystemDesc},
+    graphics::{render_thread::RenderResource, Renderer},
+    AppConfig, Application, AssetManager, HarmonyError,
+};
+
+/// Packages systems, resources, pipelines and asset loaders so they can be added to an application in one go.
+///
+/// Example:
+/// ```ignore
+/// struct MyPlugin;
+///
+/// impl Plugin for MyPlugin {
+///     fn build(&self, app: &mut AppBuilder) {
+///         app.insert_resource(MyResource::default())
+///             .add_system(my_system::create())
+///             .add_render_system(my_render_system::create());
+///     }
+/// }
+/// ```
+pub trait Plugin {
+    /// Called once when the plugin is added to an `AppBuilder`.
+    fn build(&self, app: &mut AppBuilder);
+}
+
+pub(crate) type PipelineFn = Box<dyn FnOnce(&Resources)>;
+pub(crate) type AssetLoaderFn = Box<dyn FnOnce(&mut AssetManager)>;
+
+/// Collects everything needed to create an `Application`.
+/// Use `build` to create a windowed application or `build_headless` to create one without a window.
+pub struct AppBuilder {
+    pub(crate) asset_path: PathBuf,
+    pub(crate) config: AppConfig,
+    /// Legion resources, these are moved into the application once it's built.
+    pub resources: Resources,
+    pub(crate) systems: Vec<(Stage, SystemDesc)>,
+    pub(crate) render_systems: Vec<(Stage, SystemDesc)>,
+    // Resources inserted with `insert_render_resource`, moved to the render thread while a frame renders.
+    pub(crate) render_resources: Vec<RenderResource>,
+    pub(crate) pipelines: Vec<PipelineFn>,
+    pub(crate) asset_loaders: Vec<AssetLoaderFn>,
+    pub(crate) bundles: Vec<PathBuf>,
+    pub(crate) gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    plugins: Vec<&'static str>,
+}
+
+impl AppBuilder {
+    /// # Arguments
+    ///
+    /// * `asset_path` - Path to the asset folder.
+    /// * `config` - Engine settings, use `AppConfig::default()` or load them with `AppConfig::load`.
+    pub fn new<T: Into<PathBuf>>(asset_path: T, config: AppConfig) -> Self {
+        Self {
+            asset_path: asset_path.into(),
+            config,
+            resources: Resources::default(),
+            systems: Vec::new(),
+            render_systems: Vec::new(),
+            render_resources: Vec::new(),
+            pipelines: Vec::new(),
+            asset_loaders: Vec::new(),
+            bundles: Vec::new(),
+            gamepad_backend: None,
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Adds a plugin. Adding the same plugin type twice does nothing.
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
+        let name = std::any::type_name::<P>();
+        if self.plugins.contains(&name) {
+            log::warn!("Plugin {} was already added.", name);
+            return self;
+        }
+        self.plugins.push(name);
+        plugin.build(self);
+        self
+    }
+
+    /// Returns true if a plugin of type `P` has been added.
+    pub fn has_plugin<P: Plugin>(&self) -> bool {
+        self.plugins.contains(&std::any::type_name::<P>())
+    }
+
+    /// Inserts a legion resource. Resources harmony creates itself(ex: `Arc<wgpu::Device>`) can't be replaced.
+    pub fn insert_resource<T: Resource>(&mut self, resource: T) -> &mut Self {
+        self.resources.insert(resource);
+        self
+    }
+
+    /// Inserts a legion resource read by render systems. Render systems run on the render thread against their
+    /// own resources, the resource is moved there while a frame renders and is back in `Application::resources`
+    /// once the frame has been submitted, see `Application::wait_for_render`.
+    pub fn insert_render_resource<T: Resource>(&mut self, resource: T) -> &mut Self {
+        self.resources.insert(resource);
+        self.render_resources.push(RenderResource::owned::<T>());
+        self
+    }
+
+    /// Adds a system to `Stage::Update` of the scene's game schedule which runs every fixed update.
+    ///
+    /// *Note*: Game systems are only added to the scene created with the application, scenes set with
+    /// `Application::set_scene` need to add them to their own schedule.
+    pub fn add_system<S: Into<SystemDesc>>(&mut self, system: S) -> &mut Self {
+        self.add_system_to_stage(Stage::Update, system)
+    }
+
+    /// Adds a system to `Stage::Render` of the render schedule which runs once per frame on the render thread.
+    /// Resources the system reads, other than harmony's own, have to be inserted with `insert_render_resource`.
+    pub fn add_render_system<S: Into<SystemDesc>>(&mut self, system: S) -> &mut Self {
+        self.add_system_to_stage(Stage::Render, system)
+    }
+
+    /// Adds a system to the given stage. Render stages are added to the render schedule, the others to the game schedule.
+    pub fn add_system_to_stage<S: Into<SystemDesc>>(&mut self, stage: Stage, system: S) -> &mut Self {
+        if stage.is_render() {
+            self.render_systems.push((stage, system.into()));
+        } else {
+            self.systems.push((stage, system.into()));
+        }
+        self
+    }
+
+    /// Adds a function that creates pipelines, called from `Application::load` after harmony's own pipelines
+    /// have been added to the `PipelineManager`.
+    pub fn add_pipeline<F>(&mut self, create: F) -> &mut Self
+    where
+        F: FnOnce(&Resources) + 'static,
+    {
+        self.pipelines.push(Box::new(create));
+        self
+    }
+
+    /// Adds a function that registers asset loaders, called as soon as the `AssetManager` is created.
+    ///
+    /// Example:
+    /// ```ignore
+    /// app.add_asset_loader(|asset_manager| asset_manager.register::<MyAsset>());
+    /// ```
+    pub fn add_asset_loader<F>(&mut self, register: F) -> &mut Self
+    where
+        F: FnOnce(&mut AssetManager) + 'static,
+    {
+        self.asset_loaders.push(Box::new(register));
+        self
+    }
+
+    /// Mounts an asset bundle written by `BundleBuilder` before any asset is loaded, bundles added later
+    /// are mounted over earlier ones. Building the application fails if the bundle can't be opened.
+    pub fn mount_bundle<T: Into<PathBuf>>(&mut self, path: T) -> &mut Self {
+        self.bundles.push(path.into());
+        self
+    }
+
+    /// Replaces the backend gamepads are read from. Without this gilrs is used when the `gilrs` feature is enabled.
+    pub fn set_gamepad_backend<B: GamepadBackend + 'static>(&mut self, backend: B) -> &mut Self {
+        self.gamepad_backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Creates the application with a window.
+    /// Returns an error if no compatible graphics adapter was found or harmony's core assets couldn't be loaded.
+    pub fn build(
+        mut self,
+        window_builder: winit::window::WindowBuilder,
+        event_loop: &EventLoop<()>,
+    ) -> Result<Application, HarmonyError> {
+        let window = window_builder.build(event_loop)?;
+        let size = window.inner_size();
+        let renderer = futures::executor::block_on(Renderer::new(
+            window,
+            size,
+            &self.config,
+            &mut self.resources,
+        ))?;
+
+        Application::from_renderer(renderer, self)
+    }
+
+    /// Creates the application without a window, see `Application::new_headless`.
+    pub fn build_headless(
+        mut self,
+        size: winit::dpi::PhysicalSize<u32>,
+    ) -> Result<Application, HarmonyError> {
+        let renderer = futures::executor::block_on(Renderer::new_headless(
+            size,
+            &self.config,
+            &mut self.resources,
+        ))?;
+
+        Application::from_renderer(renderer, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppBuilder, Plugin};
+    use crate::AppConfig;
+    use legion::prelude::*;
+
+    struct Counter(u32);
+
+    struct RenderCounter(u32);
+
+    struct CounterPlugin;
+
+    impl Plugin for CounterPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.insert_resource(Counter(0))
+                .insert_render_resource(RenderCounter(0))
+                .add_system(SystemBuilder::new("count").build(|_, _, _, _| {}))
+                .add_render_system(SystemBuilder::new("render_count").build(|_, _, _, _| {}))
+                .add_pipeline(|_| {});
+        }
+    }
+
+    #[test]
+    fn should_register_plugin_once() {
+        let mut builder = AppBuilder::new("./assets/", AppConfig::default());
+        builder.add_plugin(CounterPlugin).add_plugin(CounterPlugin);
+
+        assert!(builder.has_plugin::<CounterPlugin>());
+        assert!(builder.resources.contains::<Counter>());
+        assert!(builder.resources.contains::<RenderCounter>());
+        assert_eq!(builder.render_resources.len(), 1);
+        assert_eq!(builder.systems.len(), 1);
+        assert_eq!(builder.render_systems.len(), 1);
+        assert_eq!(builder.pipelines.len(), 1);
+    }
+}
@@ -9,6 +9,7 @@ pub enum LightType {
 }
 
 /// Directional light information
+#[derive(Debug, Clone)]
 pub struct DirectionalLightData {
     /// The direction of the light.
     pub direction: Vec3,
@@ -32,6 +33,7 @@ impl Default for DirectionalLightData {
 /// Position is defined by the transform.
 /// Currently point lights do not render.
 /// TODO: Fix point lighting rendering.
+#[derive(Debug, Clone)]
 pub struct PointLightData {
     /// Color of the light.
     pub color: Vec3,
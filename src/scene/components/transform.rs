@@ -1,10 +1,6 @@
-use crate::{
-    graphics::resources::{BindGroup, GPUResourceManager},
-    Application, TransformCount,
-};
 use bytemuck::{Pod, Zeroable};
 use nalgebra_glm::{Mat4, Quat, Vec3};
-use std::{borrow::Cow, sync::Arc};
+use serde::{Deserialize, Serialize};
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -23,15 +19,16 @@ impl Default for LocalUniform {
 }
 
 /// A transform used to move entities around in the world. Consists of:
-/// index: Used internally
 /// position: A Vector3 representing it's world coordinates.
 /// scale: A Vector3 representing it's world scale.
 /// rotation: A quaternion representing it's world rotation.
 /// matrix: A world matrix.
-#[derive(Debug, PartialEq, Clone)]
+///
+/// Transforms are plain data, GPU buffers for the ones attached to meshes are allocated by the renderer.
+/// Only position, scale and rotation are serialized.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Transform {
-    /// Index of the transform used internally.
-    pub(crate) index: u32,
     /// Position of the transform.
     pub position: Vec3,
     /// Scale of the transform.
@@ -39,31 +36,33 @@ pub struct Transform {
     /// Rotation quaternion.
     pub rotation: Quat,
     /// Transformation matrix.
+    #[serde(skip)]
     pub matrix: Mat4,
      /* 
         Represents if this entity is culled or not.
         Automatically set by an internal system.
      */
+    #[serde(skip)]
     pub cull: bool,
+    /// Position, rotation and scale before the last fixed update. Used to interpolate between fixed updates.
+    #[serde(skip)]
+    pub(crate) previous: Option<(Vec3, Quat, Vec3)>,
 }
 
-impl Transform {
-    /// Creates a new transform with default values.
-    pub fn new(app: &mut Application) -> Self {
-        let mut index = app.resources.get_mut::<TransformCount>().unwrap();
-        index.0 += 1;
-        Self::create_bindings(app, index.0);
-
+impl Default for Transform {
+    fn default() -> Self {
         Self {
-            index: index.0,
             position: Vec3::new(0.0, 0.0, 0.0),
             scale: Vec3::new(1.0, 1.0, 1.0),
             rotation: Quat::identity(),
             matrix: Mat4::identity(),
             cull: false,
+            previous: None,
         }
     }
+}
 
+impl Transform {
     /// Lets you rotate the transform along a specific axis.
     pub fn rotate_on_axis<'a>(&'a mut self, axis: Vec3, angle: f32) -> &'a mut Self {
         self.rotation = self.rotation * nalgebra_glm::quat_angle_axis(angle, &axis);
@@ -109,30 +108,63 @@ impl Transform {
         self.matrix = translation * rotation * scale;
     }
 
-    pub(crate) fn create_bindings(app: &Application, index: u32) {
-        let resource_manager = app.resources.get::<Arc<GPUResourceManager>>().unwrap();
-        let bind_group_layout = resource_manager.get_bind_group_layout("locals").unwrap();
-        // This data needs to be saved and passed onto the pipeline.
-        let device = app.resources.get_mut::<Arc<wgpu::Device>>().unwrap();
-        let local_buffer = device.create_buffer_with_data(
-            bytemuck::bytes_of(&LocalUniform::default()),
-            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-        );
-
-        let local_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: Cow::Borrowed(&[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(local_buffer.slice(..)),
-            }]),
-            label: None,
-        });
-
-        resource_manager.add_multi_bind_group(
-            "transform",
-            BindGroup::new(0, local_bind_group),
-            index,
-        );
-        resource_manager.add_multi_buffer("transform", local_buffer, index);
+    /// Returns a world matrix blended between the state before the last fixed update and the current state.
+    /// An alpha of 0.0 is the previous state and 1.0 is the current state.
+    pub fn interpolated_matrix(&self, alpha: f32) -> Mat4 {
+        let (previous_position, previous_rotation, previous_scale) = match self.previous {
+            Some(previous) => previous,
+            None => return self.matrix,
+        };
+
+        let position = nalgebra_glm::lerp(&previous_position, &self.position, alpha);
+        let rotation = nalgebra_glm::quat_slerp(&previous_rotation, &self.rotation, alpha);
+        let scale = nalgebra_glm::lerp(&previous_scale, &self.scale, alpha);
+
+        nalgebra_glm::translation(&position)
+            * nalgebra_glm::quat_to_mat4(&rotation)
+            * nalgebra_glm::scaling(&scale)
+    }
+
+    /// Saves the current position, rotation and scale. Called internally before every fixed update.
+    pub(crate) fn store_previous(&mut self) {
+        self.previous = Some((self.position, self.rotation, self.scale));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transform;
+    use nalgebra_glm::Vec3;
+
+    #[test]
+    fn should_interpolate_between_fixed_updates() {
+        let mut transform = Transform::default();
+        transform.update();
+        assert_eq!(transform.interpolated_matrix(0.5), transform.matrix);
+
+        transform.store_previous();
+        transform.position = Vec3::new(2.0, 0.0, 0.0);
+        transform.update();
+
+        let halfway = transform.interpolated_matrix(0.5);
+        assert_eq!(halfway.column(3).x, 1.0);
+        assert_eq!(transform.interpolated_matrix(1.0), transform.matrix);
+    }
+
+    #[test]
+    fn should_deserialize_transform() {
+        let mut transform = Transform::default();
+        transform.position = Vec3::new(1.0, 2.0, 3.0);
+        transform.rotate_on_y(1.0);
+        transform.update();
+
+        let serialized = ron::ser::to_string(&transform).unwrap();
+        let mut deserialized: Transform = ron::de::from_str(&serialized).unwrap();
+        deserialized.update();
+        assert_eq!(deserialized, transform);
+
+        let partial: Transform = ron::de::from_str("(scale: [2.0, 2.0, 2.0])").unwrap();
+        assert_eq!(partial.position, Vec3::zeros());
+        assert_eq!(partial.scale, Vec3::new(2.0, 2.0, 2.0));
     }
 }
@@ -22,7 +22,7 @@ pub fn create(
     };
     let probe_component = components::Probe { id: probe_id };
 
-    let mut transform = components::Transform::new(app);
+    let mut transform = components::Transform::default();
     transform.position = position;
 
     app.current_scene
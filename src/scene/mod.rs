@@ -5,3 +5,6 @@ pub mod systems;
 
 mod scene;
 pub use scene::Scene;
+
+mod runner;
+pub use runner::SceneRunner;
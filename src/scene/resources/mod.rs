@@ -1,2 +1,7 @@
 #[derive(Default)]
 pub struct DeltaTime(pub f32);
+
+/// How far the current frame is between the previous and the latest fixed update, from 0.0 to 1.0.
+/// Render systems use this to blend between the previous and current `Transform`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InterpolationAlpha(pub f32);
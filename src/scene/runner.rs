@@ -0,0 +1,190 @@
+use super::{
+    resources::{DeltaTime, InterpolationAlpha},
+    Scene,
+};
+use crate::{
+    core::{
+        input::{Input, InputChange, InputRecording},
+        FixedTimestep, PerformanceMetrics,
+    },
+    AppConfig,
+};
+use legion::prelude::*;
+
+/// Steps a `Scene` without a window, renderer or GPU, ex: to test game systems on CI.
+///
+/// The runner owns the resources game systems normally get from `Application`: `DeltaTime`, `Input`,
+/// `InterpolationAlpha` and `PerformanceMetrics`. Anything else the systems need can be added with
+/// `insert_resource`.
+pub struct SceneRunner {
+    /// The scene being stepped.
+    pub scene: Scene,
+    /// Resources passed to the scene's systems.
+    pub resources: Resources,
+    // Splits recorded frames into fixed updates like `Application` does.
+    fixed_timestep: FixedTimestep,
+}
+
+impl SceneRunner {
+    /// Creates a runner for a scene.
+    pub fn new(scene: Scene) -> Self {
+        let mut resources = Resources::default();
+        resources.insert(DeltaTime::default());
+        resources.insert(InterpolationAlpha(1.0));
+        resources.insert(Input::new());
+        resources.insert(PerformanceMetrics::new());
+
+        let config = AppConfig::default();
+        Self {
+            scene,
+            resources,
+            fixed_timestep: FixedTimestep::new(config.fixed_timestep, config.max_substeps),
+        }
+    }
+
+    /// Changes the time in seconds between scene updates during `play_recording`,
+    /// use the same value as the application the recording was made with.
+    pub fn set_fixed_timestep(&mut self, fixed_timestep: f32) {
+        self.fixed_timestep.set_timestep(fixed_timestep);
+    }
+
+    /// Adds or replaces a resource the scene's systems can read.
+    pub fn insert_resource<T: Resource>(&mut self, resource: T) {
+        self.resources.insert(resource);
+    }
+
+    /// Applies a change to `Input` before the next step, ex: `InputChange::Key(VirtualKeyCode::W, true)`.
+    pub fn apply_input(&mut self, change: InputChange) {
+        self.resources.get_mut::<Input>().unwrap().apply(change);
+    }
+
+    /// Runs the scene's game schedule once with the given delta time.
+    /// Keys pressed or released before this step only count as pressed or released during this step.
+    pub fn step(&mut self, delta_time: f32) {
+        self.scene.update(delta_time, &mut self.resources);
+        self.resources.get_mut::<Input>().unwrap().clear();
+    }
+
+    /// Runs the scene's game schedule `steps` times with the same delta time.
+    pub fn step_n(&mut self, steps: usize, delta_time: f32) {
+        for _ in 0..steps {
+            self.step(delta_time);
+        }
+    }
+
+    /// Plays back a recording the same way `Application::play_recording` does. Every recorded frame's time is
+    /// split into fixed updates and the scene is stepped once per fixed update with the fixed timestep.
+    pub fn play_recording(&mut self, recording: &InputRecording) {
+        self.fixed_timestep.reset();
+        self.resources.get_mut::<Input>().unwrap().reset();
+
+        let fixed_timestep = self.fixed_timestep.timestep();
+        for frame in recording.frames.iter() {
+            for change in frame.changes.iter() {
+                self.apply_input(*change);
+            }
+
+            self.resources.get_mut::<Input>().unwrap().begin_fixed_update();
+            for _ in 0..self.fixed_timestep.advance(frame.frame_time) {
+                self.scene.update(fixed_timestep, &mut self.resources);
+                self.resources.get_mut::<Input>().unwrap().end_fixed_update();
+            }
+            self.resources
+                .insert(InterpolationAlpha(self.fixed_timestep.alpha()));
+
+            // There are no per frame updates, the frame's edges are dropped.
+            let mut input = self.resources.get_mut::<Input>().unwrap();
+            input.begin_frame_update();
+            input.end_frame_update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SceneRunner;
+    use crate::core::{
+        input::{Input, InputChange, InputRecording, RecordedFrame},
+        Stage, StagedSchedule, SystemDesc,
+    };
+    use crate::scene::{components::Transform, resources::DeltaTime, Scene};
+    use legion::prelude::*;
+    use winit::event::VirtualKeyCode;
+
+    // Moves every transform along X while W is held.
+    fn create_movement() -> Box<dyn Schedulable> {
+        SystemBuilder::new("movement")
+            .read_resource::<DeltaTime>()
+            .read_resource::<Input>()
+            .with_query(<Write<Transform>>::query())
+            .build(|_, mut world, (delta_time, input), query| {
+                if !input.is_key_down(VirtualKeyCode::W) {
+                    return;
+                }
+                for mut transform in query.iter_mut(&mut world) {
+                    transform.position.x += 10.0 * delta_time.0;
+                }
+            })
+    }
+
+    fn create_runner() -> SceneRunner {
+        let mut schedule = StagedSchedule::game();
+        schedule.add_system(Stage::Update, SystemDesc::new(create_movement()));
+        let mut runner = SceneRunner::new(Scene::new(None, Some(schedule)));
+        runner
+            .scene
+            .world
+            .insert((), vec![(Transform::default(),)]);
+        runner
+    }
+
+    fn position_x(runner: &SceneRunner) -> f32 {
+        let query = <Read<Transform>>::query();
+        query.iter(&runner.scene.world).next().unwrap().position.x
+    }
+
+    #[test]
+    fn should_step_scene_without_gpu() {
+        let mut runner = create_runner();
+        runner.step(0.5);
+        assert_eq!(position_x(&runner), 0.0);
+
+        runner.apply_input(InputChange::Key(VirtualKeyCode::W, true));
+        runner.step_n(2, 0.5);
+        assert_eq!(position_x(&runner), 10.0);
+        assert!(!runner.resources.get::<Input>().unwrap().is_key_pressed(VirtualKeyCode::W));
+
+        runner.apply_input(InputChange::Key(VirtualKeyCode::W, false));
+        runner.step(0.5);
+        assert_eq!(position_x(&runner), 10.0);
+    }
+
+    #[test]
+    fn should_play_recording() {
+        let mut runner = create_runner();
+        runner.set_fixed_timestep(0.125);
+        runner.play_recording(&InputRecording {
+            frames: vec![
+                // Frames shorter than the fixed timestep don't step the scene.
+                RecordedFrame {
+                    frame_time: 0.0625,
+                    changes: vec![InputChange::Key(VirtualKeyCode::W, true)],
+                },
+                RecordedFrame {
+                    frame_time: 0.0625,
+                    changes: vec![],
+                },
+                RecordedFrame {
+                    frame_time: 0.25,
+                    changes: vec![],
+                },
+                RecordedFrame {
+                    frame_time: 0.25,
+                    changes: vec![InputChange::Key(VirtualKeyCode::W, false)],
+                },
+            ],
+        });
+        // One fixed update after the first two frames and two after the third, each moving 1.25.
+        assert_eq!(position_x(&runner), 3.75);
+    }
+}
@@ -1,6 +1,6 @@
-use super::resources;
+use super::{components::Transform, resources};
+use crate::core::{Stage, StagedSchedule, SystemDesc};
 use legion::prelude::*;
-use legion::systems::schedule::Builder;
 
 /// A representation of our scene.
 pub struct Scene {
@@ -14,15 +14,19 @@ pub struct Scene {
 
 impl Scene {
     /// Allows you to create a new scene with an optional world and optional schedule(which contains systems).
-    /// If None is passed in for world or schedule_builder a default one is created.
-    pub fn new(world: Option<World>, schedule_builder: Option<Builder>) -> Self {
+    /// If None is passed in for world or schedule a default one is created.
+    /// Harmony's `culling` system is added to `Stage::PostUpdate`.
+    pub fn new(world: Option<World>, schedule: Option<StagedSchedule>) -> Self {
         let universe = Universe::new();
         let world = world.unwrap_or(universe.create_world());
 
         // Add our systems here..
-        let game_schedule_builder = schedule_builder.unwrap_or(Schedule::builder())
-            .add_system(super::systems::culling::create());
-        let game_schedule = game_schedule_builder.build();
+        let mut game_schedule = schedule.unwrap_or_else(StagedSchedule::game);
+        game_schedule.add_system(
+            Stage::PostUpdate,
+            SystemDesc::new(super::systems::culling::create()).label("culling"),
+        );
+        let game_schedule = game_schedule.build();
 
         Scene {
             world,
@@ -37,6 +41,12 @@ impl Scene {
             *delta = resources::DeltaTime(delta_time);
         }
 
+        // Keep the state from before this update around so rendering can interpolate.
+        let query = <(Write<Transform>,)>::query();
+        for (mut transform,) in query.iter_mut(&mut self.world) {
+            transform.store_previous();
+        }
+
         self.game_schedule.execute(&mut self.world, resources);
     }
 }
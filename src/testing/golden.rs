@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+/// Set this environment variable to overwrite the stored reference images with the newly rendered ones.
+pub const BLESS_ENV_VAR: &str = "HARMONY_BLESS_GOLDEN";
+
+/// The result of comparing a rendered image against a reference image.
+pub struct ImageComparison {
+    /// Number of pixels where at least one channel differs by more than the tolerance.
+    pub mismatched_pixels: u32,
+    /// The largest difference found in any channel.
+    pub max_difference: u8,
+    /// A visualization of the differences. Mismatched pixels are red, everything else is a faded copy of the reference.
+    pub diff: image::RgbaImage,
+}
+
+impl ImageComparison {
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    // Thrown when the rendered image and reference don't have the same dimensions.
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    // Thrown when more pixels than allowed differ from the reference.
+    Mismatch {
+        mismatched_pixels: u32,
+        max_difference: u8,
+        actual_path: PathBuf,
+        diff_path: PathBuf,
+    },
+    // Thrown when there's no reference image and `HARMONY_BLESS_GOLDEN` isn't set.
+    MissingReference(PathBuf),
+    // Thrown when reading or writing an image fails.
+    Image(image::ImageError),
+}
+
+impl std::fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GoldenError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Rendered image is {}x{} but the reference is {}x{}.",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            GoldenError::Mismatch {
+                mismatched_pixels,
+                max_difference,
+                actual_path,
+                diff_path,
+            } => write!(
+                f,
+                "{} pixels differ from the reference(max difference: {}). Rendered image: {}, diff: {}",
+                mismatched_pixels,
+                max_difference,
+                actual_path.display(),
+                diff_path.display()
+            ),
+            GoldenError::MissingReference(path) => write!(
+                f,
+                "Reference image {} doesn't exist, run the test with {}=1 to create it.",
+                path.display(),
+                BLESS_ENV_VAR
+            ),
+            GoldenError::Image(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+impl From<image::ImageError> for GoldenError {
+    fn from(error: image::ImageError) -> Self {
+        GoldenError::Image(error)
+    }
+}
+
+/// Compares two images channel by channel.
+/// A pixel is considered a mismatch if any of its channels differ by more than `tolerance`.
+/// Returns `None` if the images don't have the same dimensions.
+pub fn compare_images(
+    actual: &image::RgbaImage,
+    expected: &image::RgbaImage,
+    tolerance: u8,
+) -> Option<ImageComparison> {
+    if actual.dimensions() != expected.dimensions() {
+        return None;
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    let diff = image::RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let actual_pixel = actual.get_pixel(x, y);
+        let expected_pixel = expected.get_pixel(x, y);
+        let difference = (0..4)
+            .map(|i| (actual_pixel[i] as i16 - expected_pixel[i] as i16).abs() as u8)
+            .max()
+            .unwrap();
+        max_difference = max_difference.max(difference);
+
+        if difference > tolerance {
+            mismatched_pixels += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (expected_pixel[0] as u32 + expected_pixel[1] as u32 + expected_pixel[2] as u32) / 3;
+            let faded = (luma / 4) as u8;
+            image::Rgba([faded, faded, faded, 255])
+        }
+    });
+
+    Some(ImageComparison {
+        mismatched_pixels,
+        max_difference,
+        diff,
+    })
+}
+
+/// Compares a rendered image against the reference image stored at `reference_path`.
+/// When `HARMONY_BLESS_GOLDEN` is set the rendered image is saved as the new reference instead,
+/// otherwise a missing reference is an error.
+/// On failure the rendered image and a diff image are written next to the reference as `<name>.actual.png`
+/// and `<name>.diff.png`.
+pub fn assert_golden<P: AsRef<Path>>(
+    reference_path: P,
+    actual: &image::RgbaImage,
+    tolerance: u8,
+) -> Result<(), GoldenError> {
+    let reference_path = reference_path.as_ref();
+    if std::env::var_os(BLESS_ENV_VAR).is_some() {
+        if let Some(parent) = reference_path.parent() {
+            std::fs::create_dir_all(parent).map_err(image::ImageError::IoError)?;
+        }
+        log::warn!("Saving new reference image: {}", reference_path.display());
+        actual.save(reference_path)?;
+        return Ok(());
+    }
+    if !reference_path.exists() {
+        return Err(GoldenError::MissingReference(reference_path.to_path_buf()));
+    }
+
+    let expected = image::open(reference_path)?.to_rgba();
+    let comparison = match compare_images(actual, &expected, tolerance) {
+        Some(comparison) => comparison,
+        None => {
+            return Err(GoldenError::SizeMismatch {
+                expected: expected.dimensions(),
+                actual: actual.dimensions(),
+            })
+        }
+    };
+
+    if comparison.is_match() {
+        return Ok(());
+    }
+
+    let actual_path = reference_path.with_extension("actual.png");
+    let diff_path = reference_path.with_extension("diff.png");
+    actual.save(&actual_path)?;
+    comparison.diff.save(&diff_path)?;
+
+    Err(GoldenError::Mismatch {
+        mismatched_pixels: comparison.mismatched_pixels,
+        max_difference: comparison.max_difference,
+        actual_path,
+        diff_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_golden, compare_images, GoldenError, BLESS_ENV_VAR};
+
+    #[test]
+    fn should_match_within_tolerance() {
+        let expected = image::RgbaImage::from_pixel(4, 4, image::Rgba([100, 100, 100, 255]));
+        let actual = image::RgbaImage::from_pixel(4, 4, image::Rgba([102, 99, 100, 255]));
+        let comparison = compare_images(&actual, &expected, 2).unwrap();
+        assert!(comparison.is_match());
+        assert_eq!(comparison.max_difference, 2);
+    }
+
+    #[test]
+    fn should_mark_mismatched_pixels() {
+        let expected = image::RgbaImage::from_pixel(4, 4, image::Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 2, image::Rgba([200, 100, 100, 255]));
+        let comparison = compare_images(&actual, &expected, 2).unwrap();
+        assert_eq!(comparison.mismatched_pixels, 1);
+        assert_eq!(comparison.max_difference, 100);
+        assert_eq!(comparison.diff.get_pixel(1, 2).0, [255, 0, 0, 255]);
+        assert_ne!(comparison.diff.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn should_fail_without_reference() {
+        if std::env::var_os(BLESS_ENV_VAR).is_some() {
+            return;
+        }
+        let path = std::env::temp_dir().join("harmony_missing_reference.png");
+        let actual = image::RgbaImage::new(4, 4);
+        assert!(match assert_golden(&path, &actual, 0) {
+            Err(GoldenError::MissingReference(missing)) => missing == path,
+            _ => false,
+        });
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn should_reject_different_sizes() {
+        let expected = image::RgbaImage::new(4, 4);
+        let actual = image::RgbaImage::new(2, 4);
+        assert!(compare_images(&actual, &expected, 0).is_none());
+    }
+}
@@ -0,0 +1,11 @@
+mod golden;
+pub use golden::{assert_golden, compare_images, GoldenError, ImageComparison, BLESS_ENV_VAR};
+
+mod scene_description;
+
+// Imgui only allows a single context at a time, tests creating an `Application` hold this lock.
+#[cfg(test)]
+pub(crate) static APPLICATION_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+pub use scene_description::{
+    CameraDescription, LightDescription, MeshDescription, SceneDescription, SkyboxDescription,
+};
@@ -0,0 +1,380 @@
+use nalgebra_glm::Vec3;
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    assets::{material::PBRMaterialRon, mesh::Gltf, AssetError, AssetHandle},
+    graphics::{
+        material::Skybox,
+        resources::{ProbeFormat, ProbeQuality},
+    },
+    scene::components::{
+        CameraData, DirectionalLightData, LightType, Mesh, PointLightData, Transform,
+    },
+    AppConfig, AppState, Application, AssetManager,
+};
+
+// How long we wait for meshes and materials to finish loading before giving up.
+const LOADING_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A mesh placed in the scene.
+#[derive(Debug, Clone)]
+pub struct MeshDescription {
+    /// Path to a gltf file relative to the asset path.
+    pub path: PathBuf,
+    pub position: Vec3,
+    /// Rotation in radians applied on the x, y then z axis.
+    pub rotation: Vec3,
+    pub scale: Vec3,
+}
+
+impl MeshDescription {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            position: Vec3::zeros(),
+            rotation: Vec3::zeros(),
+            scale: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+/// A light placed in the scene.
+#[derive(Debug, Clone)]
+pub enum LightDescription {
+    Directional {
+        direction: Vec3,
+        color: Vec3,
+        intensity: f32,
+    },
+    Point {
+        position: Vec3,
+        color: Vec3,
+        attenuation: f32,
+        intensity: f32,
+        shadow: bool,
+    },
+}
+
+/// The skybox used by the scene.
+#[derive(Debug, Clone)]
+pub enum SkyboxDescription {
+    ClearColor(Vec3),
+    /// Path to a HDR texture relative to the asset path and the size of the cube map.
+    Hdr(String, f32),
+    Realtime,
+}
+
+/// A perspective camera looking from `position` at `target`.
+#[derive(Debug, Clone)]
+pub struct CameraDescription {
+    pub fov: f32,
+    pub position: Vec3,
+    pub target: Vec3,
+    pub z_near: f32,
+    pub z_far: f32,
+}
+
+impl Default for CameraDescription {
+    fn default() -> Self {
+        Self {
+            fov: 70.0,
+            position: Vec3::new(0.0, 0.0, 5.0),
+            target: Vec3::zeros(),
+            z_near: 1.0,
+            z_far: 4000.0,
+        }
+    }
+}
+
+/// Describes a scene that can be rendered headlessly, used for golden image tests.
+#[derive(Debug, Clone)]
+pub struct SceneDescription {
+    pub width: u32,
+    pub height: u32,
+    pub meshes: Vec<MeshDescription>,
+    pub lights: Vec<LightDescription>,
+    pub skybox: SkyboxDescription,
+    pub camera: CameraDescription,
+    /// Frames rendered after everything has loaded before the image is captured.
+    /// Gives probes and shadow maps time to update.
+    pub settle_frames: u32,
+}
+
+impl Default for SceneDescription {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+            meshes: Vec::new(),
+            lights: Vec::new(),
+            skybox: SkyboxDescription::ClearColor(Vec3::new(0.2, 0.2, 0.2)),
+            camera: CameraDescription::default(),
+            settle_frames: 2,
+        }
+    }
+}
+
+impl SceneDescription {
+    /// Builds the scene in a headless application, renders it through the normal render schedule and
+    /// returns the final frame.
+    /// Returns `None` if the application couldn't be created, the assets didn't load in time or the frame
+    /// couldn't be read back.
+    pub fn render<T: Into<String>>(&self, asset_path: T) -> Option<image::RgbaImage> {
+        let application = Application::new_headless(
+            winit::dpi::PhysicalSize::new(self.width, self.height),
+            asset_path.into(),
+            vec![],
+            AppConfig::default(),
+        );
+        let mut application = match application {
+            Ok(application) => application,
+            Err(error) => {
+                log::error!("{}", error);
+                return None;
+            }
+        };
+        let mut scene_state = SceneState {
+            description: self.clone(),
+            meshes: Vec::new(),
+        };
+        application.load(&mut scene_state);
+
+        let start = Instant::now();
+        while !scene_state.is_loaded(&application) {
+            if start.elapsed() > LOADING_TIMEOUT {
+                log::error!("Timed out waiting for the scene's assets to load.");
+                return None;
+            }
+            application.run_headless(&mut scene_state);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        for _ in 0..=self.settle_frames {
+            application.run_headless(&mut scene_state);
+        }
+
+        application.capture_frame()
+    }
+}
+
+struct SceneState {
+    description: SceneDescription,
+    meshes: Vec<Arc<AssetHandle<Gltf>>>,
+}
+
+impl SceneState {
+    // True once no mesh or material is still loading. Assets that failed to load count as loaded.
+    fn is_loaded(&self, app: &Application) -> bool {
+        let is_loading = |error: &Arc<AssetError>| match **error {
+            AssetError::Loading => true,
+            _ => false,
+        };
+
+        let meshes_loaded = self
+            .meshes
+            .iter()
+            .all(|mesh| mesh.get().err().map_or(true, |error| !is_loading(&error)));
+        if !meshes_loaded {
+            return false;
+        }
+
+        let asset_manager = app.resources.get::<AssetManager>().unwrap();
+        asset_manager
+            .get_all_materials::<PBRMaterialRon>()
+            .iter()
+            .all(|material| material.get().err().map_or(true, |error| !is_loading(&error)))
+    }
+}
+
+impl AppState for SceneState {
+    fn load(&mut self, app: &mut Application) {
+        let description = self.description.clone();
+
+        for mesh in description.meshes.iter() {
+            let mesh_handle = {
+                let asset_manager = app.resources.get_mut::<AssetManager>().unwrap();
+                asset_manager.get_mesh(mesh.path.clone())
+            };
+            self.meshes.push(mesh_handle.clone());
+
+            let mut transform = Transform::default();
+            transform.position = mesh.position;
+            transform.scale = mesh.scale;
+            transform.rotate_on_x(mesh.rotation.x);
+            transform.rotate_on_y(mesh.rotation.y);
+            transform.rotate_on_z(mesh.rotation.z);
+            app.current_scene
+                .world
+                .insert((), vec![(Mesh::new(mesh_handle), transform)]);
+        }
+
+        let skybox = match description.skybox {
+            SkyboxDescription::ClearColor(color) => Skybox::create_clear_color(color),
+            SkyboxDescription::Hdr(texture, size) => Skybox::new_hdr(app, texture, size),
+            SkyboxDescription::Realtime => Skybox::create_realtime(),
+        };
+        app.current_scene.world.insert((), vec![(skybox,)]);
+
+        // The PBR pipeline needs a probe.
+        if !description.meshes.is_empty() {
+            crate::scene::entities::probe::create(
+                app,
+                Vec3::zeros(),
+                ProbeQuality::Low,
+                ProbeFormat::RGBA16,
+            );
+        }
+
+        for light in description.lights.iter() {
+            let mut transform = Transform::default();
+            let light_type = match *light {
+                LightDescription::Directional {
+                    direction,
+                    color,
+                    intensity,
+                } => LightType::Directional(DirectionalLightData {
+                    direction,
+                    color,
+                    intensity,
+                }),
+                LightDescription::Point {
+                    position,
+                    color,
+                    attenuation,
+                    intensity,
+                    shadow,
+                } => {
+                    transform.position = position;
+                    LightType::Point(PointLightData::new(color, attenuation, intensity, shadow))
+                }
+            };
+            crate::scene::entities::light::create(
+                &mut app.current_scene.world,
+                light_type,
+                transform,
+            );
+        }
+
+        let camera = description.camera;
+        let mut camera_data = CameraData::new_perspective(
+            camera.fov,
+            description.width as f32,
+            description.height as f32,
+            camera.z_near,
+            camera.z_far,
+        );
+        camera_data.position = camera.position;
+        camera_data.update_view(camera.position, camera.target, Vec3::new(0.0, 1.0, 0.0));
+        crate::scene::entities::camera::create(&mut app.current_scene.world, camera_data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LightDescription, MeshDescription, SceneDescription};
+    use crate::testing::{assert_golden, APPLICATION_LOCK};
+    use nalgebra_glm::Vec3;
+
+    const ASSET_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/");
+    const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/");
+    const TOLERANCE: u8 = 8;
+
+    fn create_cube() -> MeshDescription {
+        let mut cube = MeshDescription::new("example/meshes/cube/cube.gltf");
+        cube.rotation = Vec3::new(0.5, 0.5, 0.0);
+        cube
+    }
+
+    fn create_floor() -> MeshDescription {
+        let mut floor = MeshDescription::new("example/meshes/cube/cube.gltf");
+        floor.position = Vec3::new(0.0, -2.0, 0.0);
+        floor.scale = Vec3::new(10.0, 0.1, 10.0);
+        floor
+    }
+
+    // The golden images can only be rendered with a GPU, the test is skipped on machines without an adapter.
+    fn has_adapter() -> bool {
+        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+        let adapter = async_std::task::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::Default,
+            compatible_surface: None,
+        }));
+        adapter.is_some()
+    }
+
+    // Imgui only allows a single context at a time so the scenes are rendered one after another in a single test.
+    #[test]
+    fn should_match_golden_images() {
+        if !has_adapter() {
+            eprintln!("Skipping should_match_golden_images, no GPU adapter is available.");
+            return;
+        }
+        let _lock = APPLICATION_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+
+        let pbr = SceneDescription {
+            meshes: vec![create_cube()],
+            lights: vec![LightDescription::Directional {
+                direction: Vec3::new(0.0, 1.0, 0.0),
+                color: Vec3::new(1.0, 1.0, 1.0),
+                intensity: 10.0,
+            }],
+            ..Default::default()
+        };
+
+        let shadows = SceneDescription {
+            meshes: vec![create_cube(), create_floor()],
+            lights: vec![LightDescription::Point {
+                position: Vec3::new(0.0, 3.0, 0.0),
+                color: Vec3::new(1.0, 1.0, 1.0),
+                attenuation: 20.0,
+                intensity: 20.0,
+                shadow: true,
+            }],
+            settle_frames: 4,
+            ..Default::default()
+        };
+
+        let mut clustered_lighting = SceneDescription {
+            meshes: vec![create_cube(), create_floor()],
+            ..Default::default()
+        };
+        let colors = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 1.0, 0.0),
+        ];
+        for (i, color) in colors.iter().enumerate() {
+            let angle = i as f32 * std::f32::consts::FRAC_PI_2;
+            clustered_lighting.lights.push(LightDescription::Point {
+                position: Vec3::new(angle.cos() * 2.0, 0.0, angle.sin() * 2.0),
+                color: *color,
+                attenuation: 5.0,
+                intensity: 10.0,
+                shadow: false,
+            });
+        }
+
+        let scenes = vec![
+            ("pbr", pbr),
+            ("shadows", shadows),
+            ("clustered_lighting", clustered_lighting),
+        ];
+
+        let mut failures = Vec::new();
+        for (name, scene) in scenes {
+            let image = scene.render(ASSET_PATH);
+            assert!(image.is_some(), "Couldn't render scene: {}", name);
+            let reference_path = format!("{}{}.png", GOLDEN_PATH, name);
+            if let Err(error) = assert_golden(reference_path, &image.unwrap(), TOLERANCE) {
+                failures.push(format!("{}: {}", name, error));
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}